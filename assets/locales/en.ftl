@@ -0,0 +1,168 @@
+# English, also used for any text missing in another language.
+-language-name = English
+
+## main menu
+menu-title = TETRIS
+menu-continue = Continue
+menu-mode = Mode: { $mode }
+menu-puzzles = Puzzles
+menu-easy = Easy
+menu-normal = Normal
+menu-hard = Hard
+menu-versus = Versus
+menu-vs-cpu = VS CPU
+menu-online = Online
+menu-watch-ai = Watch AI
+menu-settings = Settings
+menu-how-to-play = How To Play
+menu-quit = Quit
+menu-back = Back
+
+mode-marathon = Marathon
+mode-four-wide = 4-Wide
+mode-wide = 12x24
+mode-huge = 20x40
+mode-cheese-10 = Cheese 10L
+mode-cheese-18 = Cheese 18L
+mode-cheese-100 = Cheese 100L
+mode-finesse = Finesse
+mode-invisible = Invisible
+mode-fading = Fading
+mode-big = Big
+mode-zen = Zen
+
+## how to play
+help-title = CONTROLS
+help-controls =
+    Left : move left
+    Right : move right
+    Up : rotate
+    Down : soft drop
+    Space : hard drop
+    H : show hints
+    C / V : copy / paste board
+    F5 : save game
+    Esc : pause game
+    Enter : end a zen session
+
+## puzzles
+puzzles-title = PUZZLES
+puzzles-solved = { $goal }  SOLVED
+goal-clear-all = CLEAR ALL
+goal-one-line = CLEAR 1 LINE
+goal-lines = CLEAR { $lines } LINES
+goal-tspin = T-SPIN
+
+## settings
+settings-title = SETTINGS
+settings-theme = Theme
+settings-language = Language
+settings-palette = Palette
+settings-patterns = Patterns
+settings-bright-ghost = Bright Ghost
+settings-on = On
+settings-off = Off
+palette-normal = Normal
+palette-deuteranopia = Deutan
+palette-protanopia = Protan
+palette-tritanopia = Tritan
+
+## game
+game-score = SCORE
+game-level = LEVEL
+game-lines = LINES
+game-next = NEXT
+
+hud-time = TIME  { $time }
+hud-pps = PPS  { $pps }
+hud-apm = APM  { $apm }
+hud-combo = COMBO  { $combo }
+hud-back-to-back = B2B  { $state }
+hud-on = ON
+hud-faults = FAULTS  { $faults }
+hud-garbage = GARBAGE  { $garbage }
+hud-targets = TARGETS  { $hit }/{ $placed }
+hud-pieces = PIECES  { $pieces }
+hud-resets = RESETS  { $resets }
+hud-end-session = ENTER: END
+hud-finesse-fault =
+    FINESSE FAULT
+    { $keys } KEYS, { $needed } NEEDED
+
+action-single = SINGLE
+action-double = DOUBLE
+action-triple = TRIPLE
+action-tetris = TETRIS
+action-tspin = T-SPIN { $clear }
+action-tspin-zero = T-SPIN
+action-back-to-back = B2B { $clear }
+action-combo = { $combo } COMBO
+action-perfect-clear = PERFECT CLEAR
+
+## game over
+gameover-complete = COMPLETE
+gameover-title = GAME OVER
+gameover-zen = SESSION OVER
+gameover-resets = Board resets : { $resets }
+gameover-score = Score : { $score }   Level : { $level }   Lines : { $lines }
+gameover-main-menu = Main Menu
+gameover-watch-replay = Watch Replay
+topout-block = Block Out
+topout-lock = Lock Out
+topout-garbage = Top Out
+topout-out-of-pieces = Out of Pieces
+
+stats-time = Time
+stats-pieces = Pieces
+stats-pps = PPS
+stats-kpp = KPP
+stats-apm = APM
+stats-singles = Singles
+stats-doubles = Doubles
+stats-triples = Triples
+stats-tetrises = Tetrises
+stats-tspins = T-Spins
+stats-max-combo = Max Combo
+stats-perfect-clears = Perfect Clears
+stats-finesse-faults = Finesse Faults
+stats-targets-hit = Targets Hit
+
+## versus
+versus-player = PLAYER { $number }
+versus-you = YOU
+versus-cpu = CPU
+versus-opponent = OPPONENT
+versus-sent = SENT { $lines }
+versus-you-win = YOU WIN
+versus-you-lose = YOU LOSE
+versus-wins = { $player } WINS
+versus-draw = DRAW
+versus-rematch = Enter: Rematch   Esc: Menu
+versus-hints-wasd =
+    A D move   W rotate
+    S soft drop   Space hard drop
+versus-hints-arrows =
+    Arrows move and rotate
+    Down soft drop   Enter hard drop
+versus-hints-single =
+    Arrows move and rotate
+    Down soft drop   Space hard drop
+
+online-connecting =
+    Connecting to
+    { $address }
+online-waiting = Waiting for an opponent
+online-leave = Esc: Menu
+online-other-version =
+    The opponent runs
+    another version
+online-disconnected =
+    Disconnected
+    { $reason }
+
+## replays
+replay-controls = Space : play/pause   Up/Down : speed   Left/Right : seek   Esc : menu
+replay-end = END
+replay-paused = PAUSED
+replay-playing = PLAYING
+replay-status = REPLAY  { $state }  { $speed }x  { $time } / { $total }
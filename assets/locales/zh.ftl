@@ -0,0 +1,170 @@
+# 简体中文
+-language-name = 中文
+# the font has to cover the Chinese glyphs, see the README
+-font = fonts/NotoSansSC-Regular.ttf
+
+## main menu
+menu-title = 俄罗斯方块
+menu-continue = 继续游戏
+menu-mode = 模式：{ $mode }
+menu-puzzles = 谜题
+menu-easy = 简单
+menu-normal = 普通
+menu-hard = 困难
+menu-versus = 双人对战
+menu-vs-cpu = 人机对战
+menu-online = 联机对战
+menu-watch-ai = 观看 AI
+menu-settings = 设置
+menu-how-to-play = 操作说明
+menu-quit = 退出
+menu-back = 返回
+
+mode-marathon = 马拉松
+mode-four-wide = 四宽
+mode-wide = 12x24
+mode-huge = 20x40
+mode-cheese-10 = 挖掘 10 行
+mode-cheese-18 = 挖掘 18 行
+mode-cheese-100 = 挖掘 100 行
+mode-finesse = 极简操作
+mode-invisible = 隐形
+mode-fading = 渐隐
+mode-big = 大方块
+mode-zen = 禅
+
+## how to play
+help-title = 操作说明
+help-controls =
+    左 : 左移
+    右 : 右移
+    上 : 旋转
+    下 : 软降
+    空格 : 硬降
+    H : 显示提示
+    C / V : 复制 / 粘贴棋盘
+    F5 : 保存游戏
+    Esc : 暂停游戏
+    Enter : 结束禅模式
+
+## puzzles
+puzzles-title = 谜题
+puzzles-solved = { $goal }  已完成
+goal-clear-all = 全部消除
+goal-one-line = 消除 1 行
+goal-lines = 消除 { $lines } 行
+goal-tspin = T 旋
+
+## settings
+settings-title = 设置
+settings-theme = 主题
+settings-language = 语言
+settings-palette = 配色
+settings-patterns = 图案
+settings-bright-ghost = 高亮影子
+settings-on = 开
+settings-off = 关
+palette-normal = 普通
+palette-deuteranopia = 绿色盲
+palette-protanopia = 红色盲
+palette-tritanopia = 蓝色盲
+
+## game
+game-score = 分数
+game-level = 等级
+game-lines = 行数
+game-next = 下一个
+
+hud-time = 时间  { $time }
+hud-pps = 每秒块数  { $pps }
+hud-apm = 每分攻击  { $apm }
+hud-combo = 连击  { $combo }
+hud-back-to-back = B2B  { $state }
+hud-on = 开
+hud-faults = 失误  { $faults }
+hud-garbage = 垃圾行  { $garbage }
+hud-targets = 目标  { $hit }/{ $placed }
+hud-pieces = 剩余方块  { $pieces }
+hud-resets = 重置  { $resets }
+hud-end-session = Enter：结束
+hud-finesse-fault =
+    操作失误
+    按键 { $keys } 次，需要 { $needed } 次
+
+action-single = 单消
+action-double = 双消
+action-triple = 三消
+action-tetris = 四消
+action-tspin = T 旋{ $clear }
+action-tspin-zero = T 旋
+action-back-to-back = B2B { $clear }
+action-combo = { $combo } 连击
+action-perfect-clear = 全消
+
+## game over
+gameover-complete = 完成
+gameover-title = 游戏结束
+gameover-zen = 练习结束
+gameover-resets = 棋盘重置：{ $resets } 次
+gameover-score = 分数：{ $score }   等级：{ $level }   行数：{ $lines }
+gameover-main-menu = 主菜单
+gameover-watch-replay = 观看回放
+topout-block = 出生点被堵
+topout-lock = 锁定出界
+topout-garbage = 垃圾行顶出
+topout-out-of-pieces = 方块用尽
+
+stats-time = 时间
+stats-pieces = 方块
+stats-pps = 每秒块数
+stats-kpp = 每块按键
+stats-apm = 每分攻击
+stats-singles = 单消
+stats-doubles = 双消
+stats-triples = 三消
+stats-tetrises = 四消
+stats-tspins = T 旋
+stats-max-combo = 最高连击
+stats-perfect-clears = 全消
+stats-finesse-faults = 操作失误
+stats-targets-hit = 命中目标
+
+## versus
+versus-player = 玩家 { $number }
+versus-you = 你
+versus-cpu = 电脑
+versus-opponent = 对手
+versus-sent = 发送 { $lines }
+versus-you-win = 你赢了
+versus-you-lose = 你输了
+versus-wins = { $player } 获胜
+versus-draw = 平局
+versus-rematch = 回车：再来一局   Esc：菜单
+versus-hints-wasd =
+    A D 移动   W 旋转
+    S 软降   空格 硬降
+versus-hints-arrows =
+    方向键 移动和旋转
+    下 软降   回车 硬降
+versus-hints-single =
+    方向键 移动和旋转
+    下 软降   空格 硬降
+
+online-connecting =
+    正在连接
+    { $address }
+online-waiting = 等待对手
+online-leave = Esc：菜单
+online-other-version =
+    对手使用的是
+    另一个版本
+online-disconnected =
+    连接已断开
+    { $reason }
+
+## replays
+replay-controls = 空格：播放/暂停   上/下：速度   左/右：跳转   Esc：菜单
+replay-end = 结束
+replay-paused = 暂停
+replay-playing = 播放中
+replay-status = 回放  { $state }  { $speed }x  { $time } / { $total }
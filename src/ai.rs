@@ -0,0 +1,294 @@
+use crate::{
+    board::Board,
+    brick::Brick,
+    engine::{create_brick_start_position, ActiveBrick, GameEngine, InputAction, InputFrame},
+    position::Position,
+};
+
+// ticks between two actions of the AI
+pub const WATCH_AI_ACTION_TICKS: u32 = 4;
+pub const EASY_CPU_ACTION_TICKS: u32 = 12;
+pub const NORMAL_CPU_ACTION_TICKS: u32 = 6;
+pub const HARD_CPU_ACTION_TICKS: u32 = 2;
+// how many times the AI looks for another placement when its moves are blocked
+const MAX_REPLANS: usize = 3;
+
+/// How much each feature of a board counts in its evaluation, higher scores are better boards.
+/// The default weights are the ones tuned by Yiyuan Lee,
+/// https://codemyroad.wordpress.com/2013/04/14/tetris-ai-the-near-perfect-player/
+#[derive(Clone, Copy, Debug)]
+pub struct Weights {
+    // the sum of the heights of the columns
+    pub aggregate_height: f64,
+    // empty cells with a block above them in their column
+    pub holes: f64,
+    // the sum of the height differences of neighbouring columns
+    pub bumpiness: f64,
+    pub lines: f64,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights {
+            aggregate_height: -0.510066,
+            holes: -0.35663,
+            bumpiness: -0.184483,
+            lines: 0.760666,
+        }
+    }
+}
+
+impl Weights {
+    /// Scores a board once the lines of the placement were cleared.
+    pub fn evaluate(&self, board: &Board, lines: usize) -> f64 {
+        let heights = column_heights(board);
+        let aggregate_height: usize = heights.iter().sum();
+        let bumpiness: usize = heights
+            .windows(2)
+            .map(|pair| pair[0].abs_diff(pair[1]))
+            .sum();
+        self.aggregate_height * aggregate_height as f64
+            + self.holes * count_holes(board, &heights) as f64
+            + self.bumpiness * bumpiness as f64
+            + self.lines * lines as f64
+    }
+}
+
+/// The height of the highest block of every column.
+pub fn column_heights(board: &Board) -> Vec<usize> {
+    (0..board.width())
+        .map(|x| {
+            (0..board.height())
+                .rev()
+                .find(|y| board.is_position_occupied(&Position::new(x as i32, *y as i32)))
+                .map_or(0, |y| y + 1)
+        })
+        .collect()
+}
+
+/// The empty cells below the highest block of their column.
+pub fn count_holes(board: &Board, heights: &[usize]) -> usize {
+    heights
+        .iter()
+        .enumerate()
+        .map(|(x, height)| {
+            (0..*height)
+                .filter(|y| !board.is_position_occupied(&Position::new(x as i32, *y as i32)))
+                .count()
+        })
+        .sum()
+}
+
+/// The empty cells with a block above them in their column, i.e. the holes and the overhangs.
+pub fn covered_cells(board: &Board) -> Vec<Position> {
+    let heights = column_heights(board);
+    heights
+        .iter()
+        .enumerate()
+        .flat_map(|(x, height)| (0..*height).map(move |y| Position::new(x as i32, y as i32)))
+        .filter(|pos| !board.is_position_occupied(pos))
+        .collect()
+}
+
+/// Where a brick can be dropped: it is rotated, then shifted, then hard dropped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Placement {
+    // the rotated brick
+    pub brick: Brick,
+    // the final position of the brick
+    pub pos: Position,
+    pub rotations: usize,
+}
+
+impl Placement {
+    /// The board after the brick locked and the full lines were cleared, with the cleared lines.
+    pub fn apply(&self, board: &Board) -> (Board, usize) {
+        let mut board = board.clone();
+        board.occupy_brick(self.brick, self.pos);
+        let lines = board.clean_lines();
+        (board, lines)
+    }
+
+    // the brick locked completely inside the vanish zone, which ends the game
+    fn is_lock_out(&self, board: &Board) -> bool {
+        let visible_height = board.visible_height() as i32;
+        self.brick
+            .cells(self.pos, board.block_size())
+            .all(|cell| cell.y >= visible_height)
+    }
+}
+
+/// Lists the placements of a brick falling from `pos`,
+/// with the same moves and rotations as the engine.
+pub fn find_placements(board: &Board, brick: Brick, pos: Position) -> Vec<Placement> {
+    let mut placements: Vec<Placement> = Vec::new();
+    if !board.is_valid_brick(&brick, &pos) {
+        return placements;
+    }
+    let mut rotated = brick;
+    let mut rotated_pos = pos;
+    for rotations in 0..4 {
+        if rotations > 0 {
+            let next = rotated.rotate_right();
+            if !board.is_valid_brick_for_rotation(&next, &mut rotated_pos) {
+                break;
+            }
+            rotated = next;
+        }
+        // the O brick and the symmetric rotations give the same placements
+        if placements
+            .iter()
+            .any(|placement| placement.brick == rotated)
+        {
+            continue;
+        }
+        for step in [-1, 1] {
+            let mut shifted = rotated_pos;
+            // the unshifted column is only listed once
+            if step > 0 {
+                shifted.x += step;
+            }
+            while board.is_valid_brick(&rotated, &shifted) {
+                placements.push(Placement {
+                    brick: rotated,
+                    pos: board.get_bottom_valid_brick_pos(&rotated, &shifted),
+                    rotations,
+                });
+                shifted.x += step;
+            }
+        }
+    }
+    placements
+}
+
+/// Picks the best placement of `brick`, looking one brick ahead when the next one is given.
+pub fn best_placement(
+    board: &Board,
+    brick: Brick,
+    pos: Position,
+    next: Option<Brick>,
+    weights: &Weights,
+) -> Option<Placement> {
+    find_placements(board, brick, pos)
+        .into_iter()
+        .filter(|placement| !placement.is_lock_out(board))
+        .map(|placement| {
+            let (board, lines) = placement.apply(board);
+            let score = match next {
+                Some(next) => {
+                    let next_pos = create_brick_start_position(&board, &next.0);
+                    find_placements(&board, next, next_pos)
+                        .into_iter()
+                        .filter(|next_placement| !next_placement.is_lock_out(&board))
+                        .map(|next_placement| {
+                            let (next_board, next_lines) = next_placement.apply(&board);
+                            weights.evaluate(&next_board, next_lines) + weights.lines * lines as f64
+                        })
+                        .fold(f64::NEG_INFINITY, f64::max)
+                }
+                None => weights.evaluate(&board, lines),
+            };
+            (placement, score)
+        })
+        .fold(
+            None,
+            |best: Option<(Placement, f64)>, (placement, score)| match best {
+                Some((_, best_score)) if best_score >= score => best,
+                _ => Some((placement, score)),
+            },
+        )
+        .map(|(placement, _)| placement)
+}
+
+/// Plays a game through the same inputs as a player, one action every few ticks.
+#[derive(Clone, Debug)]
+pub struct AiController {
+    pub weights: Weights,
+    action_ticks: u32,
+    wait_ticks: u32,
+    // the placement chosen for the brick numbered `target_piece`
+    target: Option<Placement>,
+    target_piece: usize,
+    replans: usize,
+}
+
+impl AiController {
+    pub fn new(action_ticks: u32) -> Self {
+        AiController {
+            weights: Weights::default(),
+            action_ticks,
+            wait_ticks: 0,
+            target: None,
+            target_piece: 0,
+            replans: 0,
+        }
+    }
+
+    /// The input of the next tick of the engine.
+    pub fn next_input(&mut self, engine: &GameEngine) -> InputFrame {
+        let mut input = InputFrame::default();
+        let Some(active) = engine.active.filter(|active| active.movable) else {
+            return input;
+        };
+        // a new brick, the last one may have locked before reaching its target
+        if self.target_piece != engine.pieces {
+            self.target = None;
+            self.target_piece = engine.pieces;
+            self.replans = 0;
+        }
+        if self.wait_ticks > 0 {
+            self.wait_ticks -= 1;
+            return input;
+        }
+        self.wait_ticks = self.action_ticks.saturating_sub(1);
+
+        if self.target.is_none() {
+            self.target = self.plan(engine, &active);
+        }
+        let mut action = self
+            .target
+            .and_then(|target| next_action(&engine.board, &active, &target));
+        // the brick fell where the planned moves are blocked, look again from there
+        if action.is_none() && self.replans < MAX_REPLANS {
+            self.replans += 1;
+            self.target = self.plan(engine, &active);
+            action = self
+                .target
+                .and_then(|target| next_action(&engine.board, &active, &target));
+        }
+        // nowhere to go, the game is lost anyway
+        input.insert(action.unwrap_or(InputAction::HardDrop));
+        input
+    }
+
+    fn plan(&self, engine: &GameEngine, active: &ActiveBrick) -> Option<Placement> {
+        best_placement(
+            &engine.board,
+            active.brick,
+            active.pos,
+            Some(engine.next),
+            &self.weights,
+        )
+    }
+}
+
+/// The action bringing the brick closer to the placement, None when it is blocked.
+pub fn next_action(board: &Board, active: &ActiveBrick, target: &Placement) -> Option<InputAction> {
+    if active.brick != target.brick {
+        let mut pos = active.pos;
+        return board
+            .is_valid_brick_for_rotation(&active.brick.rotate_right(), &mut pos)
+            .then_some(InputAction::Rotate);
+    }
+    if active.pos.x > target.pos.x {
+        return board
+            .is_valid_brick(&active.brick, &active.pos.left())
+            .then_some(InputAction::MoveLeft);
+    }
+    if active.pos.x < target.pos.x {
+        return board
+            .is_valid_brick(&active.brick, &active.pos.right())
+            .then_some(InputAction::MoveRight);
+    }
+    Some(InputAction::HardDrop)
+}
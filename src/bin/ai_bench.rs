@@ -0,0 +1,61 @@
+//! Headless benchmark of the AI player.
+//!
+//! Plays a few Marathon games on Easy with the AI, without any window, and reports the lines
+//! cleared before topping out.
+//!
+//! Usage: `ai_bench [games] [first seed] [max pieces]`, defaults to 10 games from seed 0,
+//! with at most 10000 bricks per game.
+
+use std::{env, time::Instant};
+
+use tetris::{ai::AiController, engine::GameEngine, menu::GameSelectedLevel, mode::GameMode};
+
+const DEFAULT_GAMES: u64 = 10;
+const DEFAULT_MAX_PIECES: usize = 10_000;
+// the AI acts on every tick, the benchmark does not need to be watchable
+const BENCH_ACTION_TICKS: u32 = 1;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let arg = |i: usize, default: u64| -> u64 {
+        match args.get(i) {
+            Some(value) => value.parse().unwrap_or_else(|_| {
+                eprintln!("invalid number: {}", value);
+                std::process::exit(2);
+            }),
+            None => default,
+        }
+    };
+    let games = arg(0, DEFAULT_GAMES);
+    let first_seed = arg(1, 0);
+    let max_pieces = arg(2, DEFAULT_MAX_PIECES as u64) as usize;
+
+    let started = Instant::now();
+    let mut total_lines = 0;
+    for seed in first_seed..first_seed + games {
+        let mut engine = GameEngine::new(GameSelectedLevel::Easy, GameMode::Marathon, seed);
+        let mut ai = AiController::new(BENCH_ACTION_TICKS);
+        while !engine.is_game_over() && engine.pieces <= max_pieces {
+            let input = ai.next_input(&engine);
+            engine.step(input);
+            engine.drain_events();
+        }
+        let result = match engine.top_out {
+            Some(top_out) => top_out.name(),
+            None => "piece limit",
+        };
+        println!(
+            "seed {:>4}: {:>5} lines, {:>5} pieces, level {:>2} ({})",
+            seed, engine.lines, engine.pieces, engine.level, result
+        );
+        total_lines += engine.lines;
+    }
+    if games > 0 {
+        println!(
+            "average: {:.1} lines over {} games in {:.1}s",
+            total_lines as f64 / games as f64,
+            games,
+            started.elapsed().as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,42 @@
+//! Converts boards between fumen and the text format of the puzzles.
+//!
+//! Usage: `fumen decode <fumen>` prints the first page of a fumen, or a link to it, as text.
+//! `fumen encode [file]` prints the fumen of a text board, read from the standard input
+//! when no file is given.
+
+use std::{env, fs, io::Read, process};
+
+use tetris::fumen::BoardSetup;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["decode", fumen] => BoardSetup::from_fumen(fumen).map(|setup| setup.to_text()),
+        ["encode"] => {
+            let mut text = String::new();
+            std::io::stdin()
+                .read_to_string(&mut text)
+                .and_then(|_| BoardSetup::from_text(&text))
+                .and_then(|setup| setup.to_fumen())
+        }
+        ["encode", path] => fs::read_to_string(path)
+            .and_then(|text| BoardSetup::from_text(&text))
+            .and_then(|setup| setup.to_fumen()),
+        _ => {
+            eprintln!("usage: fumen decode <fumen> | fumen encode [file]");
+            process::exit(2);
+        }
+    };
+    match result {
+        Ok(text) => println!("{}", text.trim_end()),
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    }
+}
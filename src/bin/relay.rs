@@ -0,0 +1,81 @@
+//! Relay server of the online versus mode.
+//!
+//! The clients connecting one after the other are paired, everything one client sends
+//! is forwarded to the other one. The relay does not know the protocol of the game,
+//! so it does not need to be updated with it.
+//!
+//! Usage: `relay [address]`, the address defaults to 0.0.0.0:7878.
+
+use std::{
+    env,
+    io::{self, ErrorKind},
+    net::{Shutdown, TcpListener, TcpStream},
+    thread,
+};
+
+const DEFAULT_LISTEN_ADDR: &str = "0.0.0.0:7878";
+
+fn main() -> io::Result<()> {
+    let addr = env::args()
+        .nth(1)
+        .unwrap_or_else(|| DEFAULT_LISTEN_ADDR.to_string());
+    let listener = TcpListener::bind(&addr)?;
+    println!("relay listening on {}", listener.local_addr()?);
+
+    let mut waiting: Option<TcpStream> = None;
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                eprintln!("failed to accept a connection: {}", err);
+                continue;
+            }
+        };
+        let _ = stream.set_nodelay(true);
+        match waiting.take() {
+            // the waiting client may have left in the meantime
+            Some(first) if is_alive(&first) => {
+                if let Err(err) = start_pair(first, stream) {
+                    eprintln!("failed to pair two clients: {}", err);
+                }
+            }
+            _ => waiting = Some(stream),
+        }
+    }
+    Ok(())
+}
+
+// a client which has not sent anything yet would block, a closed one reads nothing
+fn is_alive(stream: &TcpStream) -> bool {
+    if stream.set_nonblocking(true).is_err() {
+        return false;
+    }
+    let mut byte = [0; 1];
+    let alive = match stream.peek(&mut byte) {
+        Ok(read) => read > 0,
+        Err(err) => err.kind() == ErrorKind::WouldBlock,
+    };
+    stream.set_nonblocking(false).is_ok() && alive
+}
+
+fn start_pair(first: TcpStream, second: TcpStream) -> io::Result<()> {
+    println!("pairing {} with {}", peer_name(&first), peer_name(&second));
+    let first_clone = first.try_clone()?;
+    let second_clone = second.try_clone()?;
+    thread::spawn(move || forward(first, second_clone));
+    thread::spawn(move || forward(second, first_clone));
+    Ok(())
+}
+
+// when one side is done, the whole pair is closed
+fn forward(mut from: TcpStream, mut to: TcpStream) {
+    let _ = io::copy(&mut from, &mut to);
+    let _ = from.shutdown(Shutdown::Both);
+    let _ = to.shutdown(Shutdown::Both);
+}
+
+fn peer_name(stream: &TcpStream) -> String {
+    stream
+        .peer_addr()
+        .map_or_else(|_| "unknown".to_string(), |addr| addr.to_string())
+}
@@ -0,0 +1,743 @@
+//! Plays the game in a terminal, e.g. over SSH.
+//!
+//! Usage: `tui [--mode <name>] [--difficulty <level>] [--level <n>] [--seed <n>]
+//! [--board <cols>x<rows>] [--config <file>]`, the options of the window frontend.
+//! A game starts directly when any of them is given, otherwise the menu is shown.
+//!
+//! The terminal has no key release events, so a soft drop lasts a few ticks after every press
+//! of Down, and holding the key keeps it going through the key repeat of the terminal.
+
+use std::{
+    collections::VecDeque,
+    env,
+    fmt::Write as _,
+    io::{self, Write},
+    process, thread,
+    time::{Duration, Instant},
+};
+
+use rand::seq::SliceRandom;
+use tetris::{
+    brick::{Brick, BrickType},
+    cli::CliArgs,
+    engine::{EngineEvent, GameEngine, InputAction, InputFrame, TICKS_PER_SECOND},
+    fade::StackFade,
+    finesse::placement_cells,
+    game::BRICK_COLOR_MAP,
+    hud::action_text,
+    locale::Locale,
+    menu::GameSelectedLevel,
+    mode::{GameMode, GameOptions},
+    position::Position,
+    replay::Replay,
+    stats::GameStats,
+};
+
+const USAGE: &str = "\
+Usage: tui [options]
+
+Options:
+  --mode <name>          start a game of this mode: marathon, 4-wide, 12x24, 20x40,
+                         cheese-10l, cheese-18l, cheese-100l, finesse, invisible, fading,
+                         big or zen
+  --difficulty <level>   easy, normal or hard
+  --level <n>            the level to start from
+  --seed <n>             the seed of the bricks and the garbage
+  --board <cols>x<rows>  the size of the board instead of the one of the mode
+  --config <file>        read the options from a file of `name = value` lines
+  --help                 show this help
+
+Keys: Left/Right move, Up rotate, Space hard drop, Down or J soft drop, Esc pause";
+
+const DIFFICULTIES: [GameSelectedLevel; 3] = [
+    GameSelectedLevel::Easy,
+    GameSelectedLevel::Normal,
+    GameSelectedLevel::Hard,
+];
+// a soft drop falls one row every 6 ticks, so one press drops about one row
+const SOFT_DROP_PRESS_TICKS: u32 = 8;
+const ACTION_TEXT_TICKS: u32 = 2 * TICKS_PER_SECOND;
+// the frame is late by more than this, the ticks are not caught up
+const MAX_LATE_TICKS: u32 = 10;
+const TARGET_COLOR: &str = "#33E64D";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Key {
+    Left,
+    Right,
+    Up,
+    Down,
+    Enter,
+    Esc,
+    // Ctrl-C, the terminal does not send signals in raw mode
+    Interrupt,
+    Char(char),
+}
+
+enum Screen {
+    Menu(MenuScreen),
+    Game(Box<GameScreen>),
+    GameOver(GameOverScreen),
+}
+
+struct MenuScreen {
+    mode_index: usize,
+    difficulty_index: usize,
+}
+
+struct GameScreen {
+    engine: GameEngine,
+    difficulty: GameSelectedLevel,
+    mode: GameMode,
+    stats: GameStats,
+    replay: Replay,
+    paused: bool,
+    // the actions pressed but not played yet, at most one of each per tick
+    pending: VecDeque<InputAction>,
+    soft_drop_ticks: u32,
+    action_text: String,
+    action_ticks: u32,
+    // the placement to reach in the finesse trainer
+    target: Option<[Position; 4]>,
+    target_piece: usize,
+    // the terminal shows the English texts
+    locale: Locale,
+    stack_fade: StackFade,
+}
+
+struct GameOverScreen {
+    game: Box<GameScreen>,
+    // where the replay was saved, or why it was not
+    replay_status: String,
+}
+
+fn main() {
+    let cli_args = CliArgs::parse(env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("{}\n\n{}", err, USAGE);
+        process::exit(2);
+    });
+    if cli_args.help {
+        println!("{}", USAGE);
+        return;
+    }
+    if cli_args.replay.is_some() || cli_args.window_size.is_some() || cli_args.fullscreen {
+        eprintln!("replays and window options are only supported by the window frontend");
+        process::exit(2);
+    }
+    let mut terminal = Terminal::open().unwrap_or_else(|err| {
+        eprintln!("failed to set up the terminal: {}", err);
+        process::exit(1);
+    });
+
+    let options = cli_args.game_options();
+    let menu = MenuScreen {
+        mode_index: GameMode::ALL
+            .iter()
+            .position(|mode| Some(*mode) == cli_args.mode)
+            .unwrap_or(0),
+        difficulty_index: DIFFICULTIES
+            .iter()
+            .position(|difficulty| Some(*difficulty) == cli_args.difficulty)
+            .unwrap_or(0),
+    };
+    let mut screen = if cli_args.starts_game() {
+        Screen::Game(menu.start_game(options, cli_args.seed))
+    } else {
+        Screen::Menu(menu)
+    };
+
+    let tick = Duration::from_secs(1) / TICKS_PER_SECOND;
+    let mut next_tick = Instant::now();
+    loop {
+        let keys = terminal.read_keys();
+        if keys.contains(&Key::Interrupt) {
+            break;
+        }
+        screen = match screen {
+            Screen::Menu(mut menu) => {
+                if keys.contains(&Key::Char('q')) {
+                    break;
+                }
+                menu.handle_keys(&keys);
+                if keys.contains(&Key::Enter) {
+                    Screen::Game(menu.start_game(options, cli_args.seed))
+                } else {
+                    Screen::Menu(menu)
+                }
+            }
+            Screen::Game(mut game) => {
+                if game.paused && keys.contains(&Key::Char('q')) {
+                    Screen::Menu(game.menu())
+                } else {
+                    game.handle_keys(&keys);
+                    if !game.paused {
+                        game.step();
+                    }
+                    if game.engine.is_game_over() {
+                        Screen::GameOver(GameOverScreen::new(game))
+                    } else {
+                        Screen::Game(game)
+                    }
+                }
+            }
+            Screen::GameOver(game_over) => {
+                if keys.contains(&Key::Char('q')) {
+                    break;
+                }
+                if keys.contains(&Key::Enter) || keys.contains(&Key::Esc) {
+                    Screen::Menu(game_over.game.menu())
+                } else {
+                    Screen::GameOver(game_over)
+                }
+            }
+        };
+        let frame = match &screen {
+            Screen::Menu(menu) => menu.render(),
+            Screen::Game(game) => game.render(),
+            Screen::GameOver(game_over) => game_over.render(),
+        };
+        if let Err(err) = terminal.draw(&frame) {
+            drop(terminal);
+            eprintln!("failed to draw: {}", err);
+            process::exit(1);
+        }
+
+        next_tick += tick;
+        let now = Instant::now();
+        if now > next_tick + tick * MAX_LATE_TICKS {
+            next_tick = now;
+        }
+        thread::sleep(next_tick.saturating_duration_since(now));
+    }
+}
+
+impl MenuScreen {
+    fn handle_keys(&mut self, keys: &[Key]) {
+        let modes = GameMode::ALL.len();
+        for key in keys {
+            match key {
+                Key::Up => self.mode_index = (self.mode_index + modes - 1) % modes,
+                Key::Down => self.mode_index = (self.mode_index + 1) % modes,
+                Key::Left => self.difficulty_index = self.difficulty_index.saturating_sub(1),
+                Key::Right => {
+                    self.difficulty_index = (self.difficulty_index + 1).min(DIFFICULTIES.len() - 1)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn start_game(&self, options: GameOptions, seed: Option<u64>) -> Box<GameScreen> {
+        let seed = seed.unwrap_or_else(rand::random);
+        let mode = GameMode::ALL[self.mode_index];
+        let difficulty = DIFFICULTIES[self.difficulty_index];
+        Box::new(GameScreen {
+            engine: GameEngine::with_options(difficulty, mode, seed, options),
+            difficulty,
+            mode,
+            // the faults are always shown next to the board
+            stats: GameStats {
+                finesse_faults: Some(0),
+                ..Default::default()
+            },
+            replay: Replay::new(difficulty, mode, seed, options),
+            paused: false,
+            pending: VecDeque::new(),
+            soft_drop_ticks: 0,
+            action_text: String::new(),
+            action_ticks: 0,
+            target: None,
+            target_piece: 0,
+            locale: Locale::english(),
+            stack_fade: StackFade::default(),
+        })
+    }
+
+    fn render(&self) -> String {
+        let mut frame = String::from("\r\n  T E T R I S\r\n\r\n  MODE\r\n");
+        for (index, mode) in GameMode::ALL.iter().enumerate() {
+            let cursor = if index == self.mode_index { ">" } else { " " };
+            let _ = write!(frame, "  {} {}\r\n", cursor, mode.name());
+        }
+        let _ = write!(
+            frame,
+            "\r\n  DIFFICULTY  < {} >\r\n\r\n",
+            difficulty_name(DIFFICULTIES[self.difficulty_index])
+        );
+        frame.push_str("  Up/Down: mode   Left/Right: difficulty   Enter: play   Q: quit\r\n");
+        frame
+    }
+}
+
+impl GameScreen {
+    fn menu(&self) -> MenuScreen {
+        MenuScreen {
+            mode_index: GameMode::ALL
+                .iter()
+                .position(|mode| *mode == self.mode)
+                .unwrap_or(0),
+            difficulty_index: DIFFICULTIES
+                .iter()
+                .position(|difficulty| *difficulty == self.difficulty)
+                .unwrap_or(0),
+        }
+    }
+
+    fn handle_keys(&mut self, keys: &[Key]) {
+        for key in keys {
+            let action = match key {
+                Key::Esc | Key::Char('p') => {
+                    self.paused = !self.paused;
+                    continue;
+                }
+                _ if self.paused => continue,
+                // a zen session has no game over, it ends here
+                Key::Enter if self.engine.zen_resets().is_some() => InputAction::EndSession,
+                Key::Left => InputAction::MoveLeft,
+                Key::Right => InputAction::MoveRight,
+                Key::Up => InputAction::Rotate,
+                Key::Char(' ') => InputAction::HardDrop,
+                Key::Down | Key::Char('j') => InputAction::SoftDropPressed,
+                _ => continue,
+            };
+            self.pending.push_back(action);
+        }
+    }
+
+    fn step(&mut self) {
+        let mut input = InputFrame::default();
+        // a key pressed twice within a tick is played on the next one
+        while let Some(action) = self.pending.front().copied() {
+            if input.contains(action) {
+                break;
+            }
+            self.pending.pop_front();
+            if action == InputAction::SoftDropPressed {
+                if self.soft_drop_ticks > 0 {
+                    self.soft_drop_ticks = SOFT_DROP_PRESS_TICKS;
+                    continue;
+                }
+                self.soft_drop_ticks = SOFT_DROP_PRESS_TICKS;
+            }
+            input.insert(action);
+        }
+        if self.soft_drop_ticks > 0 && !input.contains(InputAction::SoftDropPressed) {
+            self.soft_drop_ticks -= 1;
+            if self.soft_drop_ticks == 0 {
+                input.insert(InputAction::SoftDropReleased);
+            }
+        }
+
+        self.engine.step(input);
+        self.stack_fade.update(&self.engine);
+        self.action_ticks = self.action_ticks.saturating_sub(1);
+        let events: Vec<EngineEvent> = self.engine.drain_events().collect();
+        for event in events {
+            match event {
+                EngineEvent::Ticked { tick, input } => {
+                    self.stats.record_tick(input);
+                    self.replay.record(tick, input);
+                }
+                EngineEvent::PieceLocked(brick, pos) => {
+                    self.stats.record_piece(brick.0);
+                    if self.engine.is_finesse_trainer() {
+                        let (hit, placed) = self.stats.targets.get_or_insert((0, 0));
+                        *placed += 1;
+                        if self.target == Some(placement_cells(&brick, pos)) {
+                            *hit += 1;
+                        }
+                    }
+                }
+                EngineEvent::FinesseFault { presses, expected } => {
+                    *self.stats.finesse_faults.get_or_insert(0) += 1;
+                    self.show_action_text(format!(
+                        "FINESSE FAULT\n{} KEYS, {} NEEDED",
+                        presses, expected
+                    ));
+                }
+                EngineEvent::LinesCleared(clear) => {
+                    self.stats.record_clear(&clear);
+                    self.show_action_text(action_text(&clear, &self.locale));
+                }
+                _ => {}
+            }
+        }
+
+        // any placement the brick can reach by shifting and rotating
+        if self.engine.is_finesse_trainer()
+            && self.engine.active.is_some()
+            && self.target_piece != self.engine.pieces
+        {
+            self.target_piece = self.engine.pieces;
+            self.target = self
+                .engine
+                .finesse_paths()
+                .choose(&mut rand::thread_rng())
+                .map(|path| path.cells);
+        }
+    }
+
+    fn show_action_text(&mut self, text: String) {
+        self.action_text = text;
+        self.action_ticks = ACTION_TEXT_TICKS;
+    }
+
+    fn render(&self) -> String {
+        let mut panel = vec![
+            format!("{}  {}", self.mode.name(), difficulty_name(self.difficulty)),
+            String::new(),
+            "NEXT".to_string(),
+        ];
+        // the preview is hidden when no brick is left to come
+        if self.engine.pieces_left() == Some(0) {
+            panel.extend([String::new(), String::new()]);
+        } else {
+            panel.extend(render_brick(&self.engine.next));
+        }
+        panel.push(String::new());
+        panel.extend(self.hud_lines());
+        panel.push(String::new());
+        if self.paused {
+            panel.extend([
+                "PAUSED".to_string(),
+                "Esc: resume   Q: main menu".to_string(),
+            ]);
+        } else if self.action_ticks > 0 {
+            panel.extend(self.action_text.lines().map(str::to_string));
+        }
+        render_board_with_panel(self, &panel)
+    }
+
+    fn hud_lines(&self) -> Vec<String> {
+        let engine = &self.engine;
+        let secs = self.stats.play_seconds() as u64;
+        let mut lines = vec![
+            format!("SCORE  {}", engine.score),
+            format!("LEVEL  {}", engine.level),
+            format!("LINES  {}", engine.lines),
+            format!("TIME  {:02}:{:02}", secs / 60, secs % 60),
+            format!("PPS  {:.2}", self.stats.pieces_per_second()),
+            format!("APM  {:.1}", self.stats.attack_per_minute()),
+            match engine.combo() {
+                Some(combo) if combo > 0 => format!("COMBO  {}", combo),
+                _ => "COMBO  -".to_string(),
+            },
+            if engine.back_to_back() {
+                "B2B  ON".to_string()
+            } else {
+                "B2B  -".to_string()
+            },
+            format!("FAULTS  {}", engine.finesse_faults()),
+        ];
+        if let Some(garbage) = engine.garbage_remaining() {
+            lines.push(format!("GARBAGE  {}", garbage));
+        }
+        if engine.is_finesse_trainer() {
+            let (hit, placed) = self.stats.targets.unwrap_or_default();
+            lines.push(format!("TARGETS  {}/{}", hit, placed));
+        }
+        if let Some(resets) = engine.zen_resets() {
+            lines.push(format!("RESETS  {}", resets));
+            lines.push("Enter: end".to_string());
+        }
+        lines
+    }
+}
+
+impl GameOverScreen {
+    fn new(game: Box<GameScreen>) -> Self {
+        let secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        let path = format!("replays/replay-{}.ttr", secs);
+        let replay_status = match game.replay.save(&path) {
+            Ok(()) => format!("Replay saved to {}", path),
+            Err(err) => format!("Failed to save the replay: {}", err),
+        };
+        GameOverScreen {
+            game,
+            replay_status,
+        }
+    }
+
+    fn render(&self) -> String {
+        let engine = &self.game.engine;
+        let mut panel = vec![
+            if engine.zen_resets().is_some() {
+                "SESSION OVER".to_string()
+            } else if engine.goal_reached {
+                "COMPLETE".to_string()
+            } else {
+                "GAME OVER".to_string()
+            },
+            match (engine.top_out, engine.zen_resets()) {
+                (Some(top_out), _) => top_out.name().to_string(),
+                (None, Some(resets)) => format!("Board resets : {}", resets),
+                (None, None) => String::new(),
+            },
+            String::new(),
+            format!(
+                "Score : {}   Level : {}   Lines : {}",
+                engine.score, engine.level, engine.lines
+            ),
+            String::new(),
+        ];
+        panel.extend(
+            self.game
+                .stats
+                .summary()
+                .into_iter()
+                .map(|(label, value)| format!("{} : {}", self.game.locale.text(label), value)),
+        );
+        panel.extend([
+            String::new(),
+            self.replay_status.clone(),
+            "Enter: main menu   Q: quit".to_string(),
+        ]);
+        render_board_with_panel(&self.game, &panel)
+    }
+}
+
+fn difficulty_name(difficulty: GameSelectedLevel) -> &'static str {
+    match difficulty {
+        GameSelectedLevel::Easy => "Easy",
+        GameSelectedLevel::Normal => "Normal",
+        GameSelectedLevel::Hard => "Hard",
+    }
+}
+
+/// The board in a frame, two characters per cell, with the lines of the panel on its right.
+fn render_board_with_panel(game: &GameScreen, panel: &[String]) -> String {
+    let engine = &game.engine;
+    let board = &engine.board;
+    // the ghost and the grid follow the difficulty, like in the window
+    let show_ghost = game.difficulty == GameSelectedLevel::Easy;
+    let show_grid = game.difficulty != GameSelectedLevel::Hard;
+    let block_size = board.block_size();
+    let active_cells: Vec<Position> = engine
+        .active
+        .map(|active| active.brick.cells(active.pos, block_size).collect())
+        .unwrap_or_default();
+    let ghost_cells: Vec<Position> = engine
+        .active
+        .filter(|_| show_ghost)
+        .map(|active| {
+            let pos = board.get_bottom_valid_brick_pos(&active.brick, &active.pos);
+            active.brick.cells(pos, block_size).collect()
+        })
+        .unwrap_or_default();
+    let active_type = engine.active.map(|active| active.brick.0);
+
+    let mut rows = vec![format!("+{}+", "-".repeat(board.width() * 2))];
+    for y in (0..board.visible_height()).rev() {
+        let mut row = String::from("|");
+        for x in 0..board.width() {
+            let pos = Position {
+                x: x as i32,
+                y: y as i32,
+            };
+            let mut cell = board.get(x, y);
+            // the terminal cannot fade a block, it is hidden half way
+            if game.stack_fade.visibility(x, y) < 0.5 {
+                cell = BrickType::None;
+            }
+            let empty = background(BrickType::None);
+            if active_cells.contains(&pos) {
+                let _ = write!(row, "{}  ", background(active_type.unwrap()));
+            } else if cell != BrickType::None {
+                let _ = write!(row, "{}  ", background(cell));
+            } else if game.target.is_some_and(|cells| cells.contains(&pos)) {
+                let _ = write!(row, "{}{}::", empty, foreground(TARGET_COLOR));
+            } else if ghost_cells.contains(&pos) {
+                let color = &BRICK_COLOR_MAP[&active_type.unwrap()];
+                let _ = write!(row, "{}{}[]", empty, foreground(color));
+            } else if show_grid {
+                let _ = write!(row, "{}\x1b[38;2;32;31;30m .", empty);
+            } else {
+                let _ = write!(row, "{}  ", empty);
+            }
+        }
+        row.push_str("\x1b[0m|");
+        rows.push(row);
+    }
+    rows.push(rows[0].clone());
+
+    let mut frame = String::new();
+    for index in 0..rows.len().max(panel.len()) {
+        match rows.get(index) {
+            Some(row) => frame.push_str(row),
+            None => frame.push_str(&" ".repeat(board.width() * 2 + 2)),
+        }
+        let _ = write!(
+            frame,
+            "   {}\x1b[K\r\n",
+            panel.get(index).map_or("", String::as_str)
+        );
+    }
+    frame
+}
+
+/// The two rows of a brick in its spawn rotation.
+fn render_brick(brick: &Brick) -> Vec<String> {
+    let min_x = brick.1.iter().map(|pos| pos.x).min().unwrap_or(0);
+    let max_y = brick.1.iter().map(|pos| pos.y).max().unwrap_or(0);
+    (0..2)
+        .map(|row| {
+            let mut line = String::new();
+            for x in min_x..min_x + 4 {
+                let pos = Position { x, y: max_y - row };
+                if brick.1.contains(&pos) {
+                    let _ = write!(line, "{}  \x1b[0m", background(brick.0));
+                } else {
+                    line.push_str("  ");
+                }
+            }
+            line
+        })
+        .collect()
+}
+
+fn rgb(hex: &str) -> (u8, u8, u8) {
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap_or_default();
+    (channel(1), channel(3), channel(5))
+}
+
+fn background(brick_type: BrickType) -> String {
+    let (r, g, b) = rgb(&BRICK_COLOR_MAP[&brick_type]);
+    format!("\x1b[48;2;{};{};{}m", r, g, b)
+}
+
+fn foreground(hex: &str) -> String {
+    let (r, g, b) = rgb(hex);
+    format!("\x1b[38;2;{};{};{}m", r, g, b)
+}
+
+/// The terminal in raw mode on the alternate screen, restored when dropped.
+struct Terminal {
+    #[cfg(unix)]
+    original: libc::termios,
+    // the last frame drawn, not drawn again while nothing changed
+    last_frame: String,
+}
+
+impl Terminal {
+    #[cfg(unix)]
+    fn open() -> io::Result<Self> {
+        // SAFETY: termios is plain data, filled in by tcgetattr before it is read
+        let mut original: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut raw = original;
+        raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG | libc::IEXTEN);
+        raw.c_iflag &= !(libc::IXON | libc::ICRNL);
+        // reads return at once, with whatever was typed
+        raw.c_cc[libc::VMIN] = 0;
+        raw.c_cc[libc::VTIME] = 0;
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let terminal = Terminal {
+            original,
+            last_frame: String::new(),
+        };
+        // the alternate screen, without the cursor
+        print!("\x1b[?1049h\x1b[?25l\x1b[2J");
+        io::stdout().flush()?;
+        Ok(terminal)
+    }
+
+    #[cfg(not(unix))]
+    fn open() -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the terminal frontend needs a unix terminal",
+        ))
+    }
+
+    #[cfg(unix)]
+    fn read_keys(&mut self) -> Vec<Key> {
+        let mut bytes = Vec::new();
+        let mut buf = [0u8; 64];
+        loop {
+            // SAFETY: the buffer outlives the call and its length is passed along
+            let count = unsafe {
+                libc::read(
+                    libc::STDIN_FILENO,
+                    buf.as_mut_ptr() as *mut libc::c_void,
+                    buf.len(),
+                )
+            };
+            if count <= 0 {
+                break;
+            }
+            bytes.extend_from_slice(&buf[..count as usize]);
+        }
+        parse_keys(&bytes)
+    }
+
+    #[cfg(not(unix))]
+    fn read_keys(&mut self) -> Vec<Key> {
+        Vec::new()
+    }
+
+    fn draw(&mut self, frame: &str) -> io::Result<()> {
+        if frame == self.last_frame {
+            return Ok(());
+        }
+        let mut stdout = io::stdout().lock();
+        // from the top left, clearing what the previous frame left below
+        write!(stdout, "\x1b[H{}\x1b[0m\x1b[J", frame)?;
+        stdout.flush()?;
+        self.last_frame = frame.to_string();
+        Ok(())
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        print!("\x1b[0m\x1b[?25h\x1b[?1049l");
+        let _ = io::stdout().flush();
+        #[cfg(unix)]
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original);
+        }
+    }
+}
+
+fn parse_keys(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut index = 0;
+    while index < bytes.len() {
+        let key = match bytes[index] {
+            0x1b if bytes.get(index + 1) == Some(&b'[') => {
+                index += 2;
+                match bytes.get(index) {
+                    Some(b'A') => Key::Up,
+                    Some(b'B') => Key::Down,
+                    Some(b'C') => Key::Right,
+                    Some(b'D') => Key::Left,
+                    // the other sequences are skipped up to their final byte
+                    _ => {
+                        while bytes
+                            .get(index)
+                            .is_some_and(|byte| !(0x40..=0x7e).contains(byte))
+                        {
+                            index += 1;
+                        }
+                        index += 1;
+                        continue;
+                    }
+                }
+            }
+            0x1b => Key::Esc,
+            b'\r' | b'\n' => Key::Enter,
+            0x03 => Key::Interrupt,
+            byte => Key::Char(byte.to_ascii_lowercase() as char),
+        };
+        keys.push(key);
+        index += 1;
+    }
+    keys
+}
@@ -2,62 +2,104 @@
 
 use crate::{
     brick::{Brick, BrickType},
-    constants::BOARD_X,
-    constants::BOARD_Y,
+    constants::BOARD_BUFFER_FACTOR,
     position::Position,
 };
 
-#[derive(Debug, Clone, Copy)]
-pub(crate) struct Board(pub [[BrickType; BOARD_Y]; BOARD_X]);
+/// The cells of the board, indexed as `cells[x][y]` with y = 0 at the bottom.
+/// Above the visible rows is the vanish zone, hidden rows where the bricks spawn
+/// and where the stack can grow without topping out.
+///
+/// The positions of the bricks count in blocks, which cover `block_size` by `block_size` cells,
+/// so in the big mode the bricks move by 2 cells and fill the lines in pairs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Board {
+    cells: Vec<Vec<BrickType>>,
+    visible_height: usize,
+    block_size: usize,
+}
 
 impl Board {
-    pub fn new() -> Self {
-        Board([[BrickType::None; BOARD_Y]; BOARD_X])
+    pub fn new(width: usize, visible_height: usize) -> Self {
+        Board {
+            cells: vec![vec![BrickType::None; visible_height * BOARD_BUFFER_FACTOR]; width],
+            visible_height,
+            block_size: 1,
+        }
+    }
+
+    pub fn with_block_size(self, block_size: usize) -> Self {
+        Board { block_size, ..self }
+    }
+
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    pub fn width(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// The number of rows, including the hidden ones.
+    pub fn height(&self) -> usize {
+        self.cells[0].len()
+    }
+
+    pub fn visible_height(&self) -> usize {
+        self.visible_height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> BrickType {
+        self.cells[x][y]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, brick_type: BrickType) {
+        self.cells[x][y] = brick_type;
     }
 
     pub fn occupy_brick(&mut self, brick: Brick, moving_pos: Position) {
-        let len: usize = self.0.len();
-        let len_y: usize = self.0[0].len();
-        for pos in brick.1 {
-            let real_pos = pos + moving_pos;
+        let len: usize = self.cells.len();
+        let len_y: usize = self.cells[0].len();
+        for real_pos in brick.cells(moving_pos, self.block_size) {
             if (real_pos.x as usize) < len && (real_pos.y as usize) < len_y {
-                self.0[real_pos.x as usize][real_pos.y as usize] = brick.0;
+                self.cells[real_pos.x as usize][real_pos.y as usize] = brick.0;
             }
         }
     }
 
     pub fn is_position_occupied(&self, pos: &Position) -> bool {
-        self.0[pos.x as usize][pos.y as usize] != BrickType::None
+        self.cells[pos.x as usize][pos.y as usize] != BrickType::None
     }
 
     pub fn is_brick_conflicted(&self, brick: &Brick, moving_pos: &Position) -> bool {
-        for pos in brick.1 {
-            let real_pos = pos + *moving_pos;
-            if self.0[real_pos.x as usize][real_pos.y as usize] != BrickType::None {
+        for real_pos in brick.cells(*moving_pos, self.block_size) {
+            if self.cells[real_pos.x as usize][real_pos.y as usize] != BrickType::None {
                 return true;
             }
         }
-        return false;
+        false
     }
 
     pub fn is_position_in_board(&self, pos: &Position) -> bool {
-        let len = self.0.len();
-        let len_y = self.0[0].len();
-        0 <= pos.x && (pos.x as usize) < len && (pos.y as usize) < len_y - 3 && pos.y >= 0
+        0 <= pos.x
+            && (pos.x as usize) < self.width()
+            && pos.y >= 0
+            && (pos.y as usize) < self.height()
     }
 
     pub fn is_brick_in_board(&self, brick: &Brick, moving_pos: &Position) -> bool {
-        for pos in brick.1 {
-            let real_pos = pos + *moving_pos;
+        for real_pos in brick.cells(*moving_pos, self.block_size) {
             if !self.is_position_in_board(&real_pos) {
                 return false;
             }
         }
-        return true;
+        true
     }
 
+    /// Whether a block of a brick fits at the position, counted in blocks.
     pub fn is_valid_position(&self, pos: &Position) -> bool {
-        self.is_position_in_board(pos) && !self.is_position_occupied(pos)
+        pos.block_cells(self.block_size)
+            .all(|cell| self.is_position_in_board(&cell) && !self.is_position_occupied(&cell))
     }
 
     pub fn is_valid_brick(&self, brick: &Brick, moving_pos: &Position) -> bool {
@@ -65,7 +107,7 @@ impl Board {
     }
 
     pub fn get_bottom_valid_brick_pos(&self, brick: &Brick, moving_pos: &Position) -> Position {
-        let mut bottom_pos = moving_pos.clone();
+        let mut bottom_pos = *moving_pos;
         loop {
             if !self.is_valid_brick(brick, &bottom_pos) {
                 if bottom_pos.y < moving_pos.y {
@@ -78,37 +120,80 @@ impl Board {
         }
     }
 
+    /// Pushes the stack up by one row for every hole and fills the bottom rows with garbage,
+    /// `holes[i]` is the empty column of the i-th row from the bottom.
+    /// Returns false when blocks were pushed out of the top of the buffer.
+    pub fn push_garbage(&mut self, holes: &[usize]) -> bool {
+        let count = holes.len().min(self.height());
+        let height = self.height();
+        let mut in_buffer = true;
+        for (x, column) in self.cells.iter_mut().enumerate() {
+            if column[height - count..]
+                .iter()
+                .any(|cell| *cell != BrickType::None)
+            {
+                in_buffer = false;
+            }
+            column.truncate(height - count);
+            let garbage = holes[..count].iter().map(|hole| {
+                if *hole == x {
+                    BrickType::None
+                } else {
+                    BrickType::Garbage
+                }
+            });
+            column.splice(0..0, garbage);
+        }
+        in_buffer
+    }
+
     pub fn clear_board(&mut self) {
-        for row in self.0.iter_mut() {
+        for row in self.cells.iter_mut() {
             for col in row.iter_mut() {
                 *col = BrickType::None;
             }
         }
     }
 
+    /// Empties the rows from `y` up to the top of the buffer.
+    pub fn clear_rows_from(&mut self, y: usize) {
+        for column in self.cells.iter_mut() {
+            for cell in column.iter_mut().skip(y) {
+                *cell = BrickType::None;
+            }
+        }
+    }
+
     pub fn is_line_empty(&self, y: usize) -> bool {
-        let len = self.0.len();
+        let len = self.cells.len();
         for x in 0..len {
-            if self.0[x][y] != BrickType::None {
+            if self.cells[x][y] != BrickType::None {
                 return false;
             }
         }
-        return true;
+        true
+    }
+
+    /// Whether the line has garbage cells, i.e. it was not built by the player alone.
+    pub fn is_garbage_line(&self, y: usize) -> bool {
+        self.cells
+            .iter()
+            .any(|column| column[y] == BrickType::Garbage)
     }
 
     pub fn is_line_full(&self, y: usize) -> bool {
-        let len: usize = self.0.len();
+        let len: usize = self.cells.len();
         for x in 0..len {
-            if self.0[x][y] == BrickType::None {
+            if self.cells[x][y] == BrickType::None {
                 return false;
             }
         }
-        return true;
+        true
     }
 
     pub fn get_full_lines(&self) -> Vec<usize> {
         let mut vec = Vec::with_capacity(4);
-        let len_y = self.0[0].len();
+        let len_y = self.cells[0].len();
         // must save it from big to small, because we must clear the lines from top to bottom.
         for y in (0..len_y).rev() {
             if self.is_line_full(y) {
@@ -119,17 +204,17 @@ impl Board {
     }
 
     pub fn clean_one_line(&mut self, y_pos: usize) {
-        let len: usize = self.0.len();
-        let len_y: usize = self.0[0].len();
+        let len: usize = self.cells.len();
+        let len_y: usize = self.cells[0].len();
         for y in y_pos..len_y {
             if y > y_pos && self.is_line_empty(y) {
                 break;
             }
             for x in 0..len {
                 if y == len_y - 1 {
-                    self.0[x][y_pos] = BrickType::None;
+                    self.cells[x][y_pos] = BrickType::None;
                 } else {
-                    self.0[x][y] = self.0[x][y + 1];
+                    self.cells[x][y] = self.cells[x][y + 1];
                 }
             }
         }
@@ -281,13 +366,12 @@ impl Board {
             }
         }
 
-        if (brick.0 == BrickType::Z && brick.1[0] == Position::new(2, 2))
-            || (brick.0 == BrickType::S && brick.1[0] == Position::new(1, 2))
+        if ((brick.0 == BrickType::Z && brick.1[0] == Position::new(2, 2))
+            || (brick.0 == BrickType::S && brick.1[0] == Position::new(1, 2)))
+            && change_moving_position(0, -1)
         {
-            if change_moving_position(0, -1) {
-                return true;
-            }
+            return true;
         }
-        return false;
+        false
     }
 }
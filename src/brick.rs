@@ -1,8 +1,3 @@
-use std::collections::VecDeque;
-use std::sync::{Mutex, OnceLock};
-
-use rand::prelude::*;
-
 use crate::constants::BRICKS_MAP;
 use crate::position::Position;
 
@@ -15,58 +10,71 @@ pub enum BrickType {
     S,
     Z,
     T,
+    // the cells of the garbage lines
+    Garbage,
     None,
 }
 
-#[derive(Copy, Clone, Debug)]
-pub struct Brick(pub BrickType, pub [Position; 4]);
-
-impl Brick {
-    pub fn new(use_bag7: bool) -> Self {
-        let type_index: usize;
-        if use_bag7 {
-            // use 7 bag randomization algorithm
-            static S_BRICK_BAG_VEC: OnceLock<Mutex<VecDeque<usize>>> = OnceLock::new();
-            let brick_bag_vec = S_BRICK_BAG_VEC.get_or_init(|| {
-                let mut m = VecDeque::new();
-                m.reserve(7);
-                Mutex::new(m)
-            });
+impl BrickType {
+    /// All the playable brick types, in the order used by the randomizer.
+    pub const ALL: [BrickType; 7] = [
+        BrickType::O,
+        BrickType::I,
+        BrickType::J,
+        BrickType::L,
+        BrickType::S,
+        BrickType::Z,
+        BrickType::T,
+    ];
 
-            let mut data = brick_bag_vec.lock().unwrap();
-
-            if data.len() == 0 {
-                let mut items = vec![0, 1, 2, 3, 4, 5, 6];
-                let mut rng = thread_rng();
-                items.shuffle(&mut rng);
+    /// The character of a cell in the text boards, `.` for an empty cell and `G` for garbage.
+    pub fn to_char(&self) -> char {
+        match self {
+            BrickType::O => 'O',
+            BrickType::I => 'I',
+            BrickType::J => 'J',
+            BrickType::L => 'L',
+            BrickType::S => 'S',
+            BrickType::Z => 'Z',
+            BrickType::T => 'T',
+            BrickType::Garbage => 'G',
+            BrickType::None => '.',
+        }
+    }
 
-                for item in items {
-                    data.push_back(item);
-                }
-            }
-            type_index = data.pop_front().unwrap_or(0);
-        } else {
-            // use normal randomization algorithm
-            type_index = rand::thread_rng().gen_range(0..BRICKS_MAP.len());
+    pub fn from_char(c: char) -> Option<BrickType> {
+        match c.to_ascii_uppercase() {
+            'O' => Some(BrickType::O),
+            'I' => Some(BrickType::I),
+            'J' => Some(BrickType::J),
+            'L' => Some(BrickType::L),
+            'S' => Some(BrickType::S),
+            'Z' => Some(BrickType::Z),
+            'T' => Some(BrickType::T),
+            'G' | 'X' => Some(BrickType::Garbage),
+            '.' | '_' => Some(BrickType::None),
+            _ => None,
         }
+    }
+}
 
-        let brick_type = match type_index {
-            0 => BrickType::O,
-            1 => BrickType::I,
-            2 => BrickType::J,
-            3 => BrickType::L,
-            4 => BrickType::S,
-            5 => BrickType::Z,
-            6 => BrickType::T,
-            _ => BrickType::J,
-        };
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Brick(pub BrickType, pub [Position; 4]);
 
+impl Brick {
+    /// Creates a brick of the given type in its spawn rotation.
+    pub fn from_type(brick_type: BrickType) -> Self {
         let brick_kind: &Vec<Brick> = BRICKS_MAP.get(&brick_type).unwrap();
-        // choose a rotation type
-        // let rotate_index: usize = rand::thread_rng().gen_range(0..brick_kind.len());
         brick_kind[0]
     }
 
+    /// The cells of the board covered by the brick at `moving_pos`, see `Position::block_cells`.
+    pub fn cells(&self, moving_pos: Position, block_size: usize) -> impl Iterator<Item = Position> {
+        self.1
+            .into_iter()
+            .flat_map(move |pos| (pos + moving_pos).block_cells(block_size))
+    }
+
     pub fn rotate_right(&self) -> Self {
         self.rotate(true)
     }
@@ -74,15 +82,10 @@ impl Brick {
     fn rotate(&self, is_right: bool) -> Self {
         let brick_type = self.0;
         let brick_kind: &Vec<Brick> = BRICKS_MAP.get(&brick_type).unwrap();
-        let mut rotate_index = 0;
-
-        let rotate_size = brick_kind.len();
-        for ro_index in 0..rotate_size {
-            if brick_kind[ro_index].1 == self.1 {
-                rotate_index = ro_index;
-                break;
-            }
-        }
+        let rotate_index = brick_kind
+            .iter()
+            .position(|brick| brick.1 == self.1)
+            .unwrap_or(0);
 
         let rotate_len = brick_kind.len();
         if is_right {
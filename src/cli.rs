@@ -0,0 +1,268 @@
+use std::{io, path::Path};
+
+use crate::{
+    hud::HudConfigRes,
+    menu::GameSelectedLevel,
+    mode::{GameMode, GameOptions},
+    replay::invalid_data,
+    utils::AttackTable,
+};
+
+pub const USAGE: &str = "\
+Usage: tetris [options] [replay file]
+
+Options:
+  --mode <name>          start a game of this mode: marathon, 4-wide, 12x24, 20x40,
+                         cheese-10l, cheese-18l, cheese-100l, finesse, invisible, fading,
+                         big or zen
+  --difficulty <level>   easy, normal or hard
+  --level <n>            the level to start from
+  --seed <n>             the seed of the bricks and the garbage
+  --board <cols>x<rows>  the size of the board instead of the one of the mode
+  --config <file>        read the options from a file of `name = value` lines,
+                         the options on the command line take precedence
+  --replay <file>        play back a replay
+  --hud <items>          the items shown next to the board, separated by commas:
+                         time, pps, apm, combo, b2b, finesse and action
+  --attack-lines <list>  the garbage sent in versus by a single, double, triple and
+                         tetris, e.g. 0,1,2,4
+  --attack-tspins <list> the garbage sent by the T-spins of 1 to 4 lines, e.g. 2,4,6,6
+  --attack-back-to-back <n>
+                         the garbage added by a back-to-back clear
+  --attack-combos <list> the garbage added from no combo up, the last one for longer combos
+  --attack-perfect-clear <n>
+                         the garbage added by a perfect clear
+  --garbage-messiness <rate>
+                         0 to 1, how often the hole of the garbage received in versus
+                         moves from a line to the next
+  --window <w>x<h>       the size of the window
+  --fullscreen           start in fullscreen
+  --help                 show this help";
+
+// the level stops rising at 99
+const MAX_START_LEVEL: usize = 99;
+// cheese races need room above their garbage
+const MIN_BOARD_SIZE: (usize, usize) = (4, 12);
+const MAX_BOARD_SIZE: (usize, usize) = (40, 80);
+
+/// The options given on the command line or in a config file.
+#[derive(Clone, Debug, Default)]
+pub struct CliArgs {
+    pub mode: Option<GameMode>,
+    pub difficulty: Option<GameSelectedLevel>,
+    pub start_level: Option<usize>,
+    pub seed: Option<u64>,
+    pub board_size: Option<(usize, usize)>,
+    pub replay: Option<String>,
+    pub hud: Option<HudConfigRes>,
+    // the rules of the versus matches, None for the default ones
+    pub attack_table: Option<AttackTable>,
+    pub garbage_messiness: Option<f64>,
+    pub window_size: Option<(f32, f32)>,
+    pub fullscreen: bool,
+    pub help: bool,
+}
+
+impl CliArgs {
+    /// Parses the arguments, without the program name. The config file is read first,
+    /// so the other arguments override it.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> io::Result<Self> {
+        let mut pairs = Vec::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let Some(name) = arg.strip_prefix("--") else {
+                // a bare path is a replay, as before the options existed
+                pairs.push(("replay".to_string(), Some(arg)));
+                continue;
+            };
+            let value = if matches!(name, "fullscreen" | "help") {
+                None
+            } else {
+                let value = args
+                    .next()
+                    .ok_or_else(|| invalid_data(&format!("missing value of --{}", name)))?;
+                Some(value)
+            };
+            pairs.push((name.to_string(), value));
+        }
+
+        let mut cli_args = CliArgs::default();
+        for (_, path) in pairs.iter().filter(|(name, _)| name == "config") {
+            cli_args.load_config(path.as_deref().unwrap_or_default())?;
+        }
+        for (name, value) in pairs.iter().filter(|(name, _)| name != "config") {
+            cli_args.set(name, value.as_deref())?;
+        }
+        Ok(cli_args)
+    }
+
+    fn load_config(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        let text = std::fs::read_to_string(&path).map_err(|err| {
+            io::Error::new(
+                err.kind(),
+                format!("config {}: {}", path.as_ref().display(), err),
+            )
+        })?;
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, value) = line
+                .split_once('=')
+                .ok_or_else(|| invalid_data(&format!("not a `name = value` line: {}", line)))?;
+            let (name, value) = (name.trim(), value.trim());
+            match name {
+                "fullscreen" => self.fullscreen = parse_bool(value)?,
+                _ => self.set(name, Some(value))?,
+            }
+        }
+        Ok(())
+    }
+
+    fn set(&mut self, name: &str, value: Option<&str>) -> io::Result<()> {
+        let value = value.unwrap_or_default();
+        match name {
+            "mode" => self.mode = Some(parse_mode(value)?),
+            "difficulty" => self.difficulty = Some(parse_difficulty(value)?),
+            "level" => {
+                let level = parse_number(value)?;
+                if !(1..=MAX_START_LEVEL).contains(&level) {
+                    return Err(invalid_data(&format!(
+                        "level must be 1 to {}",
+                        MAX_START_LEVEL
+                    )));
+                }
+                self.start_level = Some(level);
+            }
+            "seed" => self.seed = Some(parse_number(value)?),
+            "board" => {
+                let (columns, rows) = parse_size(value)?;
+                if !(MIN_BOARD_SIZE.0..=MAX_BOARD_SIZE.0).contains(&columns)
+                    || !(MIN_BOARD_SIZE.1..=MAX_BOARD_SIZE.1).contains(&rows)
+                {
+                    return Err(invalid_data(&format!(
+                        "board must be {}x{} to {}x{}",
+                        MIN_BOARD_SIZE.0, MIN_BOARD_SIZE.1, MAX_BOARD_SIZE.0, MAX_BOARD_SIZE.1
+                    )));
+                }
+                self.board_size = Some((columns, rows));
+            }
+            "replay" => self.replay = Some(value.to_string()),
+            "hud" => self.hud = Some(HudConfigRes::parse(value)?),
+            "attack-lines" => self.attack_table().lines = parse_four(value)?,
+            "attack-tspins" => self.attack_table().tspin_lines = parse_four(value)?,
+            "attack-back-to-back" => self.attack_table().back_to_back = parse_number(value)?,
+            "attack-combos" => {
+                let combo = parse_list(value)?;
+                if combo.is_empty() {
+                    return Err(invalid_data("attack-combos must not be empty"));
+                }
+                self.attack_table().combo = combo;
+            }
+            "attack-perfect-clear" => self.attack_table().perfect_clear = parse_number(value)?,
+            "garbage-messiness" => {
+                let messiness = parse_number(value)?;
+                if !(0. ..=1.).contains(&messiness) {
+                    return Err(invalid_data("garbage-messiness must be 0 to 1"));
+                }
+                self.garbage_messiness = Some(messiness);
+            }
+            "window" => {
+                let (width, height) = parse_size(value)?;
+                if width == 0 || height == 0 {
+                    return Err(invalid_data("window size must not be 0"));
+                }
+                self.window_size = Some((width as f32, height as f32));
+            }
+            "fullscreen" => self.fullscreen = true,
+            "help" => self.help = true,
+            _ => return Err(invalid_data(&format!("unknown option --{}", name))),
+        }
+        Ok(())
+    }
+
+    // the attack table being set, from the default one
+    fn attack_table(&mut self) -> &mut AttackTable {
+        self.attack_table.get_or_insert_with(AttackTable::default)
+    }
+
+    /// Whether a game starts directly, instead of the main menu.
+    pub fn starts_game(&self) -> bool {
+        self.mode.is_some()
+            || self.difficulty.is_some()
+            || self.start_level.is_some()
+            || self.seed.is_some()
+            || self.board_size.is_some()
+    }
+
+    pub fn game_options(&self) -> GameOptions {
+        GameOptions {
+            start_level: self.start_level.unwrap_or(1),
+            board_size: self.board_size,
+        }
+    }
+}
+
+// the names are compared without case, spaces and dashes, so "cheese-10l" is "Cheese 10L"
+fn parse_mode(value: &str) -> io::Result<GameMode> {
+    let normalize = |name: &str| {
+        name.chars()
+            .filter(char::is_ascii_alphanumeric)
+            .collect::<String>()
+            .to_ascii_lowercase()
+    };
+    GameMode::ALL
+        .into_iter()
+        .find(|mode| normalize(mode.name()) == normalize(value))
+        .ok_or_else(|| invalid_data(&format!("unknown mode {}", value)))
+}
+
+fn parse_difficulty(value: &str) -> io::Result<GameSelectedLevel> {
+    match value.to_ascii_lowercase().as_str() {
+        "easy" => Ok(GameSelectedLevel::Easy),
+        "normal" => Ok(GameSelectedLevel::Normal),
+        "hard" => Ok(GameSelectedLevel::Hard),
+        _ => Err(invalid_data(&format!("unknown difficulty {}", value))),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> io::Result<T> {
+    value
+        .parse()
+        .map_err(|_| invalid_data(&format!("invalid number {}", value)))
+}
+
+// numbers separated by commas, e.g. "0,1,2,4"
+fn parse_list(value: &str) -> io::Result<Vec<usize>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|number| !number.is_empty())
+        .map(parse_number)
+        .collect()
+}
+
+fn parse_four(value: &str) -> io::Result<[usize; 4]> {
+    parse_list(value)?
+        .try_into()
+        .map_err(|_| invalid_data(&format!("expected 4 numbers, got {}", value)))
+}
+
+fn parse_size(value: &str) -> io::Result<(usize, usize)> {
+    let (width, height) = value.split_once('x').ok_or_else(|| {
+        invalid_data(&format!(
+            "invalid size {}, expected <width>x<height>",
+            value
+        ))
+    })?;
+    Ok((parse_number(width)?, parse_number(height)?))
+}
+
+fn parse_bool(value: &str) -> io::Result<bool> {
+    match value {
+        "true" | "yes" | "1" => Ok(true),
+        "false" | "no" | "0" => Ok(false),
+        _ => Err(invalid_data(&format!("invalid boolean {}", value))),
+    }
+}
@@ -0,0 +1,60 @@
+use bevy::prelude::*;
+
+use crate::{
+    fumen::BoardSetup,
+    game::{GameEngineRes, GameSystemSet},
+    replay::{LastReplayRes, ReplayRecorderRes},
+    GameState,
+};
+
+const COPY_KEY: KeyCode = KeyCode::C;
+const PASTE_KEY: KeyCode = KeyCode::V;
+
+/// Copies the board as a fumen and starts again from a pasted fumen or text board.
+pub struct ClipboardPlugin;
+
+impl Plugin for ClipboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            clipboard_system
+                .in_set(GameSystemSet::Events)
+                .run_if(in_state(GameState::Game)),
+        );
+    }
+}
+
+fn clipboard_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut engine: ResMut<GameEngineRes>,
+    mut replay_recorder: ResMut<ReplayRecorderRes>,
+    mut last_replay: ResMut<LastReplayRes>,
+) {
+    if keyboard_input.just_pressed(COPY_KEY) {
+        let setup = BoardSetup::from_engine(&engine.0);
+        // the boards which do not fit into a fumen are copied as text
+        let text = setup.to_fumen().unwrap_or_else(|_| setup.to_text());
+        match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(&text)) {
+            Ok(()) => info!("copied the board: {}", text),
+            Err(err) => warn!("failed to copy the board: {}", err),
+        }
+    }
+    if keyboard_input.just_pressed(PASTE_KEY) {
+        let text = match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_text()) {
+            Ok(text) => text,
+            Err(err) => {
+                warn!("failed to paste a board: {}", err);
+                return;
+            }
+        };
+        let loaded = BoardSetup::parse(&text).and_then(|setup| engine.0.load_setup(&setup));
+        match loaded {
+            Ok(()) => {
+                // the replay could not play the pasted board again
+                replay_recorder.0 = None;
+                last_replay.0 = None;
+            }
+            Err(err) => warn!("failed to paste a board: {}", err),
+        }
+    }
+}
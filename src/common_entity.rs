@@ -1,18 +1,35 @@
+use bevy::{
+    prelude::{
+        default, AssetServer, BuildChildren, Bundle, ButtonBundle, ChildBuilder, ImageBundle, Res,
+        TextBundle,
+    },
+    text::TextStyle,
+    ui::{AlignItems, JustifyContent, PositionType, Style, UiImage, UiRect, Val},
+};
 
-use bevy::{prelude::{Bundle, AssetServer, Res, default, Color, ChildBuilder, ButtonBundle, BuildChildren, ImageBundle, TextBundle}, ui::{Style, Val, UiRect, JustifyContent, AlignItems, PositionType, UiImage}, text::TextStyle};
-
-
-const TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
-const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
-
+use crate::theme::ThemeRes;
 
 pub trait EntitySpawner {
-    fn spawn_button(&mut self, bundle: impl Bundle, icon_image_path: &'static str, title: &str, asset_server: &Res<AssetServer>);
+    fn spawn_button(
+        &mut self,
+        bundle: impl Bundle,
+        icon_image_path: &'static str,
+        title: &str,
+        asset_server: &Res<AssetServer>,
+        theme_res: &ThemeRes,
+    );
 }
 
 impl EntitySpawner for ChildBuilder<'_, '_, '_> {
-    fn spawn_button(&mut self, bundle: impl Bundle, icon_image_path: &'static str, title: &str, asset_server: &Res<AssetServer>) {
-        let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    fn spawn_button(
+        &mut self,
+        bundle: impl Bundle,
+        icon_image_path: &'static str,
+        title: &str,
+        asset_server: &Res<AssetServer>,
+        theme_res: &ThemeRes,
+    ) {
+        let ui = theme_res.theme().ui;
         let button_style = Style {
             width: Val::Px(250.0),
             height: Val::Px(65.0),
@@ -28,27 +45,27 @@ impl EntitySpawner for ChildBuilder<'_, '_, '_> {
             ..default()
         };
         let button_text_style = TextStyle {
-            font: font.clone(),
+            font: theme_res.font(),
             font_size: 40.0,
-            color: TEXT_COLOR,
+            color: ui.text,
         };
-        
+
         self.spawn((
-                            ButtonBundle {
-                                style: button_style,
-                                background_color: NORMAL_BUTTON.into(),
-                                ..default()
-                            },
-                            bundle,
-                        ))
-                        .with_children(|parent| {
-                            let icon = asset_server.load(icon_image_path);
-                            parent.spawn(ImageBundle {
-                                style: button_icon_style,
-                                image: UiImage::new(icon),
-                                ..default()
-                            });
-                            parent.spawn(TextBundle::from_section(title, button_text_style));
-                        });
+            ButtonBundle {
+                style: button_style,
+                background_color: ui.button.into(),
+                ..default()
+            },
+            bundle,
+        ))
+        .with_children(|parent| {
+            let icon = asset_server.load(icon_image_path);
+            parent.spawn(ImageBundle {
+                style: button_icon_style,
+                image: UiImage::new(icon),
+                ..default()
+            });
+            parent.spawn(TextBundle::from_section(title, button_text_style));
+        });
     }
-}
\ No newline at end of file
+}
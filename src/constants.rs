@@ -1,17 +1,16 @@
-
+use crate::{brick::*, position::Position};
 use bevy::prelude::Color;
 use lazy_static::*;
 use std::collections::HashMap;
-use crate::{brick::*, position::Position};
 
 pub const WINDOW_WIDTH: f32 = 1100.;
 pub const WINDOW_HEIGHT: f32 = 800.;
 
-pub(crate) const BOARD_VIEW_X: usize = 10;
-pub(crate) const BOARD_VIEW_Y: usize = 20;
+pub(crate) const DEFAULT_BOARD_WIDTH: usize = 10;
+pub(crate) const DEFAULT_BOARD_HEIGHT: usize = 20; // board is 10x20
 
-pub(crate) const BOARD_X: usize = 10;
-pub(crate) const BOARD_Y: usize = 23; // board is 10x20
+// the vanish zone above the visible board is as tall as the board, e.g. a 40 rows buffer for 10x20
+pub(crate) const BOARD_BUFFER_FACTOR: usize = 2;
 
 lazy_static! {
 
@@ -67,5 +66,3 @@ pub const BACKGROUND_COLOR: Color = Color::rgb(0.61, 0.7, 0.71);
 
 pub const TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
 pub const GAME_DATA_TEXT_COLOR: Color = Color::rgb(0., 0.22, 0.76);
-
-
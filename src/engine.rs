@@ -0,0 +1,824 @@
+use std::io;
+
+use crate::{
+    board::Board,
+    brick::{Brick, BrickType},
+    finesse::{FinessePath, FinesseTracker},
+    fumen::BoardSetup,
+    garbage::{GarbageGenerator, MESSY_HOLE_CHANGE_RATE},
+    menu::GameSelectedLevel,
+    mode::{GameMode, GameOptions},
+    position::Position,
+    puzzle::{Puzzle, PuzzleGoal},
+    randomizer::Randomizer,
+    replay::invalid_data,
+    utils::{get_gravity, get_level, get_score, MAX_GRAVITY},
+};
+
+pub const TICKS_PER_SECOND: u32 = 60;
+pub(crate) const TICK_SECONDS: f32 = 1. / TICKS_PER_SECOND as f32;
+
+// the harder modes fall as fast as this many levels higher
+pub(crate) const EASY_GRAVITY_LEVEL_OFFSET: usize = 0;
+pub(crate) const NORMAL_GRAVITY_LEVEL_OFFSET: usize = 2;
+pub(crate) const HARD_GRAVITY_LEVEL_OFFSET: usize = 4;
+pub(crate) const SOFT_DROP_GRAVITY: f32 = 1. / 6.; // soft drop falls at least one row every 0.1s
+pub(crate) const LOCK_DELAY_TICKS: u32 = 30; // a grounded brick locks after at least 0.5s
+pub(crate) const CHEESE_ROWS: usize = 10; // the garbage lines of a cheese race on the board at once
+pub(crate) const FINESSE_TRAINER_PIECES: usize = 40; // the bricks placed in a finesse training
+
+/// Everything the player can do in one tick.
+/// Each action is a single bit, so the input of a tick fits in one byte.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputAction {
+    MoveLeft = 0,
+    MoveRight = 1,
+    Rotate = 2,
+    HardDrop = 3,
+    SoftDropPressed = 4,
+    SoftDropReleased = 5,
+    // ends a zen session, the other modes ignore it
+    EndSession = 6,
+}
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct InputFrame(pub u8);
+
+impl InputFrame {
+    pub fn insert(&mut self, action: InputAction) {
+        self.0 |= 1 << action as u8;
+    }
+
+    pub fn contains(&self, action: InputAction) -> bool {
+        self.0 & (1 << action as u8) != 0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClearKind {
+    Normal,
+    TSpin,
+}
+
+/// A line clear, with the details needed for scoring and statistics.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineClear {
+    pub count: usize,
+    pub kind: ClearKind,
+    pub perfect_clear: bool,
+    // number of consecutive clearing bricks before this one
+    pub combo: usize,
+    // a tetris or T-spin clear right after another one
+    pub back_to_back: bool,
+}
+
+impl LineClear {
+    /// Tetrises and T-spin clears keep the back-to-back chain alive.
+    pub fn is_difficult(&self) -> bool {
+        self.count == 4 || (self.is_tspin() && self.count > 0)
+    }
+
+    pub fn is_tspin(&self) -> bool {
+        self.kind == ClearKind::TSpin
+    }
+}
+
+/// What happened during a tick, drained by the frontends after stepping the engine.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EngineEvent {
+    // a tick was played with this input, sent before the other events of the tick
+    Ticked { tick: u64, input: InputFrame },
+    PieceSpawned(BrickType),
+    // the player moved the piece left or right
+    PieceMoved(Position),
+    PieceRotated(Brick),
+    // the brick locked at this position
+    PieceLocked(Brick, Position),
+    // the brick was placed with more key presses than needed
+    FinesseFault { presses: usize, expected: usize },
+    LinesCleared(LineClear),
+    LevelUp(usize),
+    GameOver(TopOut),
+    // the goal of the mode was reached, which also ends the game
+    GoalReached,
+}
+
+/// The guideline top out conditions which end the game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TopOut {
+    // the next brick overlaps the stack when it spawns
+    Block,
+    // a brick locked completely inside the vanish zone
+    Lock,
+    // garbage pushed the stack out of the top of the buffer
+    Garbage,
+    // a puzzle ran out of bricks before its goal was met
+    OutOfPieces,
+}
+
+impl TopOut {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TopOut::Block => "Block Out",
+            TopOut::Lock => "Lock Out",
+            TopOut::Garbage => "Top Out",
+            TopOut::OutOfPieces => "Out of Pieces",
+        }
+    }
+
+    /// The id of its name in the texts of the screens.
+    pub fn message_id(&self) -> &'static str {
+        match self {
+            TopOut::Block => "topout-block",
+            TopOut::Lock => "topout-lock",
+            TopOut::Garbage => "topout-garbage",
+            TopOut::OutOfPieces => "topout-out-of-pieces",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ActiveBrick {
+    pub brick: Brick,
+    pub pos: Position,
+    // when the brick fast dropped down, it become non movable.
+    pub movable: bool,
+    // the last successful move was a rotation, needed to detect T-spins
+    pub rotated_last: bool,
+}
+
+/// The tetris rules, without any rendering.
+/// The engine only advances through `step`, one fixed tick at a time,
+/// so feeding it the same seed and inputs always gives the same game.
+#[derive(Clone)]
+pub struct GameEngine {
+    pub board: Board,
+    pub active: Option<ActiveBrick>,
+    pub next: Brick,
+    pub tick: u64,
+    pub level: usize,
+    pub score: usize,
+    pub lines: usize,
+    // the bricks spawned so far, including the falling one
+    pub pieces: usize,
+    // set when the game is over
+    pub top_out: Option<TopOut>,
+    pub goal_reached: bool,
+    randomizer: Randomizer,
+    garbage: GarbageGenerator,
+    // the garbage lines of a cheese race which were not dug yet, None in the other modes
+    garbage_remaining: Option<usize>,
+    // the garbage lines of a cheese race which were not raised yet
+    garbage_queued: usize,
+    // the board is emptied after every brick
+    finesse_trainer: bool,
+    // how long the locked blocks are shown, None when they are never hidden
+    stack_visible_ticks: Option<u64>,
+    // the times the stack topped out in the zen mode, None in the modes which end there
+    zen_resets: Option<usize>,
+    finesse: FinesseTracker,
+    // the goal of a puzzle and its bricks which were not spawned yet
+    puzzle_goal: Option<PuzzleGoal>,
+    pieces_left: Option<usize>,
+    gravity_level_offset: usize,
+    // the level never goes below it, whatever the lines cleared
+    start_level: usize,
+    // the fraction of a row the brick has fallen, in rows
+    gravity_rows: f32,
+    soft_drop: bool,
+    // how long the brick has been lying on the stack
+    lock_ticks: u32,
+    // consecutive clearing bricks, None when the last brick cleared nothing
+    combo: Option<usize>,
+    // the last clear was difficult, so the next difficult one is back-to-back
+    back_to_back_ready: bool,
+    // an id for every row of the board, which follows the row when the rows below it are cleared
+    row_ids: Vec<u64>,
+    next_row_id: u64,
+    events: Vec<EngineEvent>,
+    dirty: bool,
+}
+
+impl GameEngine {
+    pub fn new(difficulty: GameSelectedLevel, mode: GameMode, seed: u64) -> Self {
+        GameEngine::with_options(difficulty, mode, seed, GameOptions::default())
+    }
+
+    pub fn with_options(
+        difficulty: GameSelectedLevel,
+        mode: GameMode,
+        seed: u64,
+        options: GameOptions,
+    ) -> Self {
+        let gravity_level_offset = match difficulty {
+            GameSelectedLevel::Easy => EASY_GRAVITY_LEVEL_OFFSET,
+            GameSelectedLevel::Normal => NORMAL_GRAVITY_LEVEL_OFFSET,
+            GameSelectedLevel::Hard => HARD_GRAVITY_LEVEL_OFFSET,
+        };
+        let use_bag7 = difficulty != GameSelectedLevel::Hard;
+        let mut randomizer = Randomizer::new(seed, use_bag7);
+        let next = randomizer.next_brick();
+        let (width, height) = options.board_size(mode);
+        let garbage = GarbageGenerator::new(seed, width, MESSY_HOLE_CHANGE_RATE);
+        let mut engine = GameEngine {
+            board: Board::new(width, height).with_block_size(mode.block_size()),
+            active: None,
+            next,
+            tick: 0,
+            level: options.start_level,
+            score: 0,
+            lines: 0,
+            pieces: 0,
+            top_out: None,
+            goal_reached: false,
+            randomizer,
+            garbage,
+            garbage_remaining: mode.garbage_lines(),
+            garbage_queued: mode.garbage_lines().unwrap_or(0),
+            finesse_trainer: mode == GameMode::Finesse,
+            stack_visible_ticks: mode.stack_visible_ticks(),
+            zen_resets: (mode == GameMode::Zen).then_some(0),
+            finesse: FinesseTracker::default(),
+            puzzle_goal: None,
+            pieces_left: None,
+            gravity_level_offset,
+            start_level: options.start_level,
+            gravity_rows: 0.,
+            soft_drop: false,
+            lock_ticks: 0,
+            combo: None,
+            back_to_back_ready: false,
+            row_ids: Vec::new(),
+            next_row_id: 0,
+            events: Vec::new(),
+            dirty: true,
+        };
+        engine.renumber_rows();
+        engine.refill_cheese();
+        engine
+    }
+
+    /// Starts a puzzle: its board, then its bricks in order.
+    pub fn from_puzzle(difficulty: GameSelectedLevel, puzzle: &Puzzle) -> Self {
+        let mut engine = GameEngine::new(difficulty, GameMode::Marathon, 0);
+        engine.board = puzzle.board.clone();
+        engine.renumber_rows();
+        engine.randomizer.queue(puzzle.pieces.iter().copied());
+        engine.next = engine.randomizer.next_brick();
+        engine.garbage = GarbageGenerator::new(0, engine.board.width(), MESSY_HOLE_CHANGE_RATE);
+        engine.puzzle_goal = Some(puzzle.goal);
+        engine.pieces_left = Some(puzzle.pieces.len());
+        engine
+    }
+
+    /// Replaces the board and the coming bricks, starting with the falling one of the setup.
+    /// Fails when the stack does not fit on the board of the game.
+    pub fn load_setup(&mut self, setup: &BoardSetup) -> io::Result<()> {
+        let mut board = Board::new(self.board.width(), self.board.visible_height())
+            .with_block_size(self.board.block_size());
+        for y in 0..setup.board.height() {
+            for x in 0..setup.board.width() {
+                let cell = setup.board.get(x, y);
+                if cell == BrickType::None {
+                    continue;
+                }
+                if x >= board.width() || y >= board.height() {
+                    return Err(invalid_data("the board does not fit"));
+                }
+                board.set(x, y, cell);
+            }
+        }
+        self.board = board;
+        self.renumber_rows();
+        let current = setup.current.map(|(brick, _)| brick.0);
+        self.randomizer
+            .queue(current.into_iter().chain(setup.queue.iter().copied()));
+        self.next = self.randomizer.next_brick();
+        self.active = None;
+        self.soft_drop = false;
+        self.gravity_rows = 0.;
+        self.lock_ticks = 0;
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Returns whether the state changed since the last call, used to redraw the board lazily.
+    pub fn take_dirty(&mut self) -> bool {
+        std::mem::replace(&mut self.dirty, false)
+    }
+
+    /// Draws the state again, e.g. once the window was laid out again.
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    /// The current combo, None when the last brick cleared nothing.
+    pub fn combo(&self) -> Option<usize> {
+        self.combo
+    }
+
+    /// Whether the next tetris or T-spin clear will be back-to-back.
+    pub fn back_to_back(&self) -> bool {
+        self.back_to_back_ready
+    }
+
+    /// Takes the events emitted since the last call.
+    pub fn drain_events(&mut self) -> std::vec::Drain<'_, EngineEvent> {
+        self.events.drain(..)
+    }
+
+    pub fn is_finesse_trainer(&self) -> bool {
+        self.finesse_trainer
+    }
+
+    /// How many ticks the locked blocks stay visible in the invisible and fading modes.
+    pub fn stack_visible_ticks(&self) -> Option<u64> {
+        self.stack_visible_ticks
+    }
+
+    /// How many times the stack topped out and was cut down in the zen mode.
+    pub fn zen_resets(&self) -> Option<usize> {
+        self.zen_resets
+    }
+
+    // a zen session has no game over of its own, the player ends it
+    fn end_session(&mut self) {
+        if self.zen_resets.is_none() || self.is_game_over() {
+            return;
+        }
+        self.goal_reached = true;
+        self.active = None;
+        self.dirty = true;
+        self.events.push(EngineEvent::GoalReached);
+    }
+
+    pub fn puzzle_goal(&self) -> Option<PuzzleGoal> {
+        self.puzzle_goal
+    }
+
+    /// The bricks of a puzzle which were not spawned yet, the next one included.
+    pub fn pieces_left(&self) -> Option<usize> {
+        self.pieces_left
+    }
+
+    /// The garbage lines left to dig in a cheese race.
+    pub fn garbage_remaining(&self) -> Option<usize> {
+        self.garbage_remaining
+    }
+
+    /// The bricks placed with more key presses than needed.
+    pub fn finesse_faults(&self) -> usize {
+        self.finesse.faults
+    }
+
+    /// The placements the falling brick can reach from its spawn, with their shortest inputs.
+    pub fn finesse_paths(&self) -> &[FinessePath] {
+        self.finesse.paths()
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.top_out.is_some() || self.goal_reached
+    }
+
+    /// The ids of the rows of the board, from the bottom one. A row keeps its id while the
+    /// line clears and the garbage move it, so the frontends can follow its blocks.
+    pub fn row_ids(&self) -> &[u64] {
+        &self.row_ids
+    }
+
+    // new ids for all the rows, when the board is replaced
+    fn renumber_rows(&mut self) {
+        self.row_ids.clear();
+        self.add_top_rows();
+    }
+
+    // the board has rows without an id at its top, after the rows below them were removed
+    fn add_top_rows(&mut self) {
+        while self.row_ids.len() < self.board.height() {
+            self.row_ids.push(self.next_row_id);
+            self.next_row_id += 1;
+        }
+    }
+
+    /// Sets how messy the received garbage is, see `GarbageGenerator`.
+    pub fn set_garbage_hole_change_rate(&mut self, hole_change_rate: f64) {
+        self.garbage.set_hole_change_rate(hole_change_rate);
+    }
+
+    /// Raises `count` garbage rows from the bottom of the board, pushing the stack up.
+    pub fn receive_garbage(&mut self, count: usize) {
+        if self.is_game_over() || count == 0 {
+            return;
+        }
+        let holes = self.garbage.next_holes(count);
+        let in_buffer = self.board.push_garbage(&holes);
+        let raised = holes.len().min(self.row_ids.len());
+        self.row_ids.truncate(self.row_ids.len() - raised);
+        let new_ids = self.next_row_id..self.next_row_id + raised as u64;
+        self.row_ids.splice(0..0, new_ids);
+        self.next_row_id += raised as u64;
+        self.dirty = true;
+        // the falling brick is pushed up with the stack when they overlap
+        if let Some(mut active) = self.active {
+            while !self.board.is_valid_brick(&active.brick, &active.pos)
+                && active.pos.y < self.board.height() as i32
+            {
+                active.pos.up_assign();
+            }
+            self.active = Some(active);
+            if !self.board.is_valid_brick(&active.brick, &active.pos) {
+                self.top_out(TopOut::Garbage);
+                return;
+            }
+        }
+        if !in_buffer {
+            self.top_out(TopOut::Garbage);
+        }
+    }
+
+    /// Advances the game by one tick, applying the input pressed during this tick.
+    pub fn step(&mut self, input: InputFrame) {
+        if self.is_game_over() {
+            return;
+        }
+        self.tick += 1;
+        self.events.push(EngineEvent::Ticked {
+            tick: self.tick,
+            input,
+        });
+
+        if input.contains(InputAction::EndSession) {
+            self.end_session();
+            if self.is_game_over() {
+                return;
+            }
+        }
+        if input.contains(InputAction::SoftDropPressed) {
+            self.soft_drop = true;
+        }
+        if input.contains(InputAction::SoftDropReleased) {
+            self.soft_drop = false;
+        }
+        // in the initial state, no moving brick exists, so create a new one
+        if self.active.is_none() && !self.spawn_next_brick() {
+            return;
+        }
+
+        self.apply_gravity();
+        if self.is_game_over() {
+            return;
+        }
+
+        if self.active.is_some_and(|active| active.movable) {
+            self.finesse.pressed(input);
+        }
+        if input.contains(InputAction::Rotate) {
+            self.rotate();
+        }
+        if input.contains(InputAction::HardDrop) {
+            self.hard_drop();
+        }
+        if input.contains(InputAction::MoveLeft) {
+            self.shift(true);
+        } else if input.contains(InputAction::MoveRight) {
+            self.shift(false);
+        }
+        // in 20G the brick never floats, even right after moving it
+        if self.gravity() >= MAX_GRAVITY {
+            self.sonic_drop();
+        }
+    }
+
+    /// The current gravity, in G (rows per tick).
+    pub fn gravity(&self) -> f32 {
+        let gravity = get_gravity(self.gravity_level());
+        if self.soft_drop {
+            gravity.max(SOFT_DROP_GRAVITY)
+        } else {
+            gravity
+        }
+    }
+
+    // a grounded brick locks after the time it takes to fall one row, but never faster than the lock delay
+    fn lock_delay(&self) -> u32 {
+        let gravity = get_gravity(self.gravity_level());
+        ((1. / gravity).ceil() as u32).max(LOCK_DELAY_TICKS)
+    }
+
+    // the zen mode keeps the gravity of its start level
+    fn gravity_level(&self) -> usize {
+        let level = if self.zen_resets.is_some() {
+            self.start_level
+        } else {
+            self.level
+        };
+        level + self.gravity_level_offset
+    }
+
+    fn apply_gravity(&mut self) {
+        let Some(mut active) = self.active else {
+            return;
+        };
+
+        self.gravity_rows += self.gravity();
+        let rows = self.gravity_rows as usize;
+        self.gravity_rows -= rows as f32;
+        for _ in 0..rows {
+            let next_pos = active.pos.down();
+            if !self.board.is_valid_brick(&active.brick, &next_pos) {
+                break;
+            }
+            // do the falling down
+            active.pos = next_pos;
+            active.rotated_last = false;
+            self.active = Some(active);
+            self.dirty = true;
+        }
+
+        if self.board.is_valid_brick(&active.brick, &active.pos.down()) {
+            self.lock_ticks = 0;
+            return;
+        }
+        self.lock_ticks += 1;
+        // hard dropped bricks lock at once
+        if !active.movable || self.lock_ticks >= self.lock_delay() {
+            self.lock(active);
+        }
+    }
+
+    fn lock(&mut self, active: ActiveBrick) {
+        // make the brick occupy the board.
+        let kind = if self.is_tspin(&active) {
+            ClearKind::TSpin
+        } else {
+            ClearKind::Normal
+        };
+        self.board.occupy_brick(active.brick, active.pos);
+        self.active = None;
+        self.events
+            .push(EngineEvent::PieceLocked(active.brick, active.pos));
+        if let Some(fault) = self.finesse.locked(&active.brick, active.pos) {
+            self.events.push(EngineEvent::FinesseFault {
+                presses: fault.presses,
+                expected: fault.expected.len(),
+            });
+        }
+        let visible_height = self.board.visible_height() as i32;
+        if active
+            .brick
+            .cells(active.pos, self.board.block_size())
+            .all(|cell| cell.y >= visible_height)
+        {
+            self.top_out(TopOut::Lock);
+            return;
+        }
+        if self.finesse_trainer {
+            self.board.clear_board();
+            if self.pieces >= FINESSE_TRAINER_PIECES {
+                self.goal_reached = true;
+                self.events.push(EngineEvent::GoalReached);
+                return;
+            }
+        }
+        let full_lines = self.board.get_full_lines();
+        let garbage_lines = full_lines
+            .iter()
+            .filter(|y| self.board.is_garbage_line(**y))
+            .count();
+        // the lines of the big mode are cleared in pairs, which count as one
+        let cleaned_lines = self.board.clean_lines() / self.board.block_size();
+        // from the top one down, so the rows below keep their index
+        for y in full_lines {
+            self.row_ids.remove(y);
+        }
+        self.add_top_rows();
+        let mut puzzle_solved = false;
+
+        if cleaned_lines > 0 {
+            let combo = self.combo.map_or(0, |combo| combo + 1);
+            self.combo = Some(combo);
+            let mut clear = LineClear {
+                count: cleaned_lines,
+                kind,
+                perfect_clear: (0..self.board.height()).all(|y| self.board.is_line_empty(y)),
+                combo,
+                back_to_back: false,
+            };
+            clear.back_to_back = clear.is_difficult() && self.back_to_back_ready;
+            self.back_to_back_ready = clear.is_difficult();
+            self.events.push(EngineEvent::LinesCleared(clear));
+            puzzle_solved = self
+                .puzzle_goal
+                .is_some_and(|goal| goal.is_met(&clear, self.lines + cleaned_lines));
+        } else {
+            self.combo = None;
+            // a T-spin without lines scores nothing, but it is announced and counted
+            if kind == ClearKind::TSpin {
+                self.events.push(EngineEvent::LinesCleared(LineClear {
+                    count: 0,
+                    kind,
+                    perfect_clear: false,
+                    combo: 0,
+                    back_to_back: false,
+                }));
+            }
+        }
+
+        if cleaned_lines > 0 {
+            self.lines += cleaned_lines;
+            let level = get_level(self.lines).max(self.start_level);
+            if level > self.level {
+                self.events.push(EngineEvent::LevelUp(level));
+            }
+            self.level = level;
+            self.score += get_score(self.level, cleaned_lines);
+        }
+        if puzzle_solved {
+            self.goal_reached = true;
+            self.events.push(EngineEvent::GoalReached);
+            return;
+        }
+
+        if let Some(remaining) = self.garbage_remaining {
+            let remaining = remaining.saturating_sub(garbage_lines);
+            self.garbage_remaining = Some(remaining);
+            if remaining == 0 {
+                self.goal_reached = true;
+                self.events.push(EngineEvent::GoalReached);
+                return;
+            }
+            self.refill_cheese();
+        }
+
+        self.spawn_next_brick();
+    }
+
+    /// Raises the queued cheese race garbage until the board has `CHEESE_ROWS` garbage lines.
+    fn refill_cheese(&mut self) {
+        let Some(remaining) = self.garbage_remaining else {
+            return;
+        };
+        let on_board = remaining - self.garbage_queued;
+        let count = CHEESE_ROWS
+            .saturating_sub(on_board)
+            .min(self.garbage_queued);
+        self.garbage_queued -= count;
+        self.receive_garbage(count);
+    }
+
+    fn spawn_next_brick(&mut self) -> bool {
+        let brick = self.next;
+        let mut pos = create_brick_start_position(&self.board, &brick.0);
+        self.dirty = true;
+        // reset falling speed to default
+        self.soft_drop = false;
+        self.gravity_rows = 0.;
+        self.lock_ticks = 0;
+        if self.pieces_left == Some(0) {
+            self.top_out(TopOut::OutOfPieces);
+            return false;
+        }
+        if !self.board.is_valid_brick(&brick, &pos) {
+            self.top_out(TopOut::Block);
+            return false;
+        }
+        // in 20G the brick appears directly on the stack,
+        // otherwise it drops one row at once into the visible board when nothing is below
+        if self.gravity() >= MAX_GRAVITY {
+            pos = self.board.get_bottom_valid_brick_pos(&brick, &pos);
+        } else if self.board.is_valid_brick(&brick, &pos.down()) {
+            pos.down_assign();
+        }
+        self.active = Some(ActiveBrick {
+            brick,
+            pos,
+            movable: true,
+            rotated_last: false,
+        });
+        self.finesse.spawned(&self.board, brick, pos);
+        if let Some(left) = self.pieces_left.as_mut() {
+            *left -= 1;
+        }
+        self.next = self.randomizer.next_brick();
+        self.pieces += 1;
+        self.events.push(EngineEvent::PieceSpawned(brick.0));
+        true
+    }
+
+    fn top_out(&mut self, top_out: TopOut) {
+        // the zen mode goes on with the upper half of the board emptied
+        if let Some(resets) = &mut self.zen_resets {
+            *resets += 1;
+            let block_size = self.board.block_size();
+            let half = self.board.visible_height() / 2 / block_size * block_size;
+            self.board.clear_rows_from(half);
+            self.active = None;
+            self.dirty = true;
+            return;
+        }
+        self.top_out = Some(top_out);
+        self.active = None;
+        self.dirty = true;
+        self.events.push(EngineEvent::GameOver(top_out));
+    }
+
+    fn rotate(&mut self) {
+        let Some(mut active) = self.active.filter(|active| active.movable) else {
+            return;
+        };
+        let rotated_brick = active.brick.rotate_right();
+        if self
+            .board
+            .is_valid_brick_for_rotation(&rotated_brick, &mut active.pos)
+        {
+            active.brick = rotated_brick;
+            active.rotated_last = true;
+            self.active = Some(active);
+            self.events.push(EngineEvent::PieceRotated(rotated_brick));
+            self.dirty = true;
+        }
+    }
+
+    fn sonic_drop(&mut self) {
+        let Some(mut active) = self.active else {
+            return;
+        };
+        let bottom_pos = self
+            .board
+            .get_bottom_valid_brick_pos(&active.brick, &active.pos);
+        if bottom_pos != active.pos {
+            active.pos = bottom_pos;
+            active.rotated_last = false;
+            self.active = Some(active);
+            self.dirty = true;
+        }
+    }
+
+    fn hard_drop(&mut self) {
+        let Some(mut active) = self.active else {
+            return;
+        };
+        while self.board.is_valid_brick(&active.brick, &active.pos.down()) {
+            active.pos.down_assign();
+            active.rotated_last = false;
+        }
+        // hard dropped, then it cannot be moved again
+        active.movable = false;
+        self.active = Some(active);
+        self.dirty = true;
+    }
+
+    fn shift(&mut self, is_left_moving: bool) {
+        let Some(mut active) = self.active.filter(|active| active.movable) else {
+            return;
+        };
+        let next_pos = if is_left_moving {
+            active.pos.left()
+        } else {
+            active.pos.right()
+        };
+        if self.board.is_valid_brick(&active.brick, &next_pos) {
+            active.pos = next_pos;
+            active.rotated_last = false;
+            self.active = Some(active);
+            self.events.push(EngineEvent::PieceMoved(next_pos));
+            self.dirty = true;
+        }
+    }
+
+    /// 3-corner rule: a T brick locked right after a rotation,
+    /// with at least 3 of the 4 cells diagonal to its center blocked.
+    fn is_tspin(&self, active: &ActiveBrick) -> bool {
+        if active.brick.0 != BrickType::T || !active.rotated_last {
+            return false;
+        }
+        // every T rotation turns around the (1, 1) block
+        let center = active.pos + Position::new(1, 1);
+        let blocked_corners = [(-1, -1), (-1, 1), (1, -1), (1, 1)]
+            .into_iter()
+            .map(|(x, y)| center + Position::new(x, y))
+            .filter(|corner| !self.board.is_valid_position(corner))
+            .count();
+        blocked_corners >= 3
+    }
+}
+
+#[inline]
+pub fn create_brick_start_position(board: &Board, brick_type: &BrickType) -> Position {
+    // counted in blocks, like the positions of the bricks
+    let block_size = board.block_size() as i32;
+    let mut pos = Position {
+        x: ((board.width() / 2) as i32 - 2) / block_size,
+        y: board.visible_height() as i32 / block_size - 1,
+    };
+    // the bricks spawn in the two rows above the visible board
+    match brick_type {
+        BrickType::Z | BrickType::S => pos.y += 1,
+        _ => (),
+    }
+    pos
+}
@@ -0,0 +1,105 @@
+#![allow(dead_code)]
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    brick::{Brick, BrickType},
+    engine::{EngineEvent, InputFrame, LineClear, TopOut},
+    position::Position,
+};
+
+// Gameplay events sent while a game is played.
+// The HUD, the statistics and the replay recorder listen to them
+// instead of being called from the game loop.
+
+/// A tick of the game was played with this input, sent before the other events of the tick.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct GameTicked {
+    pub tick: u64,
+    pub input: InputFrame,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PieceSpawned(pub BrickType);
+
+/// The player moved the piece left or right, to this position.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PieceMoved(pub Position);
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PieceRotated(pub Brick);
+
+/// The brick locked at this position.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PieceLocked(pub Brick, pub Position);
+
+/// The brick was placed with more key presses than the expected ones.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct FinesseFault {
+    pub presses: usize,
+    pub expected: usize,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct LinesCleared(pub LineClear);
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct LevelUp(pub usize);
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct GameOver(pub TopOut);
+
+/// The goal of the mode was reached, e.g. all the garbage of a cheese race was dug.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct GoalReached;
+
+pub struct GameEventsPlugin;
+
+impl Plugin for GameEventsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GameTicked>()
+            .add_event::<PieceSpawned>()
+            .add_event::<PieceMoved>()
+            .add_event::<PieceRotated>()
+            .add_event::<PieceLocked>()
+            .add_event::<FinesseFault>()
+            .add_event::<LinesCleared>()
+            .add_event::<LevelUp>()
+            .add_event::<GameOver>()
+            .add_event::<GoalReached>();
+    }
+}
+
+/// Sends the events drained from the engine as typed bevy events.
+#[derive(SystemParam)]
+pub struct GameEventWriters<'w> {
+    ticked: EventWriter<'w, GameTicked>,
+    spawned: EventWriter<'w, PieceSpawned>,
+    moved: EventWriter<'w, PieceMoved>,
+    rotated: EventWriter<'w, PieceRotated>,
+    locked: EventWriter<'w, PieceLocked>,
+    finesse_fault: EventWriter<'w, FinesseFault>,
+    cleared: EventWriter<'w, LinesCleared>,
+    level_up: EventWriter<'w, LevelUp>,
+    game_over: EventWriter<'w, GameOver>,
+    goal_reached: EventWriter<'w, GoalReached>,
+}
+
+impl GameEventWriters<'_> {
+    pub fn send(&mut self, event: EngineEvent) {
+        match event {
+            EngineEvent::Ticked { tick, input } => self.ticked.send(GameTicked { tick, input }),
+            EngineEvent::PieceSpawned(brick_type) => self.spawned.send(PieceSpawned(brick_type)),
+            EngineEvent::PieceMoved(pos) => self.moved.send(PieceMoved(pos)),
+            EngineEvent::PieceRotated(brick) => self.rotated.send(PieceRotated(brick)),
+            EngineEvent::PieceLocked(brick, pos) => self.locked.send(PieceLocked(brick, pos)),
+            EngineEvent::FinesseFault { presses, expected } => {
+                self.finesse_fault.send(FinesseFault { presses, expected })
+            }
+            EngineEvent::LinesCleared(clear) => self.cleared.send(LinesCleared(clear)),
+            EngineEvent::LevelUp(level) => self.level_up.send(LevelUp(level)),
+            EngineEvent::GameOver(top_out) => self.game_over.send(GameOver(top_out)),
+            EngineEvent::GoalReached => self.goal_reached.send(GoalReached),
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use bevy::prelude::*;
+
+use crate::{
+    brick::BrickType,
+    engine::{GameEngine, TICKS_PER_SECOND},
+};
+
+// a line clear shows the whole stack this long
+const REVEAL_TICKS: u64 = TICKS_PER_SECOND as u64;
+// the blocks fade out over this long once their time is up
+const FADE_OUT_TICKS: u64 = TICKS_PER_SECOND as u64 / 2;
+
+/// When every block of the stack was locked, to hide the stack in the invisible and fading
+/// modes. The engine does not know how its board is drawn, so this follows its board from the
+/// frontend, after every tick.
+#[derive(Clone, Debug, Default)]
+pub struct StackFade {
+    width: usize,
+    // the tick every cell of the board was locked at, None when it is empty
+    locked_at: Vec<Option<u64>>,
+    // the ids of the rows of `locked_at`, see `GameEngine::row_ids`
+    row_ids: Vec<u64>,
+    // the tick of the last line clear
+    revealed_at: Option<u64>,
+    lines: usize,
+    tick: u64,
+    visible_ticks: Option<u64>,
+    game_over: bool,
+}
+
+impl StackFade {
+    /// Catches up with the engine, returns whether the blocks look different since the last call.
+    pub fn update(&mut self, engine: &GameEngine) -> bool {
+        let board = &engine.board;
+        let cells = board.width() * board.height();
+        // a new game, or a replay seeking backwards
+        if engine.tick < self.tick || self.width != board.width() || self.locked_at.len() != cells {
+            *self = StackFade {
+                width: board.width(),
+                locked_at: vec![None; cells],
+                lines: engine.lines,
+                ..default()
+            };
+        }
+        let ticked = engine.tick != self.tick;
+        self.tick = engine.tick;
+        self.visible_ticks = engine.stack_visible_ticks();
+        self.game_over = engine.is_game_over();
+        if self.visible_ticks.is_none() {
+            return false;
+        }
+
+        if engine.lines != self.lines {
+            self.lines = engine.lines;
+            self.revealed_at = Some(engine.tick);
+        }
+        // the blocks moved by line clears or garbage keep the tick they were locked at
+        if self.row_ids != engine.row_ids() {
+            let width = self.width;
+            let mut locked_at = vec![None; cells];
+            for (y, id) in engine.row_ids().iter().enumerate() {
+                if let Some(old_y) = self.row_ids.iter().position(|old_id| old_id == id) {
+                    locked_at[y * width..(y + 1) * width]
+                        .copy_from_slice(&self.locked_at[old_y * width..(old_y + 1) * width]);
+                }
+            }
+            self.locked_at = locked_at;
+            self.row_ids = engine.row_ids().to_vec();
+        }
+        for y in 0..board.height() {
+            for x in 0..board.width() {
+                let locked_at = &mut self.locked_at[y * self.width + x];
+                if board.get(x, y) == BrickType::None {
+                    *locked_at = None;
+                } else if locked_at.is_none() {
+                    *locked_at = Some(engine.tick);
+                }
+            }
+        }
+        ticked && self.is_fading()
+    }
+
+    /// How much of the block at (x, y) is shown, from 0 when hidden to 1.
+    pub fn visibility(&self, x: usize, y: usize) -> f32 {
+        let Some(visible_ticks) = self.visible_ticks else {
+            return 1.;
+        };
+        if self.game_over {
+            return 1.;
+        }
+        let Some(locked_at) = self.locked_at.get(y * self.width + x).copied().flatten() else {
+            return 1.;
+        };
+        let revealed = self
+            .revealed_at
+            .map_or(0., |at| fade(self.tick - at, REVEAL_TICKS));
+        fade(self.tick - locked_at, visible_ticks).max(revealed)
+    }
+
+    // some blocks are fading out, they are drawn again every tick
+    fn is_fading(&self) -> bool {
+        let Some(visible_ticks) = self.visible_ticks else {
+            return false;
+        };
+        let is_fading_at = |at: u64, visible_ticks: u64| {
+            let age = self.tick - at;
+            visible_ticks > 0 && age >= visible_ticks && age <= visible_ticks + FADE_OUT_TICKS
+        };
+        self.revealed_at
+            .is_some_and(|at| is_fading_at(at, REVEAL_TICKS))
+            || self
+                .locked_at
+                .iter()
+                .flatten()
+                .any(|&at| is_fading_at(at, visible_ticks))
+    }
+}
+
+// how much of a block shown for `visible_ticks` is left after `age` ticks
+fn fade(age: u64, visible_ticks: u64) -> f32 {
+    if age < visible_ticks {
+        1.
+    } else if visible_ticks == 0 {
+        // the invisible mode hides a block as soon as it locks
+        0.
+    } else {
+        1. - ((age - visible_ticks) as f32 / FADE_OUT_TICKS as f32).min(1.)
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct StackFadeRes(pub StackFade);
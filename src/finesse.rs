@@ -0,0 +1,130 @@
+use crate::{
+    board::Board,
+    brick::Brick,
+    engine::{InputAction, InputFrame},
+    position::Position,
+};
+
+// the actions which move the brick before it is dropped, each press counts
+const FINESSE_ACTIONS: [InputAction; 3] = [
+    InputAction::MoveLeft,
+    InputAction::MoveRight,
+    InputAction::Rotate,
+];
+
+/// The fewest key presses bringing a brick from its spawn to a placement, before the hard drop.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FinessePath {
+    // the cells of the placed brick, sorted
+    pub cells: [Position; 4],
+    pub inputs: Vec<InputAction>,
+}
+
+/// The cells of a brick at a position, sorted so that the same placement
+/// reached with another rotation state compares equal.
+pub fn placement_cells(brick: &Brick, pos: Position) -> [Position; 4] {
+    let mut cells = brick.1.map(|cell| cell + pos);
+    cells.sort_by_key(|cell| (cell.y, cell.x));
+    cells
+}
+
+/// Finds the shortest inputs to every placement of a brick spawned at `spawn`,
+/// moving and rotating it with the same rules as the engine.
+pub fn finesse_paths(board: &Board, brick: Brick, spawn: Position) -> Vec<FinessePath> {
+    let mut paths: Vec<FinessePath> = Vec::new();
+    if !board.is_valid_brick(&brick, &spawn) {
+        return paths;
+    }
+    // breadth first, so the first path found to a placement is a shortest one
+    let mut visited = vec![(brick, spawn)];
+    let mut queue = vec![(brick, spawn, Vec::new())];
+    let mut index = 0;
+    while index < queue.len() {
+        let (brick, pos, inputs) = queue[index].clone();
+        index += 1;
+
+        let drop_pos = board.get_bottom_valid_brick_pos(&brick, &pos);
+        let cells = placement_cells(&brick, drop_pos);
+        if !paths.iter().any(|path| path.cells == cells) {
+            paths.push(FinessePath {
+                cells,
+                inputs: inputs.clone(),
+            });
+        }
+
+        for action in FINESSE_ACTIONS {
+            let next = match action {
+                InputAction::MoveLeft => Some((brick, pos.left())),
+                InputAction::MoveRight => Some((brick, pos.right())),
+                _ => {
+                    let rotated = brick.rotate_right();
+                    let mut rotated_pos = pos;
+                    board
+                        .is_valid_brick_for_rotation(&rotated, &mut rotated_pos)
+                        .then_some((rotated, rotated_pos))
+                }
+            };
+            let Some(next) = next.filter(|(brick, pos)| board.is_valid_brick(brick, pos)) else {
+                continue;
+            };
+            if visited.contains(&next) {
+                continue;
+            }
+            visited.push(next);
+            let mut next_inputs = inputs.clone();
+            next_inputs.push(action);
+            queue.push((next.0, next.1, next_inputs));
+        }
+    }
+    paths
+}
+
+/// A brick placed with more key presses than needed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FinesseFault {
+    pub presses: usize,
+    pub expected: Vec<InputAction>,
+}
+
+/// Counts the key presses of every brick and checks them when it locks.
+#[derive(Clone, Debug, Default)]
+pub struct FinesseTracker {
+    // the paths of the falling brick, from where it spawned
+    paths: Vec<FinessePath>,
+    presses: usize,
+    pub faults: usize,
+}
+
+impl FinesseTracker {
+    pub fn spawned(&mut self, board: &Board, brick: Brick, spawn: Position) {
+        self.paths = finesse_paths(board, brick, spawn);
+        self.presses = 0;
+    }
+
+    pub fn pressed(&mut self, input: InputFrame) {
+        self.presses += FINESSE_ACTIONS
+            .iter()
+            .filter(|action| input.contains(**action))
+            .count();
+    }
+
+    /// The placements the falling brick can reach, with their shortest inputs.
+    pub fn paths(&self) -> &[FinessePath] {
+        &self.paths
+    }
+
+    /// Checks the presses used for the brick locked at `pos`.
+    /// Placements which cannot be reached by shifting and rotating, e.g. tucks and spins, are never faults.
+    pub fn locked(&mut self, brick: &Brick, pos: Position) -> Option<FinesseFault> {
+        let cells = placement_cells(brick, pos);
+        let path = self.paths.iter().find(|path| path.cells == cells)?;
+        if self.presses <= path.inputs.len() {
+            return None;
+        }
+        self.faults += 1;
+        Some(FinesseFault {
+            presses: self.presses,
+            expected: path.inputs.clone(),
+        })
+    }
+}
@@ -0,0 +1,729 @@
+use std::io;
+
+use crate::{
+    board::Board,
+    brick::{Brick, BrickType},
+    constants::DEFAULT_BOARD_HEIGHT,
+    engine::{create_brick_start_position, GameEngine},
+    position::Position,
+    puzzle::{board_from_rows, parse_pieces, parse_row},
+    replay::invalid_data,
+};
+
+const FUMEN_PREFIXES: [&str; 3] = ["v115@", "m115@", "d115@"];
+const FUMEN_TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+// the characters of the comments, a value of 96 per character
+const COMMENT_TABLE: &str =
+    " !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~";
+const COMMENT_CHAR_VALUES: u64 = COMMENT_TABLE.len() as u64 + 1;
+const MAX_COMMENT_LENGTH: usize = 4095;
+// the fumen field is 10 wide, 23 rows tall, with one garbage row below it
+const FIELD_WIDTH: usize = 10;
+const FIELD_TOP: usize = 23;
+const FIELD_BLOCKS: usize = FIELD_WIDTH * (FIELD_TOP + 1);
+const PIECE_TYPES: usize = 8;
+// the field of a page which did not change from the page before
+const UNCHANGED_FIELD: u64 = (PIECE_TYPES * FIELD_BLOCKS + FIELD_BLOCKS - 1) as u64;
+// the flags of a page, after its piece
+const FLAG_COLORIZE: u64 = 1 << 2;
+const FLAG_COMMENT: u64 = 1 << 3;
+// the queue is written in the comment, the way the fumen quizzes do
+const QUIZ_PREFIX: &str = "#Q=";
+
+/// Fumen rotations, in the order of their codes.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum FumenRotation {
+    Reverse,
+    Right,
+    Spawn,
+    Left,
+}
+
+impl FumenRotation {
+    const ALL: [FumenRotation; 4] = [
+        FumenRotation::Spawn,
+        FumenRotation::Right,
+        FumenRotation::Reverse,
+        FumenRotation::Left,
+    ];
+
+    fn code(self) -> u64 {
+        self as u64
+    }
+
+    fn from_code(code: u64) -> Self {
+        [
+            FumenRotation::Reverse,
+            FumenRotation::Right,
+            FumenRotation::Spawn,
+            FumenRotation::Left,
+        ][code as usize % 4]
+    }
+
+    fn rotate(self, pos: Position) -> Position {
+        match self {
+            FumenRotation::Spawn => pos,
+            FumenRotation::Right => Position::new(pos.y, -pos.x),
+            FumenRotation::Reverse => Position::new(-pos.x, -pos.y),
+            FumenRotation::Left => Position::new(-pos.y, pos.x),
+        }
+    }
+}
+
+/// A board with the falling brick, the held one and the coming ones,
+/// as shared between players with fumen links or text diagrams.
+#[derive(Clone, Debug)]
+pub struct BoardSetup {
+    pub board: Board,
+    pub current: Option<(Brick, Position)>,
+    pub hold: Option<BrickType>,
+    pub queue: Vec<BrickType>,
+}
+
+impl BoardSetup {
+    /// The state of a game, with the next brick as the queue. The game has no hold.
+    pub fn from_engine(engine: &GameEngine) -> Self {
+        let next_shown = engine.pieces_left() != Some(0);
+        BoardSetup {
+            board: engine.board.clone(),
+            current: engine.active.map(|active| (active.brick, active.pos)),
+            hold: None,
+            queue: next_shown.then_some(engine.next.0).into_iter().collect(),
+        }
+    }
+
+    /// Reads a fumen, also inside a link, or a text board.
+    pub fn parse(text: &str) -> io::Result<Self> {
+        if text.contains("115@") {
+            BoardSetup::from_fumen(text)
+        } else {
+            BoardSetup::from_text(text)
+        }
+    }
+
+    /// The text format of the puzzles, with the bricks instead of a goal, e.g.
+    /// ```text
+    /// current = T
+    /// hold = I
+    /// queue = OSZ
+    /// board:
+    /// GGGG..GGGG
+    /// ```
+    /// Only the type of the falling brick is kept, it starts again from the top.
+    pub fn to_text(&self) -> String {
+        let mut lines = Vec::new();
+        if let Some((brick, _)) = self.current {
+            lines.push(format!("current = {}", brick.0.to_char()));
+        }
+        if let Some(hold) = self.hold {
+            lines.push(format!("hold = {}", hold.to_char()));
+        }
+        if !self.queue.is_empty() {
+            let queue: String = self.queue.iter().map(BrickType::to_char).collect();
+            lines.push(format!("queue = {}", queue));
+        }
+        lines.push("board:".to_string());
+        let board = &self.board;
+        let rows = (0..board.height())
+            .rev()
+            .skip_while(|y| board.is_line_empty(*y));
+        for y in rows {
+            lines.push(
+                (0..board.width())
+                    .map(|x| board.get(x, y).to_char())
+                    .collect(),
+            );
+        }
+        lines.push(String::new());
+        lines.join("\n")
+    }
+
+    pub fn from_text(text: &str) -> io::Result<Self> {
+        let mut current = None;
+        let mut hold = None;
+        let mut queue = Vec::new();
+        let mut rows: Option<Vec<Vec<BrickType>>> = None;
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(rows) = rows.as_mut() {
+                rows.push(parse_row(line)?);
+                continue;
+            }
+            if line == "board:" {
+                rows = Some(Vec::new());
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(invalid_data("expected a `key = value` line"));
+            };
+            let pieces = parse_pieces(value)?;
+            match key.trim() {
+                "current" => current = pieces.first().copied(),
+                "hold" => hold = pieces.first().copied(),
+                "queue" => queue = pieces,
+                _ => return Err(invalid_data("unknown key")),
+            }
+        }
+
+        let Some(rows) = rows else {
+            return Err(invalid_data("no board"));
+        };
+        let board = board_from_rows(&rows)?;
+        let current = current.map(|brick_type| {
+            let pos = create_brick_start_position(&board, &brick_type);
+            (Brick::from_type(brick_type), pos)
+        });
+        Ok(BoardSetup {
+            board,
+            current,
+            hold,
+            queue,
+        })
+    }
+
+    /// A fumen v115 of one page, with the hold and the queue in a quiz comment.
+    /// Fails when the board is not 10 wide or the stack is higher than the fumen field.
+    pub fn to_fumen(&self) -> io::Result<String> {
+        let board = &self.board;
+        if board.width() != FIELD_WIDTH {
+            return Err(invalid_data("a fumen board is 10 cells wide"));
+        }
+        if (FIELD_TOP..board.height()).any(|y| !board.is_line_empty(y)) {
+            return Err(invalid_data("the stack is too high for a fumen"));
+        }
+
+        let mut data = FumenWriter::default();
+        // the field, as the difference from the empty field of the page before
+        let mut runs: Vec<(u64, usize)> = Vec::new();
+        for index in 0..FIELD_BLOCKS {
+            let code = field_cell(board, index) + PIECE_TYPES as u64;
+            match runs.last_mut() {
+                Some((last, count)) if *last == code => *count += 1,
+                _ => runs.push((code, 1)),
+            }
+        }
+        for (code, count) in &runs {
+            data.push(code * FIELD_BLOCKS as u64 + *count as u64 - 1, 2);
+        }
+        if runs.len() == 1 && runs[0].0 == PIECE_TYPES as u64 {
+            // no page after this one repeats the field
+            data.push(0, 1);
+        }
+
+        let comment = self.quiz_comment();
+        let mut flags = FLAG_COLORIZE;
+        if !comment.is_empty() {
+            flags |= FLAG_COMMENT;
+        }
+        let (piece, rotation, location) = match self.current.and_then(fumen_piece) {
+            Some(piece) => piece,
+            None => (0, FumenRotation::Reverse, 0),
+        };
+        let action = ((flags * FIELD_BLOCKS as u64 + location) * 4 + rotation.code()) * 8 + piece;
+        data.push(action, 3);
+
+        if !comment.is_empty() {
+            let comment = escape(&comment);
+            let length = comment.len().min(MAX_COMMENT_LENGTH);
+            data.push(length as u64, 2);
+            for chunk in comment.as_bytes()[..length].chunks(4) {
+                let value = chunk.iter().rev().fold(0, |value, c| {
+                    let index = COMMENT_TABLE.find(*c as char).unwrap_or_default() as u64;
+                    value * COMMENT_CHAR_VALUES + index
+                });
+                data.push(value, 5);
+            }
+        }
+        Ok(data.finish())
+    }
+
+    /// Reads the first page of a fumen v115, also inside a link.
+    pub fn from_fumen(text: &str) -> io::Result<Self> {
+        let start = FUMEN_PREFIXES
+            .iter()
+            .filter_map(|prefix| text.find(prefix).map(|index| index + prefix.len()))
+            .min()
+            .ok_or_else(|| invalid_data("not a fumen v115"))?;
+        let mut data = FumenReader::new(&text[start..])?;
+
+        let mut board = Board::new(FIELD_WIDTH, DEFAULT_BOARD_HEIGHT);
+        let mut index = 0;
+        while index < FIELD_BLOCKS {
+            let value = data.read(2)?;
+            let code = value / FIELD_BLOCKS as u64;
+            let count = (value % FIELD_BLOCKS as u64) as usize + 1;
+            if index + count > FIELD_BLOCKS || code > 2 * PIECE_TYPES as u64 {
+                return Err(invalid_data("invalid fumen field"));
+            }
+            // the first page is the difference from the empty field
+            let cell = fumen_type(code.saturating_sub(PIECE_TYPES as u64))
+                .ok_or_else(|| invalid_data("invalid fumen field"))?;
+            for index in index..index + count {
+                let (x, y) = field_position(index);
+                if let Some(y) = y.filter(|_| cell != BrickType::None) {
+                    board.set(x, y, cell);
+                }
+            }
+            if value == UNCHANGED_FIELD {
+                data.read(1)?;
+            }
+            index += count;
+        }
+
+        let action = data.read(3)?;
+        let piece = action % 8;
+        let rotation = FumenRotation::from_code(action / 8 % 4);
+        let location = action / 32 % FIELD_BLOCKS as u64;
+        let flags = action / 32 / FIELD_BLOCKS as u64;
+        let mut current = fumen_type(piece)
+            .filter(|brick_type| BrickType::ALL.contains(brick_type))
+            .and_then(|brick_type| brick_from_fumen(brick_type, rotation, location));
+
+        let mut hold = None;
+        let mut queue = Vec::new();
+        if flags & FLAG_COMMENT != 0 {
+            let length = data.read(2)? as usize;
+            let mut comment = String::with_capacity(length);
+            for _ in 0..length.div_ceil(4) {
+                let mut value = data.read(5)?;
+                for _ in 0..4 {
+                    let index = (value % COMMENT_CHAR_VALUES) as usize;
+                    comment.push(COMMENT_TABLE.chars().nth(index).unwrap_or(' '));
+                    value /= COMMENT_CHAR_VALUES;
+                }
+            }
+            comment.truncate(length);
+            let comment = unescape(&comment);
+            if let Some((quiz_hold, quiz_current, quiz_queue)) = parse_quiz(&comment) {
+                hold = quiz_hold;
+                queue = quiz_queue;
+                // the piece on the page has a position, the one of the quiz does not
+                if current.is_none() {
+                    current = quiz_current.map(|brick_type| {
+                        let pos = create_brick_start_position(&board, &brick_type);
+                        (Brick::from_type(brick_type), pos)
+                    });
+                }
+            }
+        }
+        Ok(BoardSetup {
+            board,
+            current,
+            hold,
+            queue,
+        })
+    }
+
+    // `#Q=[hold](current)queue`
+    fn quiz_comment(&self) -> String {
+        if self.hold.is_none() && self.queue.is_empty() {
+            return String::new();
+        }
+        let hold: String = self.hold.iter().map(BrickType::to_char).collect();
+        let current: String = self
+            .current
+            .iter()
+            .map(|(brick, _)| brick.0.to_char())
+            .collect();
+        let queue: String = self.queue.iter().map(BrickType::to_char).collect();
+        format!("{}[{}]({}){}", QUIZ_PREFIX, hold, current, queue)
+    }
+}
+
+type Quiz = (Option<BrickType>, Option<BrickType>, Vec<BrickType>);
+
+fn parse_quiz(comment: &str) -> Option<Quiz> {
+    let quiz = comment.strip_prefix(QUIZ_PREFIX)?;
+    let (hold, rest) = quiz.strip_prefix('[')?.split_once(']')?;
+    let (current, queue) = rest.strip_prefix('(')?.split_once(')')?;
+    let queue = queue.split(';').next().unwrap_or_default();
+    Some((
+        parse_pieces(hold).ok()?.first().copied(),
+        parse_pieces(current).ok()?.first().copied(),
+        parse_pieces(queue).ok()?,
+    ))
+}
+
+#[derive(Default)]
+struct FumenWriter(String);
+
+impl FumenWriter {
+    // little endian, 6 bits per character
+    fn push(&mut self, mut value: u64, digits: usize) {
+        for _ in 0..digits {
+            self.0.push(FUMEN_TABLE[(value % 64) as usize] as char);
+            value /= 64;
+        }
+    }
+
+    // a `?` after the first 42 characters, then every 47
+    fn finish(self) -> String {
+        let data = self.0;
+        let mut text = String::from(FUMEN_PREFIXES[0]);
+        let (head, mut tail) = data.split_at(data.len().min(42));
+        text.push_str(head);
+        while !tail.is_empty() {
+            let (chunk, rest) = tail.split_at(tail.len().min(47));
+            text.push('?');
+            text.push_str(chunk);
+            tail = rest;
+        }
+        text
+    }
+}
+
+struct FumenReader(std::vec::IntoIter<u64>);
+
+impl FumenReader {
+    fn new(data: &str) -> io::Result<Self> {
+        let values = data
+            .chars()
+            .take_while(|c| *c != '&' && *c != '#')
+            .filter(|c| *c != '?' && !c.is_whitespace())
+            .map(|c| {
+                FUMEN_TABLE
+                    .iter()
+                    .position(|t| *t as char == c)
+                    .map(|value| value as u64)
+                    .ok_or_else(|| invalid_data("invalid fumen character"))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        Ok(FumenReader(values.into_iter()))
+    }
+
+    fn read(&mut self, digits: usize) -> io::Result<u64> {
+        let mut value = 0;
+        for digit in 0..digits {
+            let next = self
+                .0
+                .next()
+                .ok_or_else(|| invalid_data("the fumen ends early"))?;
+            value += next << (6 * digit);
+        }
+        Ok(value)
+    }
+}
+
+// the board cell of a field index, None for the garbage row below the field
+fn field_position(index: usize) -> (usize, Option<usize>) {
+    let row = index / FIELD_WIDTH;
+    (index % FIELD_WIDTH, (FIELD_TOP - 1).checked_sub(row))
+}
+
+fn field_cell(board: &Board, index: usize) -> u64 {
+    match field_position(index) {
+        (x, Some(y)) => fumen_code(board.get(x, y)),
+        (_, None) => 0,
+    }
+}
+
+fn fumen_code(brick_type: BrickType) -> u64 {
+    match brick_type {
+        BrickType::None => 0,
+        BrickType::I => 1,
+        BrickType::L => 2,
+        BrickType::O => 3,
+        BrickType::Z => 4,
+        BrickType::T => 5,
+        BrickType::J => 6,
+        BrickType::S => 7,
+        BrickType::Garbage => 8,
+    }
+}
+
+fn fumen_type(code: u64) -> Option<BrickType> {
+    [
+        BrickType::None,
+        BrickType::I,
+        BrickType::L,
+        BrickType::O,
+        BrickType::Z,
+        BrickType::T,
+        BrickType::J,
+        BrickType::S,
+        BrickType::Garbage,
+    ]
+    .get(code as usize)
+    .copied()
+}
+
+// the cells of a fumen piece in spawn rotation, around its center
+fn fumen_offsets(brick_type: BrickType) -> [Position; 4] {
+    let cells = match brick_type {
+        BrickType::I => [(0, 0), (-1, 0), (1, 0), (2, 0)],
+        BrickType::T => [(0, 0), (-1, 0), (1, 0), (0, 1)],
+        BrickType::O => [(0, 0), (1, 0), (0, 1), (1, 1)],
+        BrickType::L => [(0, 0), (-1, 0), (1, 0), (1, 1)],
+        BrickType::J => [(0, 0), (-1, 0), (1, 0), (-1, 1)],
+        BrickType::S => [(0, 0), (-1, 0), (0, 1), (1, 1)],
+        _ => [(0, 0), (1, 0), (0, 1), (-1, 1)],
+    };
+    cells.map(|(x, y)| Position::new(x, y))
+}
+
+// the old fumen put the center of some pieces in another cell, which is kept for compatibility
+fn center_shift(brick_type: BrickType, rotation: FumenRotation) -> Position {
+    let (x, y) = match (brick_type, rotation) {
+        (BrickType::O, FumenRotation::Left) => (1, -1),
+        (BrickType::O, FumenRotation::Reverse) => (1, 0),
+        (BrickType::O, FumenRotation::Spawn) => (0, -1),
+        (BrickType::I, FumenRotation::Reverse) => (1, 0),
+        (BrickType::I, FumenRotation::Left) => (0, -1),
+        (BrickType::S, FumenRotation::Spawn) => (0, -1),
+        (BrickType::S, FumenRotation::Right) => (-1, 0),
+        (BrickType::Z, FumenRotation::Spawn) => (0, -1),
+        (BrickType::Z, FumenRotation::Left) => (1, 0),
+        _ => (0, 0),
+    };
+    Position::new(x, y)
+}
+
+fn sorted_cells(cells: [Position; 4]) -> [Position; 4] {
+    let mut cells = cells;
+    cells.sort_by_key(|cell| (cell.y, cell.x));
+    cells
+}
+
+// the piece, rotation and location of a brick on the board, if it is inside the field
+fn fumen_piece((brick, pos): (Brick, Position)) -> Option<(u64, FumenRotation, u64)> {
+    let cells = sorted_cells(brick.1.map(|cell| cell + pos));
+    for rotation in FumenRotation::ALL {
+        let offsets = fumen_offsets(brick.0).map(|offset| rotation.rotate(offset));
+        for center in cells {
+            if sorted_cells(offsets.map(|offset| offset + center)) != cells {
+                continue;
+            }
+            let location = center - center_shift(brick.0, rotation);
+            if !(0..FIELD_WIDTH as i32).contains(&location.x)
+                || !(0..FIELD_TOP as i32).contains(&location.y)
+            {
+                return None;
+            }
+            let index =
+                (FIELD_TOP as i32 - location.y - 1) as u64 * FIELD_WIDTH as u64 + location.x as u64;
+            return Some((fumen_code(brick.0), rotation, index));
+        }
+    }
+    None
+}
+
+// the brick of the game covering the same cells as a fumen piece
+fn brick_from_fumen(
+    brick_type: BrickType,
+    rotation: FumenRotation,
+    location: u64,
+) -> Option<(Brick, Position)> {
+    let (x, y) = field_position(location as usize);
+    let center = Position::new(x as i32, y? as i32) + center_shift(brick_type, rotation);
+    let cells =
+        sorted_cells(fumen_offsets(brick_type).map(|offset| rotation.rotate(offset) + center));
+    let mut brick = Brick::from_type(brick_type);
+    for _ in 0..4 {
+        let brick_cells = sorted_cells(brick.1);
+        let pos = cells[0] - brick_cells[0];
+        if brick_cells.map(|cell| cell + pos) == cells {
+            return Some((brick, pos));
+        }
+        brick = brick.rotate_right();
+    }
+    None
+}
+
+// the `escape` of javascript, which fumen applies to its comments
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            'A'..='Z' | 'a'..='z' | '0'..='9' | '@' | '*' | '_' | '+' | '-' | '.' | '/' => {
+                escaped.push(c)
+            }
+            c if (c as u32) < 0x100 => escaped.push_str(&format!("%{:02X}", c as u32)),
+            c => {
+                let mut units = [0; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    escaped.push_str(&format!("%u{:04X}", unit));
+                }
+            }
+        }
+    }
+    escaped
+}
+
+fn unescape(text: &str) -> String {
+    let mut units: Vec<u16> = Vec::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let hex = |digits: &str| u16::from_str_radix(digits, 16).ok();
+        let escaped = match rest.as_bytes() {
+            [b'%', b'u', ..] => rest.get(2..6).and_then(hex).map(|unit| (unit, 6)),
+            [b'%', ..] => rest.get(1..3).and_then(hex).map(|unit| (unit, 3)),
+            _ => None,
+        };
+        match escaped {
+            Some((unit, length)) => {
+                units.push(unit);
+                rest = &rest[length..];
+            }
+            None => {
+                let mut buffer = [0; 2];
+                units.extend_from_slice(c.encode_utf16(&mut buffer));
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    String::from_utf16_lossy(&units)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "current = T
+hold = I
+queue = SZO
+board:
+.......ZZ.
+....LLLJZZ
+GGGGG.GJJJ
+";
+    const EMPTY_FUMEN: &str = "v115@vhAAgH";
+    // a T on a garbage row with a hole, then `#Q=[I](T)SZO`, as the fumen editor locks it
+    const T_QUIZ_FUMEN: &str = "v115@bhI8KeVL2YAFLDmClcJSAVDVSAVG88AYe88A5srgC";
+
+    fn cells((brick, pos): (Brick, Position)) -> [Position; 4] {
+        sorted_cells(brick.1.map(|cell| cell + pos))
+    }
+
+    fn positions(cells: [(i32, i32); 4]) -> [Position; 4] {
+        sorted_cells(cells.map(|(x, y)| Position::new(x, y)))
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let setup = BoardSetup::from_text(TEXT).unwrap();
+        assert_eq!(setup.to_text(), TEXT);
+        let fumen = setup.to_fumen().unwrap();
+        let decoded = BoardSetup::parse(&fumen).unwrap();
+        assert_eq!(decoded.to_text(), TEXT);
+        assert_eq!(decoded.board, setup.board);
+        assert_eq!(decoded.current.map(cells), setup.current.map(cells));
+        assert_eq!(decoded.to_fumen().unwrap(), fumen);
+    }
+
+    #[test]
+    fn empty_field() {
+        let setup = BoardSetup::from_text("board:\n").unwrap();
+        assert_eq!(setup.to_fumen().unwrap(), EMPTY_FUMEN);
+        let decoded = BoardSetup::from_fumen(EMPTY_FUMEN).unwrap();
+        assert!((0..decoded.board.height()).all(|y| decoded.board.is_line_empty(y)));
+        assert!(decoded.current.is_none());
+        assert!(decoded.hold.is_none());
+        assert!(decoded.queue.is_empty());
+    }
+
+    #[test]
+    fn decodes_a_piece_and_a_quiz() {
+        let link = format!("https://fumen.zui.jp/?{}", T_QUIZ_FUMEN);
+        let setup = BoardSetup::parse(&link).unwrap();
+        assert_eq!(
+            setup.to_text(),
+            "current = T\nhold = I\nqueue = SZO\nboard:\nGGGGGGGGG.\n"
+        );
+        let (brick, _) = setup.current.unwrap();
+        assert_eq!(brick.0, BrickType::T);
+        assert_eq!(
+            setup.current.map(cells),
+            Some(positions([(3, 1), (4, 1), (5, 1), (4, 2)]))
+        );
+        // the same page without the lock flag
+        assert_eq!(
+            setup.to_fumen().unwrap(),
+            "v115@bhI8KeVLYYAFLDmClcJSAVDVSAVG88AYe88A5srgC"
+        );
+    }
+
+    #[test]
+    fn run_encoding() {
+        // 220 empty cells, 9 garbage cells, 11 empty cells with the garbage row
+        let setup = BoardSetup::from_text("board:\nGGGGGGGGG.\n").unwrap();
+        let fumen = setup.to_fumen().unwrap();
+        assert_eq!(&fumen[5..11], "bhI8Ke");
+        // only the empty field is followed by how often the next pages repeat it
+        assert_eq!(&EMPTY_FUMEN[5..8], "vhA");
+        assert_eq!(
+            UNCHANGED_FIELD,
+            FumenReader::new("vh").unwrap().read(2).unwrap()
+        );
+    }
+
+    #[test]
+    fn unchanged_field_repeats() {
+        // the field of the first page is repeated on the 5 next ones
+        let setup = BoardSetup::from_fumen("v115@vhFAgHAAAAAAAAAAAAAAA").unwrap();
+        assert!((0..setup.board.height()).all(|y| setup.board.is_line_empty(y)));
+        assert!(setup.current.is_none());
+    }
+
+    #[test]
+    fn offsets_and_center_shift() {
+        let location = |x: usize, y: usize| ((FIELD_TOP - y - 1) * FIELD_WIDTH + x) as u64;
+        // the center of an O is its bottom left cell in spawn, and of an I its second cell
+        let o = brick_from_fumen(BrickType::O, FumenRotation::Spawn, location(4, 10)).unwrap();
+        assert_eq!(cells(o), positions([(4, 9), (5, 9), (4, 10), (5, 10)]));
+        let i = brick_from_fumen(BrickType::I, FumenRotation::Spawn, location(4, 10)).unwrap();
+        assert_eq!(cells(i), positions([(3, 10), (4, 10), (5, 10), (6, 10)]));
+        let i = brick_from_fumen(BrickType::I, FumenRotation::Left, location(4, 10)).unwrap();
+        assert_eq!(cells(i), positions([(4, 8), (4, 9), (4, 10), (4, 11)]));
+
+        for brick_type in BrickType::ALL {
+            assert_eq!(sorted_cells(fumen_offsets(brick_type))[0].y, 0);
+            for rotation in FumenRotation::ALL {
+                let brick = brick_from_fumen(brick_type, rotation, location(4, 10)).unwrap();
+                let (code, rotation, index) = fumen_piece(brick).unwrap();
+                assert_eq!(fumen_type(code), Some(brick_type));
+                // the O, I, S and Z cover the same cells in two rotations
+                let again = brick_from_fumen(brick_type, rotation, index).unwrap();
+                assert_eq!(cells(again), cells(brick));
+            }
+        }
+    }
+
+    #[test]
+    fn comment_escape() {
+        assert_eq!(escape("#Q=[I](T)SZO"), "%23Q%3D%5BI%5D%28T%29SZO");
+        assert_eq!(escape("a b-c"), "a%20b-c");
+        assert_eq!(escape("é中😀"), "%E9%u4E2D%uD83D%uDE00");
+        for text in ["#Q=[I](T)SZO", "a b-c", "é中😀", "100%", "%u12"] {
+            assert_eq!(unescape(&escape(text)), text);
+        }
+        // the characters which are not escapes stay as they are
+        assert_eq!(unescape("100%"), "100%");
+        assert_eq!(unescape("%zz%u4E2D"), "%zz中");
+    }
+
+    #[test]
+    fn long_comments_are_cut() {
+        let mut setup = BoardSetup::from_text(TEXT).unwrap();
+        setup.hold = None;
+        setup.queue = [BrickType::I, BrickType::O].repeat(2100);
+        let decoded = BoardSetup::from_fumen(&setup.to_fumen().unwrap()).unwrap();
+        // `%23Q%3D%5B%5D%28T%29` comes before the queue
+        let kept = MAX_COMMENT_LENGTH - escape("#Q=[](T)").len();
+        assert_eq!(decoded.queue, setup.queue[..kept]);
+    }
+
+    #[test]
+    fn invalid_fumens() {
+        assert!(BoardSetup::from_fumen("vhAAgH").is_err());
+        assert!(BoardSetup::from_fumen("v115@vh!AgH").is_err());
+        assert!(BoardSetup::from_fumen("v115@vhAA").is_err());
+        let wide = BoardSetup::from_text("board:\nGGGGGGGGGGG.\n").unwrap();
+        assert!(wide.to_fumen().is_err());
+        let mut high = BoardSetup::from_text("board:\n").unwrap();
+        high.board.set(0, FIELD_TOP, BrickType::Garbage);
+        assert!(high.to_fumen().is_err());
+    }
+}
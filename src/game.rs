@@ -4,24 +4,30 @@ use bevy::{
 };
 
 use crate::{
+    ai::{AiController, WATCH_AI_ACTION_TICKS},
     board::Board,
     brick::{Brick, BrickType},
-    constants::{BOARD_VIEW_X, BOARD_VIEW_Y, BOARD_X, BOARD_Y, GAME_DATA_TEXT_COLOR},
+    constants::{DEFAULT_BOARD_HEIGHT, DEFAULT_BOARD_WIDTH},
     data::PauseStateRes,
-    menu::{GameLevelRes, GameSelectedLevel},
+    engine::{ActiveBrick, GameEngine, InputAction, InputFrame, TICK_SECONDS},
+    events::{GameEventWriters, GameOver, GoalReached, LinesCleared},
+    fade::{StackFade, StackFadeRes},
+    input::KeyMap,
+    layout::ScreenLayoutRes,
+    locale::{Locale, LocaleRes},
+    menu::{GameLevelRes, GameModeRes, GameOptionsRes, GameSelectedLevel, WatchAiRes},
     position::Position,
-    utils::{despawn_with_component, get_level, get_score, get_speed},
+    puzzle_menu::PuzzleRes,
+    replay::{LastReplayRes, Replay, ReplayRecorderRes},
+    save::{restore_engine, SavedGameRes},
+    theme::{pattern_color, PatternBar, ThemeRes, HIGH_CONTRAST_GHOST},
     GameState,
 };
 
 use lazy_static::*;
 use std::{
     collections::HashMap,
-    sync::{
-        atomic::{AtomicBool, Ordering},
-        Mutex,
-    },
-    time::Duration,
+    sync::atomic::{AtomicBool, Ordering},
 };
 
 use bevy::{prelude::*, sprite::Anchor};
@@ -29,10 +35,22 @@ use bevy::{prelude::*, sprite::Anchor};
 pub(crate) const BLOCK_INSET: f32 = 1.;
 pub(crate) const BLOCK_WIDTH: f32 = 36.;
 
-pub(crate) const DEFAULT_EASY_FALLING_SPEED: f32 = 1.1; // easy mode speed
-pub(crate) const DEFAULT_NORMAL_FALLING_SPEED: f32 = 0.725; // normal mode speed
-pub(crate) const DEFAULT_HARD_FALLING_SPEED: f32 = 0.4; // hard mode speed
-pub(crate) const MAX_FALLING_SPEED: f32 = 0.1; // max speed
+const END_SESSION_KEY: KeyCode = KeyCode::Return;
+
+// bigger boards are drawn with smaller blocks to fit into this area
+const MAX_BOARD_VIEW_WIDTH: f32 = 560.;
+const MAX_BOARD_VIEW_HEIGHT: f32 = 720.;
+// in a portrait window the game info is drawn above the board, the next brick and the HUD right of it
+const PORTRAIT_TOP_PANEL: f32 = 220.;
+const PORTRAIT_SIDE_PANEL: f32 = 200.;
+const PORTRAIT_BOTTOM_MARGIN: f32 = 60.;
+// the distance between the title and the value of a game info
+const INFO_LINE_HEIGHT: f32 = 60.;
+
+// never run more than this many ticks in one frame, e.g. after the window was dragged
+pub(crate) const MAX_TICKS_PER_FRAME: f32 = 15.;
+// the hidden stack of the invisible and fading modes is shown this long before the game over menu
+const GAME_OVER_REVEAL_SECONDS: f32 = 3.;
 
 #[derive(Resource)]
 pub struct GameScoresRes {
@@ -41,9 +59,169 @@ pub struct GameScoresRes {
     pub lines: usize,
 }
 
+impl GameScoresRes {
+    // store the data, it will be used when the game is finished.
+    fn store(&mut self, engine: &GameEngine) {
+        self.level = engine.level;
+        self.score = engine.score;
+        self.lines = engine.lines;
+    }
+}
+
+/// The running game, shared by the game and the replay viewer.
+#[derive(Resource)]
+pub struct GameEngineRes(pub GameEngine);
+
+/// The size of the board on the screen, and where the game info is drawn around it.
+#[derive(Resource, Clone, Copy)]
+pub struct BoardLayoutRes {
+    pub block_width: f32,
+    pub columns: usize,
+    pub rows: usize,
+    // the center of the board
+    pub origin_x: f32,
+    pub origin_y: f32,
+    pub screen: ScreenLayoutRes,
+}
+
+impl BoardLayoutRes {
+    pub fn new(columns: usize, rows: usize) -> Self {
+        BoardLayoutRes::for_screen(columns, rows, ScreenLayoutRes::Landscape)
+    }
+
+    /// The single player layout of a board for the orientation of the window.
+    pub fn for_screen(columns: usize, rows: usize, screen: ScreenLayoutRes) -> Self {
+        match screen {
+            ScreenLayoutRes::Landscape => {
+                BoardLayoutRes::fit(columns, rows, MAX_BOARD_VIEW_WIDTH, MAX_BOARD_VIEW_HEIGHT)
+            }
+            ScreenLayoutRes::Portrait => {
+                let size = screen.size();
+                let layout = BoardLayoutRes::fit(
+                    columns,
+                    rows,
+                    size.x - PORTRAIT_SIDE_PANEL,
+                    size.y - PORTRAIT_TOP_PANEL - PORTRAIT_BOTTOM_MARGIN,
+                );
+                let board_top = size.y / 2. - PORTRAIT_TOP_PANEL;
+                BoardLayoutRes {
+                    origin_x: -PORTRAIT_SIDE_PANEL / 2.,
+                    origin_y: board_top - layout.board_height() / 2.,
+                    screen,
+                    ..layout
+                }
+            }
+        }
+    }
+
+    /// Uses the biggest blocks for which the board fits into `max_width` x `max_height`.
+    pub fn fit(columns: usize, rows: usize, max_width: f32, max_height: f32) -> Self {
+        let block_width = BLOCK_WIDTH
+            .min(max_width / columns as f32)
+            .min(max_height / rows as f32)
+            .floor();
+        BoardLayoutRes {
+            block_width,
+            columns,
+            rows,
+            origin_x: 0.,
+            origin_y: 0.,
+            screen: ScreenLayoutRes::Landscape,
+        }
+    }
+
+    pub fn with_origin_x(self, origin_x: f32) -> Self {
+        BoardLayoutRes { origin_x, ..self }
+    }
+
+    pub fn board_width(&self) -> f32 {
+        self.block_width * self.columns as f32
+    }
+
+    pub fn board_height(&self) -> f32 {
+        self.block_width * self.rows as f32
+    }
+
+    pub fn board_top(&self) -> f32 {
+        self.origin_y + self.board_height() / 2.
+    }
+
+    pub fn board_right(&self) -> f32 {
+        self.origin_x + self.board_width() / 2.
+    }
+
+    /// Where the title of the i-th game info, the score, the level and the lines, is drawn.
+    /// Its value is drawn `INFO_LINE_HEIGHT` below.
+    pub fn info_position(&self, i: usize) -> Vec2 {
+        if self.screen.is_portrait() {
+            // in a row above the board
+            Vec2::new((i as f32 - 1.) * 200., self.board_top() + 150.)
+        } else {
+            // in a column left of the board
+            let x = self.origin_x - self.board_width() / 2. - 130.;
+            Vec2::new(x, self.origin_y + 180. - 2. * i as f32 * INFO_LINE_HEIGHT)
+        }
+    }
+
+    pub fn next_title_position(&self) -> Vec2 {
+        if self.screen.is_portrait() {
+            Vec2::new(self.board_right() + 120., self.board_top() - 10.)
+        } else {
+            Vec2::new(self.board_right() + 100., self.origin_y + 180.)
+        }
+    }
+
+    pub fn next_brick_position(&self) -> Vec2 {
+        if self.screen.is_portrait() {
+            Vec2::new(self.board_right() + 60., self.board_top() - 150.)
+        } else {
+            Vec2::new(self.board_right() + 70., self.origin_y + 10.)
+        }
+    }
+
+    /// The top left of the HUD lines.
+    pub fn hud_position(&self) -> Vec2 {
+        if self.screen.is_portrait() {
+            Vec2::new(self.board_right() + 20., self.board_top() - 220.)
+        } else {
+            Vec2::new(self.board_right() + 40., self.origin_y - 80.)
+        }
+    }
+
+    /// The top center of the name of the last line clear.
+    pub fn action_text_position(&self) -> Vec2 {
+        if self.screen.is_portrait() {
+            // over the board, the side panel is too narrow
+            Vec2::new(self.origin_x, self.board_top() - 40.)
+        } else {
+            Vec2::new(
+                self.origin_x - self.board_width() / 2. - 130.,
+                self.origin_y - 220.,
+            )
+        }
+    }
+}
+
+impl Default for BoardLayoutRes {
+    fn default() -> Self {
+        BoardLayoutRes::new(DEFAULT_BOARD_WIDTH, DEFAULT_BOARD_HEIGHT)
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GameSystemSet {
+    // follow the orientation of the window
+    Layout,
+    // advance the engine
+    Tick,
+    // react to the gameplay events
+    Events,
+    // draw the engine state
+    Render,
+}
+
 static ENABLE_SHOWING_SHADOW_BRICK: AtomicBool = AtomicBool::new(true);
 static ENABLE_SHOWING_BOARD_LINES: AtomicBool = AtomicBool::new(true);
-static ENABLE_7_BAG_RANDOMIZATION: AtomicBool = AtomicBool::new(true);
 
 lazy_static! {
     pub static ref BRICK_COLOR_MAP: HashMap<BrickType, String> = HashMap::from([
@@ -54,13 +232,13 @@ lazy_static! {
         (BrickType::S, "#00CD00".to_string()),
         (BrickType::Z, "#CD0000".to_string()),
         (BrickType::T, "#9A00CD".to_string()),
+        (BrickType::Garbage, "#7A7A7A".to_string()),
         (BrickType::None, "#484848".to_string()),
     ]);
-    static ref BRICK_FALLING_SPEED: Mutex<f32> = Mutex::new(1.0);
 }
 
 #[derive(Component)]
-struct BoardBundle(Board);
+pub(crate) struct BoardBundle;
 
 #[derive(Component)]
 struct GameInfoBundle;
@@ -70,29 +248,43 @@ struct GameInfoBundle;
 struct NextBrickTitleBundle;
 
 #[derive(Component)]
-struct MovingBrickBundle {
-    moving_pos: Position,
-    brick: Brick,
-    movable: bool,
-}
+pub(crate) struct MovingBrickBundle;
 
 #[derive(Component)]
-struct ShadowBrickBundle;
+pub(crate) struct ShadowBrickBundle;
 
 #[derive(Component)]
-struct NextBrickBundle(Brick);
+pub(crate) struct NextBrickBundle;
 
-#[derive(Component)]
-struct ScoreText(usize);
+/// The entities drawing the state of an engine, respawned on every change.
+pub(crate) type EngineViewFilter = Or<(
+    With<BoardBundle>,
+    With<MovingBrickBundle>,
+    With<ShadowBrickBundle>,
+    With<NextBrickBundle>,
+)>;
+
+/// Sent when the game view was laid out again for the orientation of the window,
+/// for the views around the board to follow.
+#[derive(Event)]
+pub struct GameViewRelayout;
 
 #[derive(Component)]
-struct LinesText(usize);
+struct ScoreText;
 
 #[derive(Component)]
-struct LevelText(usize);
+struct LinesText;
 
-#[derive(Debug, Resource)]
-struct DropTimer(Timer);
+#[derive(Component)]
+struct LevelText;
+
+#[derive(Resource, Default)]
+pub(crate) struct TickClock {
+    accumulator: f32,
+    pending_input: InputFrame,
+    // plays instead of the keyboard in the Watch AI mode
+    ai: Option<AiController>,
+}
 
 pub struct GamePlugin;
 
@@ -105,506 +297,484 @@ impl Plugin for GamePlugin {
             lines: 0,
         })
         .insert_resource(PauseStateRes::new(false, false))
-        .add_systems(
+        .init_resource::<TickClock>()
+        .init_resource::<BoardLayoutRes>()
+        .init_resource::<StackFadeRes>()
+        .add_event::<GameViewRelayout>()
+        .configure_sets(
             Update,
-            ((
-                brick_fall_down_system,
-                apply_deferred,
-                rotate_brick_key_event,
-                apply_deferred,
-                hard_drop_key_event,
-                apply_deferred,
-                move_brick_key_event,
-                apply_deferred,
-                soft_drop_key_event,
+            (
+                GameSystemSet::Layout,
+                GameSystemSet::Tick,
+                GameSystemSet::Events,
+                GameSystemSet::Render,
             )
-                .chain(),)
-                .run_if(is_not_pause_state.and_then(in_state(GameState::Game))),
+                .chain(),
         )
         .add_systems(
             Update,
-            pause_state_changed_event.run_if(in_state(GameState::Game)),
-        )
-        .add_systems(
-            OnEnter(GameState::Game),
-            (setup_game_data, apply_deferred, setup_tetris).chain(),
+            game_tick_system
+                .in_set(GameSystemSet::Tick)
+                .run_if(is_not_pause_state.and_then(in_state(GameState::Game))),
         )
         .add_systems(
-            OnExit(GameState::Game),
-            despawn_with_component::<BoardBundle>,
+            Update,
+            (update_game_data_system, game_over_system)
+                .in_set(GameSystemSet::Events)
+                .run_if(in_state(GameState::Game)),
         )
         .add_systems(
-            OnExit(GameState::Game),
-            despawn_with_component::<GameInfoBundle>,
+            Update,
+            render_game_system
+                .in_set(GameSystemSet::Render)
+                .run_if(in_state(GameState::Game).or_else(in_state(GameState::Replay))),
         )
         .add_systems(
-            OnExit(GameState::Game),
-            despawn_with_component::<NextBrickTitleBundle>,
+            Update,
+            relayout_game_system
+                .in_set(GameSystemSet::Layout)
+                .run_if(in_state(GameState::Game).or_else(in_state(GameState::Replay))),
         )
         .add_systems(
-            OnExit(GameState::Game),
-            despawn_with_component::<NextBrickBundle>,
+            Update,
+            pause_state_changed_event.run_if(in_state(GameState::Game)),
         )
         .add_systems(
-            OnExit(GameState::Game),
-            despawn_with_component::<ShadowBrickBundle>,
+            OnEnter(GameState::Game),
+            (setup_game_data, apply_deferred, setup_tetris).chain(),
         )
-        .insert_resource(DropTimer(Timer::new(
-            Duration::from_secs_f32(DEFAULT_NORMAL_FALLING_SPEED),
-            TimerMode::Repeating,
-        )));
+        .add_systems(OnExit(GameState::Game), despawn_game_view)
+        .add_systems(OnExit(GameState::Replay), despawn_game_view);
     }
 }
 
+/// Applies the rendering options of a difficulty level.
+pub fn apply_level_settings(level: GameSelectedLevel) {
+    let (show_shadow, show_lines) = match level {
+        GameSelectedLevel::Easy => (true, true),
+        GameSelectedLevel::Normal => (false, true),
+        GameSelectedLevel::Hard => (false, false),
+    };
+    ENABLE_SHOWING_SHADOW_BRICK.store(show_shadow, Ordering::Relaxed);
+    ENABLE_SHOWING_BOARD_LINES.store(show_lines, Ordering::Relaxed);
+}
+
 // init some game datas
-fn setup_game_data(
+pub(crate) fn setup_game_data(
+    mut commands: Commands,
     game_level: Res<GameLevelRes>,
-    mut drop_timer: ResMut<DropTimer>,
+    game_mode: Res<GameModeRes>,
+    game_options: Res<GameOptionsRes>,
+    watch_ai: Res<WatchAiRes>,
+    puzzle_res: Res<PuzzleRes>,
+    mut saved_game: ResMut<SavedGameRes>,
+    mut game_events: GameEventWriters,
+    screen: Res<ScreenLayoutRes>,
+    mut layout: ResMut<BoardLayoutRes>,
+    mut tick_clock: ResMut<TickClock>,
     mut game_scores_stored: ResMut<GameScoresRes>,
+    mut replay_recorder: ResMut<ReplayRecorderRes>,
+    mut last_replay: ResMut<LastReplayRes>,
 ) {
-    let duration: f32;
-    match game_level.0 {
-        GameSelectedLevel::Easy => {
-            duration = DEFAULT_EASY_FALLING_SPEED;
-            let _ = ENABLE_SHOWING_SHADOW_BRICK.compare_exchange(
-                false,
-                true,
-                Ordering::Relaxed,
-                Ordering::Relaxed,
-            );
-            let _ = ENABLE_SHOWING_BOARD_LINES.compare_exchange(
-                false,
-                true,
-                Ordering::Relaxed,
-                Ordering::Relaxed,
-            );
-            let _ = ENABLE_7_BAG_RANDOMIZATION.compare_exchange(
-                false,
-                true,
-                Ordering::Relaxed,
-                Ordering::Relaxed,
-            );
-        }
-        GameSelectedLevel::Normal => {
-            duration = DEFAULT_NORMAL_FALLING_SPEED;
-            let _ = ENABLE_SHOWING_SHADOW_BRICK.compare_exchange(
-                true,
-                false,
-                Ordering::Relaxed,
-                Ordering::Relaxed,
-            );
-            let _ = ENABLE_SHOWING_BOARD_LINES.compare_exchange(
-                false,
-                true,
-                Ordering::Relaxed,
-                Ordering::Relaxed,
-            );
-            let _ = ENABLE_7_BAG_RANDOMIZATION.compare_exchange(
-                false,
-                true,
-                Ordering::Relaxed,
-                Ordering::Relaxed,
-            );
-        }
-        GameSelectedLevel::Hard => {
-            duration = DEFAULT_HARD_FALLING_SPEED;
-            let _ = ENABLE_SHOWING_SHADOW_BRICK.compare_exchange(
-                true,
-                false,
-                Ordering::Relaxed,
-                Ordering::Relaxed,
-            );
-            let _ = ENABLE_SHOWING_BOARD_LINES.compare_exchange(
-                true,
-                false,
-                Ordering::Relaxed,
-                Ordering::Relaxed,
-            );
-            let _ = ENABLE_7_BAG_RANDOMIZATION.compare_exchange(
-                true,
-                false,
-                Ordering::Relaxed,
-                Ordering::Relaxed,
-            );
-        }
-    }
-
-    *BRICK_FALLING_SPEED.lock().unwrap() = duration;
-
-    drop_timer.0.set_duration(Duration::from_secs_f32(
-        *BRICK_FALLING_SPEED.lock().unwrap(),
-    ));
+    apply_level_settings(game_level.0);
+
+    let resumed = std::mem::take(&mut saved_game.resume)
+        .then(|| saved_game.replay.clone())
+        .flatten();
+    let engine = if let Some(replay) = resumed {
+        // the events rebuild the statistics and the replay of the saved game
+        let (columns, rows) = replay.options.board_size(replay.mode);
+        *layout = BoardLayoutRes::for_screen(columns, rows, *screen);
+        replay_recorder.0 = Some(Replay::new(
+            replay.difficulty,
+            replay.mode,
+            replay.seed,
+            replay.options,
+        ));
+        restore_engine(&replay, |event| game_events.send(event))
+    } else if let Some(puzzle) = puzzle_res.current_puzzle() {
+        // a replay only holds a seed, not the board of a puzzle, so none is recorded
+        let board = &puzzle.board;
+        *layout = BoardLayoutRes::for_screen(board.width(), board.visible_height(), *screen);
+        replay_recorder.0 = None;
+        last_replay.0 = None;
+        GameEngine::from_puzzle(game_level.0, puzzle)
+    } else {
+        let seed = game_options.seed.unwrap_or_else(rand::random);
+        let options = game_options.options;
+        let (columns, rows) = options.board_size(game_mode.0);
+        *layout = BoardLayoutRes::for_screen(columns, rows, *screen);
+        replay_recorder.0 = Some(Replay::new(game_level.0, game_mode.0, seed, options));
+        GameEngine::with_options(game_level.0, game_mode.0, seed, options)
+    };
+    *tick_clock = TickClock {
+        ai: watch_ai.0.then(|| AiController::new(WATCH_AI_ACTION_TICKS)),
+        ..default()
+    };
 
-    // reset score data
-    game_scores_stored.level = 1;
-    game_scores_stored.score = 0;
-    game_scores_stored.lines = 0;
+    // the score data of the new game, e.g. its start level or the score of the saved one
+    game_scores_stored.store(&engine);
+    commands.insert_resource(GameEngineRes(engine));
 }
 
-fn setup_tetris(mut commands: Commands, asset_server: Res<AssetServer>) {
-    spawn_board(&mut commands, &Board::new());
-    spawn_game_info(&mut commands, &asset_server);
-    spawn_next_brick_title(&mut commands, &asset_server);
-    spawn_next_brick(&mut commands, Brick::new(ENABLE_7_BAG_RANDOMIZATION.load(Ordering::Relaxed)));
+pub(crate) fn setup_tetris(
+    mut commands: Commands,
+    theme_res: Res<ThemeRes>,
+    locale: Res<LocaleRes>,
+    layout: Res<BoardLayoutRes>,
+) {
+    spawn_game_info(&mut commands, &theme_res, &locale, &layout);
+    spawn_next_brick_title(&mut commands, &theme_res, &locale, &layout);
 }
 
-#[inline]
-fn create_brick_start_position(brick_type: &BrickType) -> Position {
-    let mut pos = Position {
-        x: (BOARD_X / 2) as i32 - 2,
-        y: BOARD_Y as i32 - 6,
-    };
-    match brick_type {
-        BrickType::Z | BrickType::S | BrickType::I => pos.y += 1,
-        _ => (),
+fn despawn_game_view(
+    mut commands: Commands,
+    query: Query<
+        Entity,
+        Or<(
+            With<BoardBundle>,
+            With<GameInfoBundle>,
+            With<NextBrickTitleBundle>,
+            With<NextBrickBundle>,
+            With<MovingBrickBundle>,
+            With<ShadowBrickBundle>,
+        )>,
+    >,
+) {
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
     }
-    pos
 }
 
-fn brick_fall_down_system(
+// the board keeps its size in blocks, the window turned between landscape and portrait
+fn relayout_game_system(
     mut commands: Commands,
-    time: Res<Time>,
-    mut drop_timer: ResMut<DropTimer>,
-    mut moving_brick_query: Query<(Entity, &mut MovingBrickBundle, &mut Transform)>,
-    shadow_brick_query: Query<Entity, With<ShadowBrickBundle>>,
-    mut board_query: Query<(Entity, &mut BoardBundle)>,
-    next_brick_query: Query<(Entity, &mut NextBrickBundle)>,
-    mut text_query: ParamSet<(
-        Query<(&mut Text, &mut ScoreText)>,
-        Query<(&mut Text, &mut LinesText)>,
-        Query<(&mut Text, &mut LevelText)>,
-    )>,
-    mut play_state: ResMut<NextState<GameState>>,
-    mut game_scores_stored: ResMut<GameScoresRes>,
+    screen: Res<ScreenLayoutRes>,
+    theme_res: Res<ThemeRes>,
+    locale: Res<LocaleRes>,
+    mut layout: ResMut<BoardLayoutRes>,
+    mut engine: ResMut<GameEngineRes>,
+    mut relayout_events: EventWriter<GameViewRelayout>,
+    query: Query<Entity, Or<(With<GameInfoBundle>, With<NextBrickTitleBundle>)>>,
 ) {
-    if !drop_timer.0.tick(time.delta()).finished() {
+    if layout.screen == *screen {
         return;
     }
-    let mut board = board_query.get_single_mut().unwrap();
-    let Ok(next_brick) = next_brick_query.get_single() else {
-        return;
-    };
-
-    let create_new_next_brick_func = |commands: &mut Commands| {
-        commands.entity(next_brick.0).despawn_recursive();
-        spawn_next_brick(commands, Brick::new(ENABLE_7_BAG_RANDOMIZATION.load(Ordering::Relaxed)));
-    };
-
-    let Ok(mut moving_brick) = moving_brick_query.get_single_mut() else {
-        // in the initial state, no moving exists, so create a new one and the shadow brick
-        let brick = &next_brick.1 .0;
-        let init_brick_position = create_brick_start_position(&brick.0);
-
-        let bottom_pos = board
-            .1
-             .0
-            .get_bottom_valid_brick_pos(brick, &init_brick_position);
-
-        spawn_brick(&mut commands, brick, &init_brick_position);
-        spawn_shadow_brick(&mut commands, brick, &bottom_pos);
-
-        // recreate the next brick
-        create_new_next_brick_func(&mut commands);
-        return;
-    };
-
-    let is_next_moving_valid = board
-        .1
-         .0
-        .is_valid_brick(&moving_brick.1.brick, &moving_brick.1.moving_pos.down());
-    if is_next_moving_valid {
-        // do the falling down
-        moving_brick.1.moving_pos.down_assign();
-        moving_brick.2.translation.y -= BLOCK_WIDTH;
-    } else {
-        // make the brick occupy the board.
-        board
-            .1
-             .0
-            .occupy_brick(moving_brick.1.brick, moving_brick.1.moving_pos);
-        let cleaned_lines = board.1 .0.clean_lines();
-
-        commands.entity(moving_brick.0).despawn_recursive();
-        commands.entity(board.0).despawn_recursive();
-
-        if let Ok(shadow_bricks) = shadow_brick_query.get_single() {
-            commands.entity(shadow_bricks).despawn_recursive();
-        }
-
-        spawn_board(&mut commands, &board.1 .0);
-
-        let init_brick_position = create_brick_start_position(&next_brick.1 .0 .0);
-
-        let is_new_created_brick_valid = board
-            .1
-             .0
-            .is_valid_brick(&next_brick.1 .0, &init_brick_position);
-        if !is_new_created_brick_valid {
-            // game over
-            play_state.set(GameState::GameOver);
-            return;
-        }
-
-        let bottom_pos = board
-            .1
-             .0
-            .get_bottom_valid_brick_pos(&next_brick.1 .0, &init_brick_position);
-
-        spawn_brick(&mut commands, &next_brick.1 .0, &init_brick_position);
-        spawn_shadow_brick(&mut commands, &next_brick.1 .0, &bottom_pos);
-
-        create_new_next_brick_func(&mut commands);
-
-        if cleaned_lines > 0 {
-            let mut all_cleaned_lines = 0;
-            let mut level = 1;
-            let mut scores = 0;
-            if let Ok(mut text) = text_query.p1().get_single_mut() {
-                text.1 .0 += cleaned_lines;
-                text.0.sections[0].value = format!("{:}", text.1 .0);
-                all_cleaned_lines = text.1 .0;
-            }
-
-            if let Ok(mut text) = text_query.p2().get_single_mut() {
-                text.1 .0 = get_level(all_cleaned_lines);
-                text.0.sections[0].value = format!("{:}", text.1 .0);
-                level = text.1 .0;
-            }
-
-            if let Ok(mut text) = text_query.p0().get_single_mut() {
-                text.1 .0 += get_score(level, cleaned_lines);
-                text.0.sections[0].value = format!("{:}", text.1 .0);
-                scores = text.1 .0;
-            }
-
-            // store the data, it will be used when the game is finished.
-            game_scores_stored.level = level;
-            game_scores_stored.score = scores;
-            game_scores_stored.lines = all_cleaned_lines;
-        }
-        // reset falling speed to default
-        if let Ok(text) = text_query.p2().get_single() {
-            let level = text.1 .0;
-            let speed = *BRICK_FALLING_SPEED.lock().unwrap();
-            drop_timer
-                .0
-                .set_duration(Duration::from_secs_f32(get_speed(level, speed)));
-        }
+    *layout = BoardLayoutRes::for_screen(layout.columns, layout.rows, *screen);
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
     }
+    spawn_game_info(&mut commands, &theme_res, &locale, &layout);
+    spawn_next_brick_title(&mut commands, &theme_res, &locale, &layout);
+    engine.0.mark_dirty();
+    relayout_events.send(GameViewRelayout);
 }
 
-fn soft_drop_key_event(
+fn game_tick_system(
+    time: Res<Time>,
     keyboard_input: Res<Input<KeyCode>>,
-    level_query: Query<&LevelText>,
-    mut drop_timer: ResMut<DropTimer>,
+    mut tick_clock: ResMut<TickClock>,
+    mut engine: ResMut<GameEngineRes>,
+    mut game_events: GameEventWriters,
 ) {
-    if keyboard_input.just_pressed(KeyCode::J) || keyboard_input.just_pressed(KeyCode::Down) {
-        if drop_timer.0.duration().as_secs_f32() > MAX_FALLING_SPEED {
-            drop_timer
-                .0
-                .set_duration(Duration::from_secs_f32(MAX_FALLING_SPEED));
-        }
-    } else if keyboard_input.just_released(KeyCode::J)
-        || keyboard_input.just_released(KeyCode::Down)
-    {
-        let level: usize = level_query.get_single().unwrap().0;
-        let seted_speed = *BRICK_FALLING_SPEED.lock().unwrap();
-        let speed = get_speed(level, seted_speed);
-
-        drop_timer.0.set_duration(Duration::from_secs_f32(speed));
+    // keep the keys pressed during frames without any tick for the next tick
+    tick_clock.pending_input.0 |= KeyMap::SINGLE_PLAYER.read(&keyboard_input).0;
+    // a zen session has no game over, it ends with this key
+    if engine.0.zen_resets().is_some() && keyboard_input.just_pressed(END_SESSION_KEY) {
+        tick_clock.pending_input.insert(InputAction::EndSession);
     }
-}
-
-fn hard_drop_key_event(
-    keyboard_input: Res<Input<KeyCode>>,
-    mut moving_brick_query: Query<(Entity, &mut MovingBrickBundle, &mut Transform)>,
-    board_query: Query<&mut BoardBundle>,
-) {
-    if keyboard_input.just_pressed(KeyCode::Space) {
-        let Ok(mut moving_brick) = moving_brick_query.get_single_mut() else {
-            return;
-        };
-        let Ok(board) = board_query.get_single() else {
-            return;
+    tick_clock.accumulator =
+        (tick_clock.accumulator + time.delta_seconds()).min(MAX_TICKS_PER_FRAME * TICK_SECONDS);
+
+    let engine = &mut engine.0;
+    while tick_clock.accumulator >= TICK_SECONDS && !engine.is_game_over() {
+        tick_clock.accumulator -= TICK_SECONDS;
+        let pending = std::mem::take(&mut tick_clock.pending_input);
+        let mut input = match tick_clock.ai.as_mut() {
+            Some(ai) => ai.next_input(engine),
+            None => pending,
         };
-        let current_brick = moving_brick.1.brick;
-        let moving_pos = &mut moving_brick.1.moving_pos;
-
-        while board.0.is_valid_brick(&current_brick, &moving_pos.down()) {
-            moving_pos.down_assign();
-            moving_brick.2.translation.y -= BLOCK_WIDTH;
+        // the player also ends the zen sessions played by the AI
+        if pending.contains(InputAction::EndSession) {
+            input.insert(InputAction::EndSession);
+        }
+        engine.step(input);
+        for event in engine.drain_events() {
+            game_events.send(event);
         }
-        // hard dropped, then it cannot be moved again
-        moving_brick.1.movable = false;
     }
 }
 
-fn rotate_brick_key_event(
-    mut commands: Commands,
-    keyboard_input: Res<Input<KeyCode>>,
-    mut moving_brick_query: Query<(Entity, &mut MovingBrickBundle, &mut Transform)>,
-    shadow_brick_query: Query<Entity, With<ShadowBrickBundle>>,
-    board_query: Query<&mut BoardBundle>,
+fn update_game_data_system(
+    mut cleared_events: EventReader<LinesCleared>,
+    engine: Res<GameEngineRes>,
+    mut game_scores_stored: ResMut<GameScoresRes>,
 ) {
-    if !(keyboard_input.just_pressed(KeyCode::Up)) {
+    if cleared_events.read().count() == 0 {
         return;
     }
+    game_scores_stored.store(&engine.0);
+}
 
-    // use "let else"
-    let Ok(mut moving_brick) = moving_brick_query.get_single_mut() else {
-        return;
-    };
-    let Ok(board) = board_query.get_single() else {
-        return;
-    };
-
-    // when the brick fast dropped down, it become non movable.
-    if !moving_brick.1.movable {
-        return;
+fn game_over_system(
+    time: Res<Time>,
+    mut game_over_events: EventReader<GameOver>,
+    mut goal_events: EventReader<GoalReached>,
+    engine: Res<GameEngineRes>,
+    mut game_scores_stored: ResMut<GameScoresRes>,
+    mut reveal_timer: Local<Option<Timer>>,
+    mut play_state: ResMut<NextState<GameState>>,
+) {
+    if game_over_events.read().count() + goal_events.read().count() > 0 {
+        // e.g. the points of a soft or hard drop after the last line clear
+        game_scores_stored.store(&engine.0);
+        if engine.0.stack_visible_ticks().is_some() {
+            *reveal_timer = Some(Timer::from_seconds(
+                GAME_OVER_REVEAL_SECONDS,
+                TimerMode::Once,
+            ));
+        } else {
+            play_state.set(GameState::GameOver);
+        }
     }
-
-    let rotated_brick = moving_brick.1.brick.rotate_right();
-    let moving_pos = &mut moving_brick.1.moving_pos;
-
-    if board
-        .0
-        .is_valid_brick_for_rotation(&rotated_brick, moving_pos)
-    {
-        let bottom_pos = board
-            .0
-            .get_bottom_valid_brick_pos(&rotated_brick, moving_pos);
-        spawn_brick(&mut commands, &rotated_brick, moving_pos);
-        spawn_shadow_brick(&mut commands, &rotated_brick, &bottom_pos);
-        if let Ok(shadow_bricks) = shadow_brick_query.get_single() {
-            commands.entity(shadow_bricks).despawn_recursive();
+    // left during the reveal, e.g. from the pause menu
+    if !engine.0.is_game_over() {
+        *reveal_timer = None;
+    }
+    if let Some(timer) = reveal_timer.as_mut() {
+        if timer.tick(time.delta()).finished() {
+            *reveal_timer = None;
+            play_state.set(GameState::GameOver);
         }
-        commands.entity(moving_brick.0).despawn_recursive();
     }
 }
 
-fn move_brick_key_event(
+fn render_game_system(
     mut commands: Commands,
-    keyboard_input: Res<Input<KeyCode>>,
-    mut moving_brick_query: Query<(Entity, &mut MovingBrickBundle, &mut Transform)>,
-    shadow_brick_query: Query<Entity, With<ShadowBrickBundle>>,
-    board_query: Query<&mut BoardBundle>,
+    mut engine: ResMut<GameEngineRes>,
+    mut stack_fade: ResMut<StackFadeRes>,
+    layout: Res<BoardLayoutRes>,
+    theme_res: Res<ThemeRes>,
+    dynamic_query: Query<Entity, EngineViewFilter>,
+    mut text_query: ParamSet<(
+        Query<&mut Text, With<ScoreText>>,
+        Query<&mut Text, With<LinesText>>,
+        Query<&mut Text, With<LevelText>>,
+    )>,
 ) {
-    if !(keyboard_input.just_pressed(KeyCode::Left) || keyboard_input.just_pressed(KeyCode::Right))
-    {
+    let faded = stack_fade.0.update(&engine.0);
+    if !engine.0.take_dirty() && !faded {
         return;
     }
+    let engine = &engine.0;
 
-    // use "let else"
-    let Ok(mut moving_brick) = moving_brick_query.get_single_mut() else {
-        return;
-    };
-    let Ok(board) = board_query.get_single() else {
-        return;
-    };
-
-    // when the brick fast dropped down, it become non movable.
-    if !moving_brick.1.movable {
-        return;
+    for entity in &dynamic_query {
+        commands.entity(entity).despawn_recursive();
     }
 
-    let is_left_moving = keyboard_input.just_pressed(KeyCode::Left);
+    spawn_engine_view(
+        &mut commands,
+        engine,
+        Some(&stack_fade.0),
+        &layout,
+        &theme_res,
+    );
 
-    let current_brick = moving_brick.1.brick;
-    let moving_pos = moving_brick.1.moving_pos;
+    if let Ok(mut text) = text_query.p0().get_single_mut() {
+        text.sections[0].value = format!("{:}", engine.score);
+    }
+    if let Ok(mut text) = text_query.p1().get_single_mut() {
+        text.sections[0].value = format!("{:}", engine.lines);
+    }
+    if let Ok(mut text) = text_query.p2().get_single_mut() {
+        text.sections[0].value = format!("{:}", engine.level);
+    }
+}
 
-    let next_pos = if is_left_moving {
-        moving_pos.left()
-    } else {
-        moving_pos.right()
-    };
+/// Draws the board, the falling brick with its shadow and the next brick of an engine.
+pub(crate) fn spawn_engine_view(
+    commands: &mut Commands,
+    engine: &GameEngine,
+    stack_fade: Option<&StackFade>,
+    layout: &BoardLayoutRes,
+    theme_res: &ThemeRes,
+) {
+    spawn_board_view(
+        commands,
+        &engine.board,
+        engine.active.as_ref(),
+        // a puzzle has no brick after its last one
+        (engine.pieces_left() != Some(0)).then_some(engine.next),
+        stack_fade,
+        layout,
+        theme_res,
+    );
+}
 
-    if board.0.is_valid_brick(&current_brick, &next_pos) {
-        moving_brick.1.moving_pos = next_pos;
-        if is_left_moving {
-            moving_brick.2.translation.x -= BLOCK_WIDTH;
-        } else {
-            moving_brick.2.translation.x += BLOCK_WIDTH;
-        }
-        // dismiss shadow brick
-        if let Ok(shadow_bricks) = shadow_brick_query.get_single() {
-            commands.entity(shadow_bricks).despawn_recursive();
-        }
-        let bottom_pos = board
-            .0
-            .get_bottom_valid_brick_pos(&current_brick, &next_pos);
-        // redraw shadow brick
-        spawn_shadow_brick(&mut commands, &current_brick, &bottom_pos);
+/// Same as `spawn_engine_view`, for a board which is not simulated here, e.g. an online opponent.
+pub(crate) fn spawn_board_view(
+    commands: &mut Commands,
+    board: &Board,
+    active: Option<&ActiveBrick>,
+    next: Option<Brick>,
+    stack_fade: Option<&StackFade>,
+    layout: &BoardLayoutRes,
+    theme_res: &ThemeRes,
+) {
+    spawn_board(commands, board, stack_fade, layout, theme_res);
+    if let Some(active) = active {
+        let block_size = board.block_size();
+        spawn_brick(
+            commands,
+            &active.brick,
+            &active.pos,
+            block_size,
+            layout,
+            theme_res,
+        );
+        let shadow_pos = board.get_bottom_valid_brick_pos(&active.brick, &active.pos);
+        spawn_shadow_brick(
+            commands,
+            &active.brick,
+            &shadow_pos,
+            block_size,
+            layout,
+            theme_res,
+        );
+    }
+    if let Some(next) = next {
+        spawn_next_brick(commands, next, layout, theme_res);
     }
 }
 
 #[inline]
-fn spawn_brick(commands: &mut Commands, brick: &Brick, moving_pos: &Position) {
-    let board_width = BLOCK_WIDTH * BOARD_VIEW_X as f32;
-    let board_height = BLOCK_WIDTH * BOARD_VIEW_Y as f32;
+fn spawn_brick(
+    commands: &mut Commands,
+    brick: &Brick,
+    moving_pos: &Position,
+    block_size: usize,
+    layout: &BoardLayoutRes,
+    theme_res: &ThemeRes,
+) {
+    let block_width = layout.block_width;
     commands
         .spawn(SpriteBundle {
             transform: Transform::from_xyz(
-                moving_pos.x as f32 * BLOCK_WIDTH - board_width / 2.0 + BLOCK_WIDTH / 2.,
-                moving_pos.y as f32 * BLOCK_WIDTH - board_height / 2.0 + BLOCK_WIDTH / 2.,
+                layout.origin_x - layout.board_width() / 2. + block_width / 2.,
+                layout.origin_y - layout.board_height() / 2. + block_width / 2.,
                 0.1,
             ),
             ..default()
         })
-        .insert(MovingBrickBundle {
-            moving_pos: *moving_pos,
-            brick: brick.clone(),
-            movable: true,
-        })
+        .insert(MovingBrickBundle)
         .with_children(|parent| {
-            for pos in brick.1 {
-                let color = Color::hex(&BRICK_COLOR_MAP[&brick.0]).unwrap();
-                parent.spawn(sprite_bundle(
-                    BLOCK_WIDTH,
-                    color,
-                    position_to_vec2(&pos, 0.3),
-                ));
+            // the cells in the vanish zone are hidden
+            for pos in brick
+                .cells(*moving_pos, block_size)
+                .filter(|pos| is_cell_visible(pos, layout))
+            {
+                spawn_block(
+                    parent,
+                    block_width,
+                    brick.0,
+                    theme_res,
+                    position_to_vec2(&pos, block_width, 0.3),
+                );
             }
         });
 }
 
 #[inline]
-fn spawn_shadow_brick(commands: &mut Commands, brick: &Brick, shadow_pos: &Position) {
+fn spawn_shadow_brick(
+    commands: &mut Commands,
+    brick: &Brick,
+    shadow_pos: &Position,
+    block_size: usize,
+    layout: &BoardLayoutRes,
+    theme_res: &ThemeRes,
+) {
     if !ENABLE_SHOWING_SHADOW_BRICK.load(Ordering::Relaxed) {
         return;
     }
 
-    let board_width = BLOCK_WIDTH * BOARD_VIEW_X as f32;
-    let board_height = BLOCK_WIDTH * BOARD_VIEW_Y as f32;
+    let block_width = layout.block_width;
     commands
         .spawn(SpriteBundle {
             transform: Transform::from_xyz(
-                shadow_pos.x as f32 * BLOCK_WIDTH - board_width / 2.0 + BLOCK_WIDTH / 2.,
-                shadow_pos.y as f32 * BLOCK_WIDTH - board_height / 2.0 + BLOCK_WIDTH / 2.,
+                layout.origin_x - layout.board_width() / 2. + block_width / 2.,
+                layout.origin_y - layout.board_height() / 2. + block_width / 2.,
                 0.0,
             ),
             ..default()
         })
         .insert(ShadowBrickBundle)
         .with_children(|parent| {
-            for pos in brick.1 {
-                let color = Color::rgb_u8(90, 90, 90);
+            for pos in brick
+                .cells(*shadow_pos, block_size)
+                .filter(|pos| is_cell_visible(pos, layout))
+            {
+                spawn_ghost_block(
+                    parent,
+                    block_width,
+                    brick.0,
+                    theme_res,
+                    position_to_vec2(&pos, block_width, 0.2),
+                );
+            }
+        });
+}
+
+/// Draws single cells over the board, e.g. the hints.
+pub(crate) fn spawn_cells(
+    commands: &mut Commands,
+    cells: &[Position],
+    color: Color,
+    layout: &BoardLayoutRes,
+    marker: impl Component,
+) {
+    let block_width = layout.block_width;
+    commands
+        .spawn(SpriteBundle {
+            transform: Transform::from_xyz(
+                layout.origin_x - layout.board_width() / 2. + block_width / 2.,
+                layout.origin_y - layout.board_height() / 2. + block_width / 2.,
+                0.0,
+            ),
+            ..default()
+        })
+        .insert(marker)
+        .with_children(|parent| {
+            for pos in cells.iter().filter(|pos| is_cell_visible(pos, layout)) {
+                // above the shadow brick and below the moving brick
                 parent.spawn(sprite_bundle(
-                    BLOCK_WIDTH,
+                    block_width,
                     color,
-                    position_to_vec2(&pos, 0.2),
+                    None,
+                    position_to_vec2(pos, block_width, 0.25),
                 ));
             }
         });
 }
 
 #[inline]
-fn position_to_vec2(pos: &Position, z: f32) -> Vec3 {
-    Vec3::new(BLOCK_WIDTH * pos.x as f32, BLOCK_WIDTH * pos.y as f32, z)
+fn is_cell_visible(pos: &Position, layout: &BoardLayoutRes) -> bool {
+    (pos.y as usize) < layout.rows
+}
+
+#[inline]
+fn position_to_vec2(pos: &Position, block_width: f32, z: f32) -> Vec3 {
+    Vec3::new(block_width * pos.x as f32, block_width * pos.y as f32, z)
 }
 
 #[inline]
-fn sprite_bundle(width: f32, color: Color, trans: Vec3) -> SpriteBundle {
+fn sprite_bundle(
+    width: f32,
+    color: Color,
+    texture: Option<Handle<Image>>,
+    trans: Vec3,
+) -> SpriteBundle {
     SpriteBundle {
+        texture: texture.unwrap_or_default(),
         transform: Transform {
             translation: trans,
             ..default()
@@ -618,34 +788,118 @@ fn sprite_bundle(width: f32, color: Color, trans: Vec3) -> SpriteBundle {
     }
 }
 
-fn spawn_board(commands: &mut Commands, board: &Board) {
-    let board_width = BLOCK_WIDTH * BOARD_VIEW_X as f32;
-    let board_height = BLOCK_WIDTH * BOARD_VIEW_Y as f32;
+/// A block of a brick type, with its pattern when the patterns are on.
+fn spawn_block(
+    parent: &mut ChildBuilder,
+    width: f32,
+    brick_type: BrickType,
+    theme_res: &ThemeRes,
+    trans: Vec3,
+) {
+    let color = theme_res.theme().brick_color(brick_type);
+    parent
+        .spawn(sprite_bundle(
+            width,
+            color,
+            theme_res.block_texture(brick_type),
+            trans,
+        ))
+        .with_children(|parent| {
+            spawn_pattern(
+                parent,
+                width,
+                theme_res.block_pattern(brick_type),
+                pattern_color(color),
+            );
+        });
+}
+
+/// A block of the shadow brick, a bright outline instead of a dim block with the high contrast ghost.
+fn spawn_ghost_block(
+    parent: &mut ChildBuilder,
+    width: f32,
+    brick_type: BrickType,
+    theme_res: &ThemeRes,
+    trans: Vec3,
+) {
+    let theme = theme_res.theme();
+    let pattern = theme_res.block_pattern(brick_type);
+    if !theme_res.accessibility().high_contrast_ghost {
+        parent
+            .spawn(sprite_bundle(width, theme.ghost, None, trans))
+            .with_children(|parent| {
+                spawn_pattern(parent, width, pattern, pattern_color(theme.ghost));
+            });
+        return;
+    }
+    let outline = (width / 8.).max(2.);
+    parent
+        .spawn(sprite_bundle(width, HIGH_CONTRAST_GHOST, None, trans))
+        .with_children(|parent| {
+            let inner = theme.brick_color(BrickType::None);
+            parent.spawn(sprite_bundle(
+                width - 2. * outline,
+                inner,
+                None,
+                Vec3::new(0., 0., 0.01),
+            ));
+            spawn_pattern(parent, width, pattern, HIGH_CONTRAST_GHOST);
+        });
+}
+
+fn spawn_pattern(parent: &mut ChildBuilder, width: f32, pattern: &[PatternBar], color: Color) {
+    for bar in pattern {
+        parent.spawn(SpriteBundle {
+            transform: Transform {
+                translation: (bar.center * width).extend(0.02),
+                rotation: Quat::from_rotation_z(bar.angle),
+                ..default()
+            },
+            sprite: Sprite {
+                color,
+                custom_size: Some(bar.size * width),
+                ..default()
+            },
+            ..default()
+        });
+    }
+}
+
+fn spawn_board(
+    commands: &mut Commands,
+    board: &Board,
+    stack_fade: Option<&StackFade>,
+    layout: &BoardLayoutRes,
+    theme_res: &ThemeRes,
+) {
+    let theme = theme_res.theme();
+    let block_width = layout.block_width;
+    let board_width = layout.board_width();
+    let board_height = layout.board_height();
 
     commands
         .spawn(SpriteBundle {
             transform: Transform::from_xyz(
-                (-board_width) / 2. + BLOCK_WIDTH / 2.,
-                (-board_height) / 2. + BLOCK_WIDTH / 2.,
+                layout.origin_x + (-board_width) / 2. + block_width / 2.,
+                layout.origin_y + (-board_height) / 2. + block_width / 2.,
                 0.0, //zero,which one pixel behind the UI-screen png; cannot be seen in screen
             ),
             ..default()
         })
-        .insert(BoardBundle(board.clone()))
+        .insert(BoardBundle)
         .with_children(|parent| {
-            let line_color: Color;
-            if ENABLE_SHOWING_BOARD_LINES.load(Ordering::Relaxed) {
-                line_color = Color::rgb_u8(32, 31, 30);
+            let line_color = if ENABLE_SHOWING_BOARD_LINES.load(Ordering::Relaxed) {
+                theme.grid
             } else {
                 // use the color of the default brick, then the lines cannot be seen
-                line_color = Color::hex(&BRICK_COLOR_MAP[&BrickType::None]).unwrap();
-            }
+                theme.brick_color(BrickType::None)
+            };
             // this is the background color view
             parent.spawn(SpriteBundle {
                 transform: Transform {
                     translation: Vec3::new(
-                        (board_width) / 2. - BLOCK_WIDTH / 2.,
-                        (board_height) / 2. - BLOCK_WIDTH / 2.,
+                        (board_width) / 2. - block_width / 2.,
+                        (board_height) / 2. - block_width / 2.,
                         0.0,
                     ),
                     ..default()
@@ -661,112 +915,122 @@ fn spawn_board(commands: &mut Commands, board: &Board) {
                 ..default()
             });
             // show all the bricks
-            for x in 0..BOARD_VIEW_X {
-                for y in 0..BOARD_VIEW_Y {
-                    let color = Color::hex(&BRICK_COLOR_MAP[&board.0[x][y]]).unwrap();
-                    parent.spawn(sprite_bundle(
-                        BLOCK_WIDTH,
-                        color,
-                        Vec3::new(x as f32 * BLOCK_WIDTH, y as f32 * BLOCK_WIDTH, 0.1),
-                    ));
+            for x in 0..board.width() {
+                for y in 0..board.visible_height() {
+                    let trans = Vec3::new(x as f32 * block_width, y as f32 * block_width, 0.1);
+                    let visibility = stack_fade.map_or(1., |fade| fade.visibility(x, y));
+                    if visibility <= 0. {
+                        spawn_block(parent, block_width, BrickType::None, theme_res, trans);
+                        continue;
+                    }
+                    spawn_block(parent, block_width, board.get(x, y), theme_res, trans);
+                    if visibility < 1. {
+                        // a fading block is covered by an empty cell growing opaque
+                        let mut empty = theme.brick_color(BrickType::None);
+                        empty.set_a(1. - visibility);
+                        parent.spawn(sprite_bundle(
+                            block_width,
+                            empty,
+                            None,
+                            trans + Vec3::Z * 0.05,
+                        ));
+                    }
                 }
             }
         });
 }
 
-fn spawn_game_info(commands: &mut Commands, asset_server: &Res<AssetServer>) {
-    let board_width = BLOCK_WIDTH * BOARD_X as f32;
+fn spawn_game_info(
+    commands: &mut Commands,
+    theme_res: &ThemeRes,
+    locale: &Locale,
+    layout: &BoardLayoutRes,
+) {
     commands
-        .spawn((
-            SpriteBundle {
-                transform: Transform::from_xyz(-(board_width / 2. + 80.), 0., 0.),
-                ..default()
-            },
-            GameInfoBundle,
-        ))
+        .spawn((SpriteBundle::default(), GameInfoBundle))
         .with_children(|parent| {
-            let up_margin: f32 = 60.;
-            let top_y: f32 = 180.;
-            let x: f32 = -50.;
-            parent.spawn(create_text_bundle("SCORE", x, top_y, &asset_server));
-            parent
-                .spawn(create_text_bundle("0", x, top_y - up_margin, &asset_server))
-                .insert(ScoreText(0));
-            parent.spawn(create_text_bundle(
-                "LEVEL",
-                x,
-                top_y - 2. * up_margin,
-                &asset_server,
-            ));
-            parent
-                .spawn(create_text_bundle(
-                    "1",
-                    x,
-                    top_y - 3. * up_margin,
-                    &asset_server,
-                ))
-                .insert(LevelText(1));
-            parent.spawn(create_text_bundle(
-                "LINES",
-                x,
-                top_y - 4. * up_margin,
-                &asset_server,
-            ));
-            parent
-                .spawn(create_text_bundle(
-                    "0",
-                    x,
-                    top_y - 5. * up_margin,
-                    &asset_server,
-                ))
-                .insert(LinesText(0));
+            let infos = [
+                ("game-score", "0"),
+                ("game-level", "1"),
+                ("game-lines", "0"),
+            ];
+            for (i, (title, value)) in infos.into_iter().enumerate() {
+                let pos = layout.info_position(i);
+                parent.spawn(create_text_bundle(
+                    locale.text(title),
+                    pos.x,
+                    pos.y,
+                    theme_res,
+                ));
+                let value = create_text_bundle(value, pos.x, pos.y - INFO_LINE_HEIGHT, theme_res);
+                match i {
+                    0 => parent.spawn(value).insert(ScoreText),
+                    1 => parent.spawn(value).insert(LevelText),
+                    _ => parent.spawn(value).insert(LinesText),
+                };
+            }
         });
 }
 
-fn spawn_next_brick_title(commands: &mut Commands, asset_server: &Res<AssetServer>) {
-    let board_width = BLOCK_WIDTH * BOARD_VIEW_X as f32;
-
+fn spawn_next_brick_title(
+    commands: &mut Commands,
+    theme_res: &ThemeRes,
+    locale: &Locale,
+    layout: &BoardLayoutRes,
+) {
+    let pos = layout.next_title_position();
     commands
         .spawn((
             SpriteBundle {
-                transform: Transform::from_xyz(board_width / 2. + 100., 180., 0.),
+                transform: Transform::from_translation(pos.extend(0.)),
                 ..default()
             },
             NextBrickTitleBundle,
         ))
         .with_children(|parent| {
-            parent.spawn(create_text_bundle("NEXT", 0., 0., &asset_server));
+            parent.spawn(create_text_bundle(
+                locale.text("game-next"),
+                0.,
+                0.,
+                theme_res,
+            ));
         });
 }
 
-fn spawn_next_brick(commands: &mut Commands, brick: Brick) {
-    let board_width = BLOCK_WIDTH * BOARD_X as f32;
+fn spawn_next_brick(
+    commands: &mut Commands,
+    brick: Brick,
+    layout: &BoardLayoutRes,
+    theme_res: &ThemeRes,
+) {
+    let block_width = layout.block_width;
     commands
         .spawn(SpriteBundle {
-            transform: Transform::from_xyz(board_width / 2. + 70., 10., 0.),
+            transform: Transform::from_translation(layout.next_brick_position().extend(0.)),
             ..default()
         })
         .with_children(|parent| {
             for pos in brick.1 {
-                let color = Color::hex(&BRICK_COLOR_MAP[&brick.0]).unwrap();
-                parent.spawn(sprite_bundle(
-                    BLOCK_WIDTH,
-                    color,
-                    position_to_vec2(&pos, 0.1),
-                ));
+                spawn_block(
+                    parent,
+                    block_width,
+                    brick.0,
+                    theme_res,
+                    position_to_vec2(&pos, block_width, 0.1),
+                );
             }
         })
-        .insert(NextBrickBundle(brick));
+        .insert(NextBrickBundle);
 }
 
-fn create_text_bundle(msg: &str, x: f32, y: f32, asset_server: &Res<AssetServer>) -> Text2dBundle {
+fn create_text_bundle(msg: &str, x: f32, y: f32, theme_res: &ThemeRes) -> Text2dBundle {
     Text2dBundle {
         text: Text::from_section(
             msg,
             TextStyle {
-                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                font: theme_res.font(),
                 font_size: 42.0,
-                color: GAME_DATA_TEXT_COLOR,
+                color: theme_res.theme().ui.game_text,
             },
         )
         .with_alignment(TextAlignment::Center),
@@ -1,9 +1,14 @@
-
-
 use bevy::{app::AppExit, prelude::*};
 
-use crate::{utils::{despawn_with_component, common_button_system}, constants::{BACKGROUND, TEXT_COLOR}, common_entity::EntitySpawner, GameState, game::GameScoresRes};
-
+use crate::{
+    common_entity::EntitySpawner,
+    game::{GameEngineRes, GameScoresRes},
+    locale::LocaleRes,
+    stats::GameStatsRes,
+    theme::ThemeRes,
+    utils::{common_button_system, despawn_with_component},
+    GameState,
+};
 
 #[derive(Component)]
 struct GameOverMenuScreen;
@@ -12,6 +17,7 @@ struct GameOverMenuScreen;
 #[derive(Component)]
 enum GameOverMenuButtonAction {
     Back,
+    WatchReplay,
     Quit,
 }
 
@@ -19,8 +25,7 @@ pub struct GameOverPlugin;
 
 impl Plugin for GameOverPlugin {
     fn build(&self, app: &mut App) {
-        app
-            .add_systems(OnEnter(GameState::GameOver), gameover_menu_setup)
+        app.add_systems(OnEnter(GameState::GameOver), gameover_menu_setup)
             .add_systems(
                 OnExit(GameState::GameOver),
                 despawn_with_component::<GameOverMenuScreen>,
@@ -29,8 +34,17 @@ impl Plugin for GameOverPlugin {
     }
 }
 
-fn gameover_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>, game_scores_stored: Res<GameScoresRes>) {
-    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+fn gameover_menu_setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    theme_res: Res<ThemeRes>,
+    game_scores_stored: Res<GameScoresRes>,
+    game_stats: Res<GameStatsRes>,
+    engine: Res<GameEngineRes>,
+    locale: Res<LocaleRes>,
+) {
+    let font = theme_res.font();
+    let ui = theme_res.theme().ui;
 
     commands
         .spawn((
@@ -55,34 +69,72 @@ fn gameover_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>, g
                         padding: UiRect::px(20., 20., 10., 30.),
                         ..default()
                     },
-                    background_color: BACKGROUND.into(),
+                    background_color: ui.panel.into(),
                     ..default()
                 })
                 .with_children(|parent| {
                     // Display the game name
+                    let title = if engine.0.zen_resets().is_some() {
+                        locale.text("gameover-zen")
+                    } else if engine.0.goal_reached {
+                        locale.text("gameover-complete")
+                    } else {
+                        locale.text("gameover-title")
+                    };
                     parent.spawn(
                         TextBundle::from_section(
-                            "GAME OVER",
+                            title,
                             TextStyle {
                                 font: font.clone(),
                                 font_size: 80.0,
-                                color: TEXT_COLOR,
+                                color: ui.text,
                             },
                         )
                         .with_style(Style {
-                            margin: UiRect::all(Val::Px(50.0)),
+                            margin: UiRect::all(Val::Px(20.0)),
                             ..default()
                         }),
                     );
 
-                    let game_score = format!("Score : {:}   Level : {:}   Lines : {:}", game_scores_stored.score, game_scores_stored.level, game_scores_stored.lines);
+                    // Display how the game was lost
+                    if let Some(top_out) = engine.0.top_out {
+                        parent.spawn(TextBundle::from_section(
+                            locale.text(top_out.message_id()),
+                            TextStyle {
+                                font: font.clone(),
+                                font_size: 30.0,
+                                color: ui.text,
+                            },
+                        ));
+                    }
+
+                    // a zen session is never lost, the top outs only emptied the board
+                    if let Some(resets) = engine.0.zen_resets() {
+                        parent.spawn(TextBundle::from_section(
+                            locale.format("gameover-resets", &[("resets", &resets)]),
+                            TextStyle {
+                                font: font.clone(),
+                                font_size: 30.0,
+                                color: ui.text,
+                            },
+                        ));
+                    }
+
+                    let game_score = locale.format(
+                        "gameover-score",
+                        &[
+                            ("score", &game_scores_stored.score),
+                            ("level", &game_scores_stored.level),
+                            ("lines", &game_scores_stored.lines),
+                        ],
+                    );
                     parent.spawn(
                         TextBundle::from_section(
                             game_score,
                             TextStyle {
                                 font: font.clone(),
                                 font_size: 30.0,
-                                color: TEXT_COLOR,
+                                color: ui.text,
                             },
                         )
                         .with_style(Style {
@@ -91,8 +143,79 @@ fn gameover_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>, g
                         }),
                     );
 
-                    parent.spawn_button(GameOverMenuButtonAction::Back, "right.png", "Main Menu", &asset_server);
-                    parent.spawn_button(GameOverMenuButtonAction::Quit, "exitRight.png", "Quit", &asset_server);
+                    let stats_text_style = TextStyle {
+                        font: font.clone(),
+                        font_size: 24.0,
+                        color: ui.text,
+                    };
+                    // Display the statistics in two columns
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                width: Val::Px(600.0),
+                                flex_direction: FlexDirection::Row,
+                                flex_wrap: FlexWrap::Wrap,
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for (label, value) in game_stats.0.summary() {
+                                parent.spawn(
+                                    TextBundle::from_section(
+                                        format!("{} : {}", locale.text(label), value),
+                                        stats_text_style.clone(),
+                                    )
+                                    .with_style(Style {
+                                        width: Val::Percent(50.0),
+                                        margin: UiRect::vertical(Val::Px(4.0)),
+                                        ..default()
+                                    }),
+                                );
+                            }
+                        });
+                    parent.spawn(
+                        TextBundle::from_section(
+                            game_stats.0.piece_distribution(),
+                            stats_text_style,
+                        )
+                        .with_style(Style {
+                            margin: UiRect::all(Val::Px(10.0)),
+                            ..default()
+                        }),
+                    );
+
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Row,
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            parent.spawn_button(
+                                GameOverMenuButtonAction::Back,
+                                "right.png",
+                                locale.text("gameover-main-menu"),
+                                &asset_server,
+                                &theme_res,
+                            );
+                            parent.spawn_button(
+                                GameOverMenuButtonAction::WatchReplay,
+                                "right.png",
+                                locale.text("gameover-watch-replay"),
+                                &asset_server,
+                                &theme_res,
+                            );
+                            parent.spawn_button(
+                                GameOverMenuButtonAction::Quit,
+                                "exitRight.png",
+                                locale.text("menu-quit"),
+                                &asset_server,
+                                &theme_res,
+                            );
+                        });
                 });
         });
 }
@@ -119,7 +242,10 @@ fn menu_action(
                 GameOverMenuButtonAction::Back => {
                     game_state.set(GameState::Menu);
                 }
+                GameOverMenuButtonAction::WatchReplay => {
+                    game_state.set(GameState::Replay);
+                }
             }
         }
     }
-}
\ No newline at end of file
+}
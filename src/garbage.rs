@@ -0,0 +1,60 @@
+#![allow(dead_code)]
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+// mixed into the game seed, so the holes do not follow the brick sequence
+const GARBAGE_SEED_SALT: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// All the rows raised at once have their hole in the same column.
+pub const CLEAN_HOLE_CHANGE_RATE: f64 = 0.;
+/// Every garbage row has its hole in another column than the row below it.
+pub const MESSY_HOLE_CHANGE_RATE: f64 = 1.;
+
+/// Seeded generator of the hole columns of garbage rows.
+/// The hole moves for every batch of rows raised at once,
+/// and inside a batch it moves with the hole change rate.
+#[derive(Clone)]
+pub struct GarbageGenerator {
+    rng: StdRng,
+    width: usize,
+    hole: usize,
+    // the chance for the hole of a row to move away from the hole of the previous row
+    hole_change_rate: f64,
+}
+
+impl GarbageGenerator {
+    pub fn new(seed: u64, width: usize, hole_change_rate: f64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed ^ GARBAGE_SEED_SALT);
+        let hole = rng.gen_range(0..width);
+        GarbageGenerator {
+            rng,
+            width,
+            hole,
+            hole_change_rate: hole_change_rate.clamp(0., 1.),
+        }
+    }
+
+    pub fn set_hole_change_rate(&mut self, hole_change_rate: f64) {
+        self.hole_change_rate = hole_change_rate.clamp(0., 1.);
+    }
+
+    /// Moves the hole to another column, e.g. between two attacks of clean garbage.
+    pub fn change_hole(&mut self) {
+        if self.width > 1 {
+            self.hole = (self.hole + self.rng.gen_range(1..self.width)) % self.width;
+        }
+    }
+
+    /// The hole columns of the next batch of `count` rows, from the bottom one to the top one.
+    pub fn next_holes(&mut self, count: usize) -> Vec<usize> {
+        self.change_hole();
+        (0..count)
+            .map(|i| {
+                if i > 0 && self.rng.gen_bool(self.hole_change_rate) {
+                    self.change_hole();
+                }
+                self.hole
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,115 @@
+use bevy::prelude::*;
+
+use crate::{
+    ai::{best_placement, covered_cells, Placement, Weights},
+    engine::ActiveBrick,
+    game::{spawn_cells, BoardLayoutRes, GameEngineRes, GameSystemSet},
+    position::Position,
+    utils::despawn_with_component,
+    GameState,
+};
+
+const HINT_KEY: KeyCode = KeyCode::H;
+// the suggested placement, translucent over the board
+const SUGGESTION_COLOR: Color = Color::rgba(1., 1., 1., 0.35);
+// the holes and overhangs the brick would create where it is dropped now
+const WARNING_COLOR: Color = Color::rgba(0.9, 0.2, 0.2, 0.6);
+
+/// Whether the hints are shown, toggled during the game.
+#[derive(Resource, Default)]
+pub struct HintRes(pub bool);
+
+// what the drawn hints were computed from, to redraw them only when it changes
+#[derive(Resource, Default)]
+struct HintViewRes {
+    drawn: Option<(bool, Option<ActiveBrick>, usize)>,
+    // the suggestion for the brick numbered `pieces`
+    suggestion: Option<(usize, Option<Placement>)>,
+}
+
+#[derive(Component)]
+struct HintView;
+
+pub struct HintPlugin;
+
+impl Plugin for HintPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HintRes>()
+            .init_resource::<HintViewRes>()
+            .add_systems(OnEnter(GameState::Game), reset_hint_view)
+            .add_systems(OnExit(GameState::Game), despawn_with_component::<HintView>)
+            .add_systems(
+                Update,
+                (toggle_hint_system, hint_render_system)
+                    .chain()
+                    .in_set(GameSystemSet::Render)
+                    .run_if(in_state(GameState::Game)),
+            );
+    }
+}
+
+fn reset_hint_view(mut hint_view: ResMut<HintViewRes>) {
+    *hint_view = HintViewRes::default();
+}
+
+fn toggle_hint_system(keyboard_input: Res<Input<KeyCode>>, mut hint: ResMut<HintRes>) {
+    if keyboard_input.just_pressed(HINT_KEY) {
+        hint.0 = !hint.0;
+    }
+}
+
+fn hint_render_system(
+    mut commands: Commands,
+    hint: Res<HintRes>,
+    engine: Res<GameEngineRes>,
+    layout: Res<BoardLayoutRes>,
+    mut hint_view: ResMut<HintViewRes>,
+    query: Query<Entity, With<HintView>>,
+) {
+    let engine = &engine.0;
+    let state = (hint.0, engine.active, engine.pieces);
+    if hint_view.drawn == Some(state) {
+        return;
+    }
+    hint_view.drawn = Some(state);
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+    let Some(active) = engine.active.filter(|_| hint.0) else {
+        return;
+    };
+
+    // searched once per brick, from where it was when the hints were first shown
+    let suggestion = match hint_view.suggestion {
+        Some((pieces, suggestion)) if pieces == engine.pieces => suggestion,
+        _ => {
+            let suggestion = best_placement(
+                &engine.board,
+                active.brick,
+                active.pos,
+                Some(engine.next),
+                &Weights::default(),
+            );
+            hint_view.suggestion = Some((engine.pieces, suggestion));
+            suggestion
+        }
+    };
+    if let Some(suggestion) = suggestion {
+        let cells: Vec<Position> = suggestion
+            .brick
+            .cells(suggestion.pos, engine.board.block_size())
+            .collect();
+        spawn_cells(&mut commands, &cells, SUGGESTION_COLOR, &layout, HintView);
+    }
+
+    let board = &engine.board;
+    let drop_pos = board.get_bottom_valid_brick_pos(&active.brick, &active.pos);
+    let mut dropped = board.clone();
+    dropped.occupy_brick(active.brick, drop_pos);
+    let covered_before = covered_cells(board);
+    let created: Vec<Position> = covered_cells(&dropped)
+        .into_iter()
+        .filter(|pos| !covered_before.contains(pos))
+        .collect();
+    spawn_cells(&mut commands, &created, WARNING_COLOR, &layout, HintView);
+}
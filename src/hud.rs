@@ -0,0 +1,346 @@
+use std::io;
+
+use bevy::{prelude::*, sprite::Anchor};
+
+use crate::{
+    engine::LineClear,
+    events::{FinesseFault, LinesCleared},
+    game::{setup_game_data, BoardLayoutRes, GameEngineRes, GameSystemSet, GameViewRelayout},
+    locale::{clear_message_id, Locale, LocaleRes},
+    replay::invalid_data,
+    stats::GameStatsRes,
+    theme::ThemeRes,
+    utils::despawn_with_component,
+    GameState,
+};
+
+const HUD_FONT_SIZE: f32 = 28.;
+const HUD_LINE_HEIGHT: f32 = 40.;
+const ACTION_TEXT_FONT_SIZE: f32 = 30.;
+const ACTION_TEXT_SECONDS: f32 = 2.;
+// the action text starts fading out when this many seconds are left
+const ACTION_TEXT_FADE_SECONDS: f32 = 1.;
+
+/// Which items the HUD shows next to the board, set by the `hud` option.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct HudConfigRes {
+    pub show_time: bool,
+    pub show_pps: bool,
+    pub show_apm: bool,
+    pub show_combo: bool,
+    pub show_back_to_back: bool,
+    pub show_finesse: bool,
+    pub show_action_text: bool,
+}
+
+impl Default for HudConfigRes {
+    fn default() -> Self {
+        HudConfigRes {
+            show_time: true,
+            show_pps: true,
+            show_apm: true,
+            show_combo: true,
+            show_back_to_back: true,
+            show_finesse: true,
+            show_action_text: true,
+        }
+    }
+}
+
+impl HudConfigRes {
+    /// The items of a comma separated list, e.g. `time, pps, combo`, the others are hidden.
+    pub fn parse(list: &str) -> io::Result<Self> {
+        let mut config = HudConfigRes {
+            show_time: false,
+            show_pps: false,
+            show_apm: false,
+            show_combo: false,
+            show_back_to_back: false,
+            show_finesse: false,
+            show_action_text: false,
+        };
+        for name in list
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            let shown = match name.to_ascii_lowercase().as_str() {
+                "time" => &mut config.show_time,
+                "pps" => &mut config.show_pps,
+                "apm" => &mut config.show_apm,
+                "combo" => &mut config.show_combo,
+                "b2b" => &mut config.show_back_to_back,
+                "finesse" => &mut config.show_finesse,
+                "action" => &mut config.show_action_text,
+                _ => return Err(invalid_data(&format!("unknown HUD item {}", name))),
+            };
+            *shown = true;
+        }
+        Ok(config)
+    }
+}
+
+#[derive(Component)]
+struct HudBundle;
+
+#[derive(Component, Clone, Copy)]
+enum HudItem {
+    Time,
+    Pps,
+    Apm,
+    Combo,
+    BackToBack,
+    Finesse,
+    Garbage,
+    Targets,
+    Goal,
+    Pieces,
+    Resets,
+    EndSession,
+}
+
+#[derive(Component)]
+struct ActionText(Timer);
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HudConfigRes>()
+            .add_systems(OnEnter(GameState::Game), spawn_hud.after(setup_game_data))
+            .add_systems(OnExit(GameState::Game), despawn_with_component::<HudBundle>)
+            .add_systems(
+                Update,
+                (update_hud_system, action_text_system)
+                    .in_set(GameSystemSet::Render)
+                    .run_if(in_state(GameState::Game)),
+            )
+            .add_systems(
+                Update,
+                relayout_hud_system
+                    .after(GameSystemSet::Layout)
+                    .run_if(in_state(GameState::Game)),
+            );
+    }
+}
+
+/// Describes a line clear the way tetris games usually announce it.
+pub fn action_text(clear: &LineClear, locale: &Locale) -> String {
+    let mut lines = Vec::with_capacity(3);
+    let name = locale.text(clear_message_id(clear.count.clamp(1, 4)));
+    let mut action = if clear.is_tspin() && clear.count == 0 {
+        locale.text("action-tspin-zero").to_string()
+    } else if clear.is_tspin() {
+        locale.format("action-tspin", &[("clear", &name)])
+    } else {
+        name.to_string()
+    };
+    if clear.back_to_back {
+        action = locale.format("action-back-to-back", &[("clear", &action)]);
+    }
+    lines.push(action);
+    if clear.combo > 0 {
+        lines.push(locale.format("action-combo", &[("combo", &clear.combo)]));
+    }
+    if clear.perfect_clear {
+        lines.push(locale.text("action-perfect-clear").to_string());
+    }
+    lines.join("\n")
+}
+
+fn spawn_hud(
+    mut commands: Commands,
+    theme_res: Res<ThemeRes>,
+    config: Res<HudConfigRes>,
+    layout: Res<BoardLayoutRes>,
+    engine: Res<GameEngineRes>,
+) {
+    spawn_hud_view(&mut commands, &theme_res, &config, &layout, &engine);
+}
+
+fn relayout_hud_system(
+    mut commands: Commands,
+    mut relayout_events: EventReader<GameViewRelayout>,
+    theme_res: Res<ThemeRes>,
+    config: Res<HudConfigRes>,
+    layout: Res<BoardLayoutRes>,
+    engine: Res<GameEngineRes>,
+    query: Query<Entity, With<HudBundle>>,
+) {
+    if relayout_events.read().count() == 0 {
+        return;
+    }
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+    spawn_hud_view(&mut commands, &theme_res, &config, &layout, &engine);
+}
+
+fn spawn_hud_view(
+    commands: &mut Commands,
+    theme_res: &ThemeRes,
+    config: &HudConfigRes,
+    layout: &BoardLayoutRes,
+    engine: &GameEngineRes,
+) {
+    let text_style = TextStyle {
+        font: theme_res.font(),
+        font_size: HUD_FONT_SIZE,
+        color: theme_res.theme().ui.game_text,
+    };
+
+    let items = [
+        (config.show_time, HudItem::Time),
+        (config.show_pps, HudItem::Pps),
+        (config.show_apm, HudItem::Apm),
+        (config.show_combo, HudItem::Combo),
+        (config.show_back_to_back, HudItem::BackToBack),
+        (config.show_finesse, HudItem::Finesse),
+        // the garbage left to dig is the goal of a cheese race, it is always shown
+        (engine.0.garbage_remaining().is_some(), HudItem::Garbage),
+        (engine.0.is_finesse_trainer(), HudItem::Targets),
+        (engine.0.puzzle_goal().is_some(), HudItem::Goal),
+        (engine.0.pieces_left().is_some(), HudItem::Pieces),
+        // a zen session only ends on a key press, its hint stays below the resets
+        (engine.0.zen_resets().is_some(), HudItem::Resets),
+        (engine.0.zen_resets().is_some(), HudItem::EndSession),
+    ];
+    commands
+        .spawn((
+            SpriteBundle {
+                transform: Transform::from_translation(layout.hud_position().extend(0.)),
+                ..default()
+            },
+            HudBundle,
+        ))
+        .with_children(|parent| {
+            let shown_items = items.iter().filter(|(shown, _)| *shown);
+            for (i, (_, item)) in shown_items.enumerate() {
+                parent.spawn((
+                    Text2dBundle {
+                        text: Text::from_section("", text_style.clone()),
+                        transform: Transform::from_xyz(0., -(i as f32) * HUD_LINE_HEIGHT, 0.),
+                        text_anchor: Anchor::TopLeft,
+                        ..default()
+                    },
+                    *item,
+                ));
+            }
+        });
+
+    if config.show_action_text {
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    "",
+                    TextStyle {
+                        font_size: ACTION_TEXT_FONT_SIZE,
+                        ..text_style
+                    },
+                )
+                .with_alignment(TextAlignment::Center),
+                // above the board, where it is drawn in a portrait window
+                transform: Transform::from_translation(layout.action_text_position().extend(1.)),
+                text_anchor: Anchor::TopCenter,
+                ..default()
+            },
+            ActionText(Timer::from_seconds(ACTION_TEXT_SECONDS, TimerMode::Once)),
+            HudBundle,
+        ));
+    }
+}
+
+fn update_hud_system(
+    game_stats: Res<GameStatsRes>,
+    engine: Res<GameEngineRes>,
+    locale: Res<LocaleRes>,
+    mut item_query: Query<(&mut Text, &HudItem)>,
+) {
+    let stats = &game_stats.0;
+    for (mut text, item) in &mut item_query {
+        text.sections[0].value = match item {
+            HudItem::Time => {
+                let secs = stats.play_seconds() as u64;
+                let time = format!("{:02}:{:02}", secs / 60, secs % 60);
+                locale.format("hud-time", &[("time", &time)])
+            }
+            HudItem::Pps => {
+                let pps = format!("{:.2}", stats.pieces_per_second());
+                locale.format("hud-pps", &[("pps", &pps)])
+            }
+            HudItem::Apm => {
+                let apm = format!("{:.1}", stats.attack_per_minute());
+                locale.format("hud-apm", &[("apm", &apm)])
+            }
+            HudItem::Combo => match engine.0.combo() {
+                Some(combo) if combo > 0 => locale.format("hud-combo", &[("combo", &combo)]),
+                _ => locale.format("hud-combo", &[("combo", &"-")]),
+            },
+            HudItem::BackToBack => {
+                let state = if engine.0.back_to_back() {
+                    locale.text("hud-on")
+                } else {
+                    "-"
+                };
+                locale.format("hud-back-to-back", &[("state", &state)])
+            }
+            HudItem::Finesse => {
+                locale.format("hud-faults", &[("faults", &engine.0.finesse_faults())])
+            }
+            HudItem::Garbage => {
+                let garbage = engine.0.garbage_remaining().unwrap_or_default();
+                locale.format("hud-garbage", &[("garbage", &garbage)])
+            }
+            HudItem::Targets => {
+                let (hit, placed) = stats.targets.unwrap_or_default();
+                locale.format("hud-targets", &[("hit", &hit), ("placed", &placed)])
+            }
+            HudItem::Goal => engine
+                .0
+                .puzzle_goal()
+                .map(|goal| goal.describe(&locale))
+                .unwrap_or_default(),
+            HudItem::Pieces => {
+                let pieces = engine.0.pieces_left().unwrap_or_default();
+                locale.format("hud-pieces", &[("pieces", &pieces)])
+            }
+            HudItem::Resets => {
+                let resets = engine.0.zen_resets().unwrap_or_default();
+                locale.format("hud-resets", &[("resets", &resets)])
+            }
+            HudItem::EndSession => locale.text("hud-end-session").to_string(),
+        };
+    }
+}
+
+fn action_text_system(
+    time: Res<Time>,
+    mut cleared_events: EventReader<LinesCleared>,
+    mut fault_events: EventReader<FinesseFault>,
+    locale: Res<LocaleRes>,
+    mut text_query: Query<(&mut Text, &mut ActionText)>,
+) {
+    let Ok((mut text, mut fade)) = text_query.get_single_mut() else {
+        return;
+    };
+    if let Some(event) = cleared_events.read().last() {
+        text.sections[0].value = action_text(&event.0, &locale);
+        fade.0.reset();
+    }
+    // the finesse faults are flagged right when the brick locks
+    if let Some(event) = fault_events.read().last() {
+        text.sections[0].value = locale.format(
+            "hud-finesse-fault",
+            &[("keys", &event.presses), ("needed", &event.expected)],
+        );
+        fade.0.reset();
+    }
+    fade.0.tick(time.delta());
+
+    let remaining = (fade.0.duration() - fade.0.elapsed()).as_secs_f32();
+    let alpha = (remaining / ACTION_TEXT_FADE_SECONDS).min(1.);
+    text.sections[0].style.color.set_a(alpha);
+    if fade.0.just_finished() {
+        text.sections[0].value.clear();
+    }
+}
@@ -0,0 +1,91 @@
+use bevy::prelude::*;
+
+use crate::engine::{InputAction, InputFrame};
+
+/// The keys controlling one board.
+#[derive(Clone, Copy, Debug)]
+pub struct KeyMap {
+    pub move_left: &'static [KeyCode],
+    pub move_right: &'static [KeyCode],
+    pub rotate: &'static [KeyCode],
+    pub hard_drop: &'static [KeyCode],
+    pub soft_drop: &'static [KeyCode],
+}
+
+impl KeyMap {
+    pub const SINGLE_PLAYER: KeyMap = KeyMap {
+        move_left: &[KeyCode::Left],
+        move_right: &[KeyCode::Right],
+        rotate: &[KeyCode::Up],
+        hard_drop: &[KeyCode::Space],
+        soft_drop: &[KeyCode::J, KeyCode::Down],
+    };
+
+    // the left board in versus
+    pub const WASD: KeyMap = KeyMap {
+        move_left: &[KeyCode::A],
+        move_right: &[KeyCode::D],
+        rotate: &[KeyCode::W],
+        hard_drop: &[KeyCode::Space],
+        soft_drop: &[KeyCode::S],
+    };
+
+    // the right board in versus
+    pub const ARROWS: KeyMap = KeyMap {
+        move_left: &[KeyCode::Left],
+        move_right: &[KeyCode::Right],
+        rotate: &[KeyCode::Up],
+        hard_drop: &[KeyCode::Return],
+        soft_drop: &[KeyCode::Down],
+    };
+
+    /// The actions pressed on the keyboard during this frame.
+    pub fn read(&self, keyboard_input: &Input<KeyCode>) -> InputFrame {
+        let mut input = InputFrame::default();
+        if keyboard_input.any_just_pressed(self.move_left.iter().copied()) {
+            input.insert(InputAction::MoveLeft);
+        }
+        if keyboard_input.any_just_pressed(self.move_right.iter().copied()) {
+            input.insert(InputAction::MoveRight);
+        }
+        if keyboard_input.any_just_pressed(self.rotate.iter().copied()) {
+            input.insert(InputAction::Rotate);
+        }
+        if keyboard_input.any_just_pressed(self.hard_drop.iter().copied()) {
+            input.insert(InputAction::HardDrop);
+        }
+        if keyboard_input.any_just_pressed(self.soft_drop.iter().copied()) {
+            input.insert(InputAction::SoftDropPressed);
+        }
+        if keyboard_input.any_just_released(self.soft_drop.iter().copied()) {
+            input.insert(InputAction::SoftDropReleased);
+        }
+        input
+    }
+}
+
+/// The actions pressed on a gamepad during this frame,
+/// the d-pad moves and soft drops, A rotates and Y hard drops.
+pub fn read_gamepad(gamepad: Gamepad, button_input: &Input<GamepadButton>) -> InputFrame {
+    let button = |button_type| GamepadButton::new(gamepad, button_type);
+    let mut input = InputFrame::default();
+    if button_input.just_pressed(button(GamepadButtonType::DPadLeft)) {
+        input.insert(InputAction::MoveLeft);
+    }
+    if button_input.just_pressed(button(GamepadButtonType::DPadRight)) {
+        input.insert(InputAction::MoveRight);
+    }
+    if button_input.just_pressed(button(GamepadButtonType::South)) {
+        input.insert(InputAction::Rotate);
+    }
+    if button_input.just_pressed(button(GamepadButtonType::North)) {
+        input.insert(InputAction::HardDrop);
+    }
+    if button_input.just_pressed(button(GamepadButtonType::DPadDown)) {
+        input.insert(InputAction::SoftDropPressed);
+    }
+    if button_input.just_released(button(GamepadButtonType::DPadDown)) {
+        input.insert(InputAction::SoftDropReleased);
+    }
+    input
+}
@@ -0,0 +1,86 @@
+use bevy::{prelude::*, render::camera::ScalingMode, window::PrimaryWindow};
+
+use crate::constants::{WINDOW_HEIGHT, WINDOW_WIDTH};
+
+// the area laid out for the windows taller than wide, e.g. mobile browsers
+const PORTRAIT_WIDTH: f32 = 640.;
+const PORTRAIT_HEIGHT: f32 = 1040.;
+
+/// How the screens are arranged, following the aspect ratio of the window.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScreenLayoutRes {
+    #[default]
+    Landscape,
+    Portrait,
+}
+
+impl ScreenLayoutRes {
+    pub fn for_window(width: f32, height: f32) -> Self {
+        if height > width {
+            ScreenLayoutRes::Portrait
+        } else {
+            ScreenLayoutRes::Landscape
+        }
+    }
+
+    pub fn is_portrait(self) -> bool {
+        self == ScreenLayoutRes::Portrait
+    }
+
+    /// The area the screens are laid out in, centered on the origin. It is scaled to fit
+    /// the window, the rest of the window is left as margins.
+    pub fn size(self) -> Vec2 {
+        match self {
+            ScreenLayoutRes::Landscape => Vec2::new(WINDOW_WIDTH, WINDOW_HEIGHT),
+            ScreenLayoutRes::Portrait => Vec2::new(PORTRAIT_WIDTH, PORTRAIT_HEIGHT),
+        }
+    }
+}
+
+pub struct LayoutPlugin;
+
+impl Plugin for LayoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScreenLayoutRes>()
+            .add_systems(Update, fit_window_system);
+    }
+}
+
+// scales the world and the menus to the window, and switches the layout when it turns
+fn fit_window_system(
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut projection_query: Query<&mut OrthographicProjection>,
+    mut ui_scale: ResMut<UiScale>,
+    mut screen: ResMut<ScreenLayoutRes>,
+) {
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    let (width, height) = (window.width(), window.height());
+    // a minimized window keeps the last layout
+    if width <= 0. || height <= 0. {
+        return;
+    }
+    screen.set_if_neq(ScreenLayoutRes::for_window(width, height));
+
+    let size = screen.size();
+    for mut projection in &mut projection_query {
+        let fitted = matches!(
+            projection.scaling_mode,
+            ScalingMode::AutoMin { min_width, min_height }
+                if min_width == size.x && min_height == size.y
+        );
+        if !fitted {
+            projection.scaling_mode = ScalingMode::AutoMin {
+                min_width: size.x,
+                min_height: size.y,
+            };
+        }
+    }
+
+    // the menus are laid out for the landscape window, they shrink in a portrait one
+    let scale = (width / WINDOW_WIDTH).min(height / WINDOW_HEIGHT) as f64;
+    if ui_scale.0 != scale {
+        ui_scale.0 = scale;
+    }
+}
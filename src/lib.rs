@@ -0,0 +1,60 @@
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
+use bevy::prelude::States;
+
+pub mod ai;
+pub mod board;
+pub mod brick;
+pub mod cli;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod clipboard;
+pub mod common_entity;
+pub mod constants;
+pub mod data;
+pub mod engine;
+pub mod events;
+pub mod fade;
+pub mod finesse;
+pub mod fumen;
+pub mod game;
+pub mod gameover;
+pub mod garbage;
+pub mod hint;
+pub mod hud;
+pub mod input;
+pub mod layout;
+pub mod locale;
+pub mod menu;
+pub mod menu_help;
+pub mod mode;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod net;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod online;
+pub mod position;
+pub mod puzzle;
+pub mod puzzle_menu;
+pub mod randomizer;
+pub mod replay;
+pub mod save;
+pub mod settings;
+pub mod stats;
+pub mod theme;
+pub mod trainer;
+pub mod utils;
+pub mod versus;
+
+#[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
+pub enum GameState {
+    #[default]
+    Menu,
+    Game,
+    GameOver,
+    HelpMenu,
+    Replay,
+    Versus,
+    Puzzles,
+    Settings,
+    #[cfg(not(target_arch = "wasm32"))]
+    Online,
+}
@@ -0,0 +1,187 @@
+use std::{collections::HashMap, io, ops::Deref};
+
+use bevy::prelude::*;
+
+use crate::{replay::invalid_data, settings::SettingsRes};
+
+// built into the game, in the order of the language button; English comes first as the fallback
+const BUNDLED: [(&str, &str); 2] = [
+    ("en", include_str!("../assets/locales/en.ftl")),
+    ("zh", include_str!("../assets/locales/zh.ftl")),
+];
+
+/// The texts of the screens in one language, read from a Fluent file.
+///
+/// Only the simple messages of Fluent are read: `id = text` lines, continued by the indented
+/// lines below them, with `{ $name }` placeables. The terms `-language-name` and `-font` give
+/// the name of the language in itself and a font covering its glyphs.
+#[derive(Clone, Debug)]
+pub struct Locale {
+    pub code: String,
+    pub name: String,
+    pub font: Option<String>,
+    messages: HashMap<String, String>,
+}
+
+impl Locale {
+    pub fn parse(code: &str, text: &str) -> io::Result<Self> {
+        let mut messages: HashMap<String, String> = HashMap::new();
+        let mut last_id: Option<String> = None;
+        for line in text.lines() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with(char::is_whitespace) {
+                // a continued message, its lines are joined by line breaks
+                let id = last_id
+                    .as_ref()
+                    .ok_or_else(|| invalid_data("an indented line without a message"))?;
+                let value = messages.get_mut(id).unwrap();
+                if !value.is_empty() {
+                    value.push('\n');
+                }
+                value.push_str(line.trim());
+                continue;
+            }
+            let (id, value) = line
+                .split_once('=')
+                .ok_or_else(|| invalid_data("expected an `id = text` line"))?;
+            let id = id.trim().to_string();
+            messages.insert(id.clone(), value.trim().to_string());
+            last_id = Some(id);
+        }
+        Ok(Locale {
+            code: code.to_string(),
+            name: messages
+                .remove("-language-name")
+                .unwrap_or_else(|| code.to_string()),
+            font: messages.remove("-font"),
+            messages,
+        })
+    }
+
+    /// The English texts, built into the game.
+    pub fn english() -> Self {
+        Locale::parse(BUNDLED[0].0, BUNDLED[0].1).unwrap()
+    }
+
+    /// The bundled languages, any text missing in one of them is taken from English.
+    pub fn bundled() -> Vec<Locale> {
+        let english = Locale::english();
+        let mut locales = vec![english.clone()];
+        for (code, text) in &BUNDLED[1..] {
+            match Locale::parse(code, text) {
+                // its texts would be drawn as boxes without the glyphs of its font
+                Ok(locale) if locale.font.is_some() && locale.installed_font().is_none() => {}
+                Ok(mut locale) => {
+                    for (id, value) in &english.messages {
+                        locale
+                            .messages
+                            .entry(id.clone())
+                            .or_insert_with(|| value.clone());
+                    }
+                    locales.push(locale);
+                }
+                Err(err) => warn!("failed to read the {} texts: {}", code, err),
+            }
+        }
+        locales
+    }
+
+    /// The text of a message, its id when it is missing.
+    pub fn text<'a>(&'a self, id: &'a str) -> &'a str {
+        self.messages.get(id).map_or(id, String::as_str)
+    }
+
+    /// The text of a message with its `{ $name }` placeables replaced by the arguments.
+    pub fn format(&self, id: &str, args: &[(&str, &dyn ToString)]) -> String {
+        let mut text = self.text(id);
+        let mut formatted = String::with_capacity(text.len());
+        while let Some(start) = text.find('{') {
+            let Some(len) = text[start..].find('}') else {
+                break;
+            };
+            formatted.push_str(&text[..start]);
+            let placeable = &text[start..=start + len];
+            let name = placeable[1..placeable.len() - 1]
+                .trim()
+                .trim_start_matches('$');
+            match args.iter().find(|(arg, _)| *arg == name) {
+                Some((_, value)) => formatted.push_str(&value.to_string()),
+                None => formatted.push_str(placeable),
+            }
+            text = &text[start + len + 1..];
+        }
+        formatted.push_str(text);
+        formatted
+    }
+
+    /// The font of the language, when it is installed. A language without a font uses the one
+    /// of the theme, a language with a missing font is not offered.
+    pub fn installed_font(&self) -> Option<&str> {
+        let font = self.font.as_deref()?;
+        #[cfg(not(target_arch = "wasm32"))]
+        if !std::path::Path::new("assets").join(font).exists() {
+            warn!(
+                "the font {} of the {} texts is missing from assets/, the language is not offered",
+                font, self.code
+            );
+            return None;
+        }
+        Some(font)
+    }
+}
+
+/// The id of the name of a clear of 1 to 4 lines, e.g. "action-tetris".
+pub fn clear_message_id(count: usize) -> &'static str {
+    [
+        "action-single",
+        "action-double",
+        "action-triple",
+        "action-tetris",
+    ][count - 1]
+}
+
+/// The languages to choose from and the one in use, dereferences to the current one.
+#[derive(Resource)]
+pub struct LocaleRes {
+    pub locales: Vec<Locale>,
+    pub current: usize,
+}
+
+impl LocaleRes {
+    pub fn select(&mut self, index: usize) {
+        self.current = index % self.locales.len();
+    }
+}
+
+impl Deref for LocaleRes {
+    type Target = Locale;
+
+    fn deref(&self) -> &Locale {
+        &self.locales[self.current]
+    }
+}
+
+impl FromWorld for LocaleRes {
+    fn from_world(world: &mut World) -> Self {
+        let locales = Locale::bundled();
+        let saved = world.get_resource::<SettingsRes>().and_then(|settings| {
+            locales
+                .iter()
+                .position(|locale| locale.code == settings.language)
+        });
+        LocaleRes {
+            locales,
+            current: saved.unwrap_or(0),
+        }
+    }
+}
+
+pub struct LocalePlugin;
+
+impl Plugin for LocalePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LocaleRes>();
+    }
+}
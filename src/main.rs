@@ -1,54 +1,118 @@
 use bevy::{
-    prelude::{default, App, PluginGroup, Startup, States, Commands, Camera2dBundle, ClearColor},
-    window::{PresentMode, Window, WindowPlugin, WindowResolution},
-    DefaultPlugins, asset::AssetMetaCheck,
+    asset::AssetMetaCheck,
+    prelude::{
+        default, App, Camera2dBundle, ClearColor, Commands, NextState, PluginGroup, Startup,
+    },
+    window::{PresentMode, Window, WindowMode, WindowPlugin, WindowResolution},
+    DefaultPlugins,
 };
 
-use constants::{WINDOW_HEIGHT, WINDOW_WIDTH, BACKGROUND_COLOR};
-
-mod board;
-mod brick;
-mod constants;
-mod menu;
-mod position;
-mod utils;
-mod game;
-mod gameover;
-mod common_entity;
-mod data;
-mod menu_help;
-
-#[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
-enum GameState {
-    #[default]
-    Menu,
-    Game,
-    GameOver,
-    HelpMenu
-}
+use tetris::{
+    cli::{CliArgs, USAGE},
+    constants::{BACKGROUND_COLOR, WINDOW_HEIGHT, WINDOW_WIDTH},
+    events, game, gameover, hint, hud, layout, locale,
+    menu::{self, GameLevelRes, GameModeRes, GameOptionsRes},
+    menu_help, puzzle_menu,
+    replay::{self, LastReplayRes, Replay},
+    save, settings, stats, theme, trainer, versus, GameState,
+};
 
 fn main() {
-    App::new()
-        .insert_resource(ClearColor(BACKGROUND_COLOR))
+    let cli_args = CliArgs::parse(std::env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("{}\n\n{}", err, USAGE);
+        std::process::exit(2);
+    });
+    if cli_args.help {
+        println!("{}", USAGE);
+        return;
+    }
+    let (window_width, window_height) = cli_args
+        .window_size
+        .unwrap_or((WINDOW_WIDTH, WINDOW_HEIGHT));
+    let window_mode = if cli_args.fullscreen {
+        WindowMode::BorderlessFullscreen
+    } else {
+        WindowMode::Windowed
+    };
+
+    let mut app = App::new();
+    app.insert_resource(ClearColor(BACKGROUND_COLOR))
         .insert_resource(AssetMetaCheck::Never)
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
                 title: "TETRIS".to_string(),
-                resolution: WindowResolution::new(WINDOW_WIDTH, WINDOW_HEIGHT),
+                resolution: WindowResolution::new(window_width, window_height),
+                mode: window_mode,
                 present_mode: PresentMode::AutoVsync,
-                resizable: false,
+                resizable: true,
+                // the canvas follows the size of the page in a browser
+                fit_canvas_to_parent: true,
                 ..default()
             }),
             ..default()
         }))
         // .add_plugins(WorldInspectorPlugin::new())
         .add_systems(Startup, camera_setup)
+        .add_plugins(layout::LayoutPlugin)
         .add_state::<GameState>()
+        // the settings choose the language and the theme, which the other screens are drawn with
+        .add_plugins(settings::SettingsPlugin)
+        .add_plugins(locale::LocalePlugin)
+        .add_plugins(theme::ThemePlugin)
         .add_plugins(menu::MenuPlugin)
         .add_plugins(game::GamePlugin)
         .add_plugins(gameover::GameOverPlugin)
         .add_plugins(menu_help::MenuHelpPlugin)
-        .run();
+        .add_plugins(replay::ReplayPlugin)
+        .add_plugins(hud::HudPlugin)
+        .add_plugins(stats::StatsPlugin)
+        .add_plugins(events::GameEventsPlugin)
+        .add_plugins(versus::VersusPlugin)
+        .add_plugins(hint::HintPlugin)
+        .add_plugins(trainer::TrainerPlugin)
+        .add_plugins(puzzle_menu::PuzzleMenuPlugin)
+        .add_plugins(save::SavePlugin);
+    #[cfg(not(target_arch = "wasm32"))]
+    app.add_plugins(tetris::online::OnlinePlugin)
+        .add_plugins(tetris::clipboard::ClipboardPlugin);
+
+    // the game options apply to every game started from the menu too
+    app.insert_resource(GameOptionsRes {
+        options: cli_args.game_options(),
+        seed: cli_args.seed,
+    });
+    if let Some(mode) = cli_args.mode {
+        app.insert_resource(GameModeRes(mode));
+    }
+    if let Some(difficulty) = cli_args.difficulty {
+        app.insert_resource(GameLevelRes(difficulty));
+    }
+    if let Some(hud) = cli_args.hud {
+        app.insert_resource(hud);
+    }
+    // the rules of the versus matches
+    let mut versus_config = versus::VersusConfigRes::default();
+    if let Some(attack_table) = &cli_args.attack_table {
+        versus_config.attack_table = attack_table.clone();
+    }
+    if let Some(messiness) = cli_args.garbage_messiness {
+        versus_config.hole_change_rate = messiness;
+    }
+    app.insert_resource(versus_config);
+
+    // a replay file passed on the command line is played back directly
+    if let Some(path) = &cli_args.replay {
+        match Replay::load(path) {
+            Ok(replay) => {
+                app.insert_resource(LastReplayRes(Some(replay)))
+                    .insert_resource(NextState(Some(GameState::Replay)));
+            }
+            Err(err) => eprintln!("failed to load replay {}: {}", path, err),
+        }
+    } else if cli_args.starts_game() {
+        app.insert_resource(NextState(Some(GameState::Game)));
+    }
+    app.run();
 }
 
 fn camera_setup(mut commands: Commands) {
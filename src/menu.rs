@@ -1,11 +1,14 @@
-
-
 use bevy::{app::AppExit, prelude::*};
 
 use crate::{
     common_entity::EntitySpawner,
-    constants::{BACKGROUND, TEXT_COLOR},
+    locale::{Locale, LocaleRes},
+    mode::{GameMode, GameOptions},
+    puzzle_menu::PuzzleRes,
+    save::SavedGameRes,
+    theme::ThemeRes,
     utils::{common_button_system, despawn_with_component},
+    versus::VersusConfigRes,
     GameState,
 };
 
@@ -21,15 +24,36 @@ pub enum GameSelectedLevel {
 #[derive(Resource)]
 pub struct GameLevelRes(pub GameSelectedLevel);
 
+#[derive(Resource)]
+pub struct GameModeRes(pub GameMode);
+
+/// The single player game is played by the AI.
+#[derive(Resource, Default)]
+pub struct WatchAiRes(pub bool);
+
+/// The options and the seed of the single player games, given on the command line.
+#[derive(Resource, Default)]
+pub struct GameOptionsRes {
+    pub options: GameOptions,
+    pub seed: Option<u64>,
+}
+
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(GameLevelRes(GameSelectedLevel::Easy))
+            .insert_resource(GameModeRes(GameMode::Marathon))
+            .init_resource::<WatchAiRes>()
+            .init_resource::<GameOptionsRes>()
             .add_systems(OnEnter(GameState::Menu), main_menu_setup)
             .add_systems(
                 OnExit(GameState::Menu),
                 despawn_with_component::<OnMainMenuScreen>,
             )
-            .add_systems(Update, (menu_action, common_button_system));
+            .add_systems(Update, (menu_action, common_button_system))
+            .add_systems(
+                Update,
+                update_mode_button_system.run_if(in_state(GameState::Menu)),
+            );
     }
 }
 
@@ -40,15 +64,36 @@ struct OnMainMenuScreen;
 // All actions that can be triggered from a button click
 #[derive(Component)]
 enum MenuButtonAction {
+    Continue,
+    ChangeMode,
+    Puzzles,
     EasyPlay,
     NormalPlay,
     HardPlay,
+    Versus,
+    VersusCpu,
+    #[cfg(not(target_arch = "wasm32"))]
+    Online,
+    WatchAi,
+    Settings,
     Help,
     Quit,
 }
 
-fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+fn mode_button_title(mode: GameMode, locale: &Locale) -> String {
+    locale.format("menu-mode", &[("mode", &locale.text(mode.message_id()))])
+}
+
+fn main_menu_setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    game_mode: Res<GameModeRes>,
+    saved_game: Res<SavedGameRes>,
+    theme_res: Res<ThemeRes>,
+    locale: Res<LocaleRes>,
+) {
+    let font = theme_res.font();
+    let ui = theme_res.theme().ui;
 
     commands
         .spawn((
@@ -70,69 +115,153 @@ fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                     style: Style {
                         flex_direction: FlexDirection::Column,
                         align_items: AlignItems::Center,
-                        padding: UiRect::px(120., 120., 10., 30.),
+                        padding: UiRect::px(40., 40., 10., 30.),
                         ..default()
                     },
-                    background_color: BACKGROUND.into(),
+                    background_color: ui.panel.into(),
                     ..default()
                 })
                 .with_children(|parent| {
                     // Display the game name
                     parent.spawn(
                         TextBundle::from_section(
-                            "TETRIS",
+                            locale.text("menu-title"),
                             TextStyle {
                                 font: font.clone(),
                                 font_size: 80.0,
-                                color: TEXT_COLOR,
+                                color: ui.text,
                             },
                         )
                         .with_style(Style {
-                            margin: UiRect::all(Val::Px(50.0)),
+                            margin: UiRect::all(Val::Px(20.0)),
                             ..default()
                         }),
                     );
 
-                    // Display three buttons for each action available from the main menu:
-                    // - Easy Mode
-                    // - Normal Mode
-                    // - Hard Mode
-                    // - Help
-                    // - quit
-                    parent.spawn_button(
-                        MenuButtonAction::EasyPlay,
-                        "right.png",
-                        "Easy",
-                        &asset_server,
-                    );
-                    parent.spawn_button(
-                        MenuButtonAction::NormalPlay,
-                        "right.png",
-                        "Normal",
-                        &asset_server,
-                    );
-                    parent.spawn_button(
-                        MenuButtonAction::HardPlay,
-                        "right.png",
-                        "Hard",
-                        &asset_server,
-                    );
-                    parent.spawn_button(
-                        MenuButtonAction::Help,
-                        "wrench.png",
-                        "How To Play",
-                        &asset_server,
-                    );
-                    parent.spawn_button(
-                        MenuButtonAction::Quit,
-                        "exitRight.png",
-                        "Quit",
-                        &asset_server,
-                    );
+                    // Display the buttons for each action available from the main menu:
+                    // - Continue, when a game was saved, Game Mode, which is cycled when clicked, and Puzzles
+                    // - Easy, Normal and Hard Mode
+                    // - Versus, two players on one keyboard, against the AI, and Online, through a relay server
+                    // - Watch AI, the AI plays a single player game, Settings and Help
+                    // - Quit
+                    parent.spawn(button_row()).with_children(|parent| {
+                        if saved_game.replay.is_some() {
+                            parent.spawn_button(
+                                MenuButtonAction::Continue,
+                                "right.png",
+                                locale.text("menu-continue"),
+                                &asset_server,
+                                &theme_res,
+                            );
+                        }
+                        parent.spawn_button(
+                            MenuButtonAction::ChangeMode,
+                            "wrench.png",
+                            &mode_button_title(game_mode.0, &locale),
+                            &asset_server,
+                            &theme_res,
+                        );
+                        parent.spawn_button(
+                            MenuButtonAction::Puzzles,
+                            "right.png",
+                            locale.text("menu-puzzles"),
+                            &asset_server,
+                            &theme_res,
+                        );
+                    });
+                    parent.spawn(button_row()).with_children(|parent| {
+                        parent.spawn_button(
+                            MenuButtonAction::EasyPlay,
+                            "right.png",
+                            locale.text("menu-easy"),
+                            &asset_server,
+                            &theme_res,
+                        );
+                        parent.spawn_button(
+                            MenuButtonAction::NormalPlay,
+                            "right.png",
+                            locale.text("menu-normal"),
+                            &asset_server,
+                            &theme_res,
+                        );
+                        parent.spawn_button(
+                            MenuButtonAction::HardPlay,
+                            "right.png",
+                            locale.text("menu-hard"),
+                            &asset_server,
+                            &theme_res,
+                        );
+                    });
+                    parent.spawn(button_row()).with_children(|parent| {
+                        parent.spawn_button(
+                            MenuButtonAction::Versus,
+                            "right.png",
+                            locale.text("menu-versus"),
+                            &asset_server,
+                            &theme_res,
+                        );
+                        parent.spawn_button(
+                            MenuButtonAction::VersusCpu,
+                            "right.png",
+                            locale.text("menu-vs-cpu"),
+                            &asset_server,
+                            &theme_res,
+                        );
+                        #[cfg(not(target_arch = "wasm32"))]
+                        parent.spawn_button(
+                            MenuButtonAction::Online,
+                            "right.png",
+                            locale.text("menu-online"),
+                            &asset_server,
+                            &theme_res,
+                        );
+                    });
+                    parent.spawn(button_row()).with_children(|parent| {
+                        parent.spawn_button(
+                            MenuButtonAction::WatchAi,
+                            "right.png",
+                            locale.text("menu-watch-ai"),
+                            &asset_server,
+                            &theme_res,
+                        );
+                        parent.spawn_button(
+                            MenuButtonAction::Settings,
+                            "wrench.png",
+                            locale.text("menu-settings"),
+                            &asset_server,
+                            &theme_res,
+                        );
+                        parent.spawn_button(
+                            MenuButtonAction::Help,
+                            "wrench.png",
+                            locale.text("menu-how-to-play"),
+                            &asset_server,
+                            &theme_res,
+                        );
+                    });
+                    parent.spawn(button_row()).with_children(|parent| {
+                        parent.spawn_button(
+                            MenuButtonAction::Quit,
+                            "exitRight.png",
+                            locale.text("menu-quit"),
+                            &asset_server,
+                            &theme_res,
+                        );
+                    });
                 });
         });
 }
 
+fn button_row() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::Row,
+            ..default()
+        },
+        ..default()
+    }
+}
+
 #[allow(unused_mut)]
 #[allow(unused_variables)]
 fn menu_action(
@@ -143,28 +272,76 @@ fn menu_action(
     mut app_exit_events: EventWriter<AppExit>,
     mut game_state: ResMut<NextState<GameState>>,
     mut game_level: ResMut<GameLevelRes>,
+    mut game_mode: ResMut<GameModeRes>,
+    mut watch_ai: ResMut<WatchAiRes>,
+    mut versus_config: ResMut<VersusConfigRes>,
+    mut puzzle_res: ResMut<PuzzleRes>,
+    mut saved_game: ResMut<SavedGameRes>,
 ) {
     for (interaction, menu_button_action) in &interaction_query {
         if *interaction == Interaction::Pressed {
             match menu_button_action {
-                MenuButtonAction::Quit =>
-                {
+                MenuButtonAction::Quit => {
                     #[cfg(not(target_arch = "wasm32"))]
                     {
                         app_exit_events.send(AppExit);
                     }
                 }
+                MenuButtonAction::Continue => {
+                    let Some(replay) = &saved_game.replay else {
+                        continue;
+                    };
+                    game_state.set(GameState::Game);
+                    game_level.0 = replay.difficulty;
+                    game_mode.0 = replay.mode;
+                    watch_ai.0 = false;
+                    puzzle_res.current = None;
+                    saved_game.resume = true;
+                }
+                MenuButtonAction::ChangeMode => {
+                    game_mode.0 = game_mode.0.next();
+                }
+                MenuButtonAction::Puzzles => {
+                    game_state.set(GameState::Puzzles);
+                }
                 MenuButtonAction::EasyPlay => {
                     game_state.set(GameState::Game);
                     game_level.0 = GameSelectedLevel::Easy;
+                    watch_ai.0 = false;
+                    puzzle_res.current = None;
                 }
                 MenuButtonAction::NormalPlay => {
                     game_state.set(GameState::Game);
                     game_level.0 = GameSelectedLevel::Normal;
+                    watch_ai.0 = false;
+                    puzzle_res.current = None;
                 }
                 MenuButtonAction::HardPlay => {
                     game_state.set(GameState::Game);
                     game_level.0 = GameSelectedLevel::Hard;
+                    watch_ai.0 = false;
+                    puzzle_res.current = None;
+                }
+                MenuButtonAction::Versus => {
+                    game_state.set(GameState::Versus);
+                    versus_config.cpu_opponent = false;
+                }
+                MenuButtonAction::VersusCpu => {
+                    game_state.set(GameState::Versus);
+                    versus_config.cpu_opponent = true;
+                }
+                MenuButtonAction::WatchAi => {
+                    game_state.set(GameState::Game);
+                    game_level.0 = GameSelectedLevel::Normal;
+                    watch_ai.0 = true;
+                    puzzle_res.current = None;
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                MenuButtonAction::Online => {
+                    game_state.set(GameState::Online);
+                }
+                MenuButtonAction::Settings => {
+                    game_state.set(GameState::Settings);
                 }
                 MenuButtonAction::Help => {
                     game_state.set(GameState::HelpMenu);
@@ -173,3 +350,24 @@ fn menu_action(
         }
     }
 }
+
+fn update_mode_button_system(
+    game_mode: Res<GameModeRes>,
+    locale: Res<LocaleRes>,
+    button_query: Query<(&MenuButtonAction, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    if !game_mode.is_changed() {
+        return;
+    }
+    for (action, children) in &button_query {
+        if !matches!(action, MenuButtonAction::ChangeMode) {
+            continue;
+        }
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].value = mode_button_title(game_mode.0, &locale);
+            }
+        }
+    }
+}
@@ -1,7 +1,12 @@
-use bevy::{prelude::*};
-
-use crate::{utils::{despawn_with_component, common_button_system}, constants::{BACKGROUND, TEXT_COLOR}, common_entity::EntitySpawner, GameState};
+use bevy::prelude::*;
 
+use crate::{
+    common_entity::EntitySpawner,
+    locale::LocaleRes,
+    theme::ThemeRes,
+    utils::{common_button_system, despawn_with_component},
+    GameState,
+};
 
 #[derive(Component)]
 struct MenuHelpScreen;
@@ -15,8 +20,7 @@ pub struct MenuHelpPlugin;
 
 impl Plugin for MenuHelpPlugin {
     fn build(&self, app: &mut App) {
-        app
-            .add_systems(OnEnter(GameState::HelpMenu), help_menu_setup)
+        app.add_systems(OnEnter(GameState::HelpMenu), help_menu_setup)
             .add_systems(
                 OnExit(GameState::HelpMenu),
                 despawn_with_component::<MenuHelpScreen>,
@@ -25,8 +29,14 @@ impl Plugin for MenuHelpPlugin {
     }
 }
 
-fn help_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+fn help_menu_setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    theme_res: Res<ThemeRes>,
+    locale: Res<LocaleRes>,
+) {
+    let font = theme_res.font();
+    let ui = theme_res.theme().ui;
 
     commands
         .spawn((
@@ -51,18 +61,18 @@ fn help_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                         padding: UiRect::px(20., 20., 10., 10.),
                         ..default()
                     },
-                    background_color: BACKGROUND.into(),
+                    background_color: ui.panel.into(),
                     ..default()
                 })
                 .with_children(|parent| {
                     // Display the game name
                     parent.spawn(
                         TextBundle::from_section(
-                            "CONTROLS",
+                            locale.text("help-title"),
                             TextStyle {
                                 font: font.clone(),
                                 font_size: 60.0,
-                                color: TEXT_COLOR,
+                                color: ui.text,
                             },
                         )
                         .with_style(Style {
@@ -71,20 +81,13 @@ fn help_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                         }),
                     );
 
-                    let game_score = format!("
-                    Left : move left    \n
-                    Right : move right                 \n
-                    Up : rotate \n
-                    Down : soft drop     \n
-                    Space : hard drop      \n
-                    Esc : pause game       \n");
                     parent.spawn(
                         TextBundle::from_section(
-                            game_score,
+                            locale.text("help-controls"),
                             TextStyle {
                                 font: font.clone(),
-                                font_size: 40.0,
-                                color: TEXT_COLOR,
+                                font_size: 28.0,
+                                color: ui.text,
                             },
                         )
                         .with_style(Style {
@@ -93,7 +96,13 @@ fn help_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                         }),
                     );
 
-                    parent.spawn_button(GameOverMenuHelpButtonAction::Back, "right.png", "Back", &asset_server);
+                    parent.spawn_button(
+                        GameOverMenuHelpButtonAction::Back,
+                        "right.png",
+                        locale.text("menu-back"),
+                        &asset_server,
+                        &theme_res,
+                    );
                 });
         });
 }
@@ -114,4 +123,4 @@ fn menu_action(
             }
         }
     }
-}
\ No newline at end of file
+}
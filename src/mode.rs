@@ -0,0 +1,168 @@
+use crate::{
+    constants::{DEFAULT_BOARD_HEIGHT, DEFAULT_BOARD_WIDTH},
+    engine::TICKS_PER_SECOND,
+};
+
+// the locked blocks of the fading mode stay visible this long before they fade out
+const FADING_STACK_TICKS: u64 = 5 * TICKS_PER_SECOND as u64;
+
+/// The game modes which can be selected in the main menu.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GameMode {
+    Marathon,
+    // a 4 wide well to practice combos
+    FourWide,
+    Wide,
+    Huge,
+    // dig through this many lines of garbage as fast as possible
+    CheeseRace10,
+    CheeseRace18,
+    CheeseRace100,
+    // place the bricks on targets with the fewest key presses, on an empty board
+    Finesse,
+    // the locked blocks disappear at once, or fade out after a few seconds
+    Invisible,
+    Fading,
+    // every block of a brick covers 2x2 cells
+    Big,
+    // no game over and a constant gravity, a top out empties the upper half of the board
+    Zen,
+}
+
+impl GameMode {
+    /// All the modes, in the order they are cycled in the menu.
+    pub const ALL: [GameMode; 12] = [
+        GameMode::Marathon,
+        GameMode::FourWide,
+        GameMode::Wide,
+        GameMode::Huge,
+        GameMode::CheeseRace10,
+        GameMode::CheeseRace18,
+        GameMode::CheeseRace100,
+        GameMode::Finesse,
+        GameMode::Invisible,
+        GameMode::Fading,
+        GameMode::Big,
+        GameMode::Zen,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Marathon => "Marathon",
+            GameMode::FourWide => "4-Wide",
+            GameMode::Wide => "12x24",
+            GameMode::Huge => "20x40",
+            GameMode::CheeseRace10 => "Cheese 10L",
+            GameMode::CheeseRace18 => "Cheese 18L",
+            GameMode::CheeseRace100 => "Cheese 100L",
+            GameMode::Finesse => "Finesse",
+            GameMode::Invisible => "Invisible",
+            GameMode::Fading => "Fading",
+            GameMode::Big => "Big",
+            GameMode::Zen => "Zen",
+        }
+    }
+
+    /// The id of its name in the texts of the screens.
+    pub fn message_id(&self) -> &'static str {
+        match self {
+            GameMode::Marathon => "mode-marathon",
+            GameMode::FourWide => "mode-four-wide",
+            GameMode::Wide => "mode-wide",
+            GameMode::Huge => "mode-huge",
+            GameMode::CheeseRace10 => "mode-cheese-10",
+            GameMode::CheeseRace18 => "mode-cheese-18",
+            GameMode::CheeseRace100 => "mode-cheese-100",
+            GameMode::Finesse => "mode-finesse",
+            GameMode::Invisible => "mode-invisible",
+            GameMode::Fading => "mode-fading",
+            GameMode::Big => "mode-big",
+            GameMode::Zen => "mode-zen",
+        }
+    }
+
+    /// The visible size of the board, as (width, height).
+    pub fn board_size(&self) -> (usize, usize) {
+        match self {
+            GameMode::Marathon
+            | GameMode::CheeseRace10
+            | GameMode::CheeseRace18
+            | GameMode::CheeseRace100
+            | GameMode::Finesse
+            | GameMode::Invisible
+            | GameMode::Fading
+            | GameMode::Big
+            | GameMode::Zen => (DEFAULT_BOARD_WIDTH, DEFAULT_BOARD_HEIGHT),
+            GameMode::FourWide => (4, DEFAULT_BOARD_HEIGHT),
+            GameMode::Wide => (12, 24),
+            GameMode::Huge => (20, 40),
+        }
+    }
+
+    /// The number of garbage lines to clear in a cheese race.
+    pub fn garbage_lines(&self) -> Option<usize> {
+        match self {
+            GameMode::CheeseRace10 => Some(10),
+            GameMode::CheeseRace18 => Some(18),
+            GameMode::CheeseRace100 => Some(100),
+            _ => None,
+        }
+    }
+
+    /// How many ticks the locked blocks stay visible, None in the modes which always show them.
+    pub fn stack_visible_ticks(&self) -> Option<u64> {
+        match self {
+            GameMode::Invisible => Some(0),
+            GameMode::Fading => Some(FADING_STACK_TICKS),
+            _ => None,
+        }
+    }
+
+    /// The side of the square of cells covered by a block, see `Board::with_block_size`.
+    pub fn block_size(&self) -> usize {
+        match self {
+            GameMode::Big => 2,
+            _ => 1,
+        }
+    }
+
+    pub fn next(&self) -> GameMode {
+        let index = GameMode::ALL
+            .iter()
+            .position(|mode| mode == self)
+            .unwrap_or(0);
+        GameMode::ALL[(index + 1) % GameMode::ALL.len()]
+    }
+}
+
+/// Settings of a game on top of its mode, e.g. given on the command line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GameOptions {
+    pub start_level: usize,
+    // the visible size of the board, instead of the one of the mode
+    pub board_size: Option<(usize, usize)>,
+}
+
+impl Default for GameOptions {
+    fn default() -> Self {
+        GameOptions {
+            start_level: 1,
+            board_size: None,
+        }
+    }
+}
+
+impl GameOptions {
+    /// The visible size of the board of a game of this mode. The size given in the options is
+    /// rounded down to whole blocks, e.g. 11x24 is played as 10x24 in the big mode.
+    pub fn board_size(&self, mode: GameMode) -> (usize, usize) {
+        let Some((width, height)) = self.board_size else {
+            return mode.board_size();
+        };
+        let block_size = mode.block_size();
+        (
+            width / block_size * block_size,
+            height / block_size * block_size,
+        )
+    }
+}
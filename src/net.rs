@@ -0,0 +1,344 @@
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    net::{Shutdown, TcpStream},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use crate::{
+    board::Board,
+    brick::{Brick, BrickType},
+    engine::{ActiveBrick, GameEngine},
+    menu::GameSelectedLevel,
+    position::Position,
+    replay::{difficulty_from_u8, difficulty_to_u8, invalid_data, write_varint, ByteReader},
+};
+
+pub const DEFAULT_SERVER_ADDR: &str = "127.0.0.1:7878";
+const PROTOCOL_VERSION: u8 = 1;
+// no message comes close to this, a bigger frame means a broken stream
+const MAX_FRAME_LEN: usize = 64 * 1024;
+// the order in which the cells are sent
+const CELL_TYPES: [BrickType; 9] = [
+    BrickType::O,
+    BrickType::I,
+    BrickType::J,
+    BrickType::L,
+    BrickType::S,
+    BrickType::Z,
+    BrickType::T,
+    BrickType::Garbage,
+    BrickType::None,
+];
+
+/// What a player shows of its game to the opponent.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BoardSnapshot {
+    pub board: Board,
+    pub active: Option<ActiveBrick>,
+    pub next: Brick,
+    pub garbage_queued: usize,
+    pub lines_sent: usize,
+}
+
+impl BoardSnapshot {
+    pub fn new(engine: &GameEngine, garbage_queued: usize, lines_sent: usize) -> Self {
+        BoardSnapshot {
+            board: engine.board.clone(),
+            active: engine.active,
+            next: engine.next,
+            garbage_queued,
+            lines_sent,
+        }
+    }
+}
+
+/// The messages exchanged by two clients through the relay server.
+///
+/// Every message is a frame made of its length as u32 (little endian) and its bytes,
+/// the first byte tells the kind of the message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NetMessage {
+    // sent once connected, the match starts when both clients received the hello of the other one
+    Hello {
+        version: u8,
+        difficulty: GameSelectedLevel,
+        seed: u64,
+        // decides whose difficulty is used
+        nonce: u64,
+    },
+    Board(BoardSnapshot),
+    // garbage lines sent to the opponent
+    Attack(usize),
+    // the sender topped out
+    GameOver,
+}
+
+impl NetMessage {
+    pub fn hello(difficulty: GameSelectedLevel, seed: u64, nonce: u64) -> Self {
+        NetMessage::Hello {
+            version: PROTOCOL_VERSION,
+            difficulty,
+            seed,
+            nonce,
+        }
+    }
+
+    pub fn is_supported_version(&self) -> bool {
+        !matches!(self, NetMessage::Hello { version, .. } if *version != PROTOCOL_VERSION)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        match self {
+            NetMessage::Hello {
+                version,
+                difficulty,
+                seed,
+                nonce,
+            } => {
+                bytes.push(0);
+                bytes.push(*version);
+                bytes.push(difficulty_to_u8(*difficulty));
+                bytes.extend_from_slice(&seed.to_le_bytes());
+                bytes.extend_from_slice(&nonce.to_le_bytes());
+            }
+            NetMessage::Board(snapshot) => {
+                bytes.push(1);
+                encode_snapshot(&mut bytes, snapshot);
+            }
+            NetMessage::Attack(lines) => {
+                bytes.push(2);
+                write_varint(&mut bytes, *lines as u64);
+            }
+            NetMessage::GameOver => bytes.push(3),
+        }
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> io::Result<Self> {
+        let mut reader = ByteReader::new(bytes);
+        match reader.take(1)?[0] {
+            0 => Ok(NetMessage::Hello {
+                version: reader.take(1)?[0],
+                difficulty: difficulty_from_u8(reader.take(1)?[0])?,
+                seed: u64::from_le_bytes(reader.take(8)?.try_into().unwrap()),
+                nonce: u64::from_le_bytes(reader.take(8)?.try_into().unwrap()),
+            }),
+            1 => Ok(NetMessage::Board(decode_snapshot(&mut reader)?)),
+            2 => Ok(NetMessage::Attack(reader.varint()? as usize)),
+            3 => Ok(NetMessage::GameOver),
+            _ => Err(invalid_data("unknown message")),
+        }
+    }
+}
+
+// only the visible rows are sent
+fn encode_snapshot(bytes: &mut Vec<u8>, snapshot: &BoardSnapshot) {
+    let board = &snapshot.board;
+    bytes.push(board.width() as u8);
+    bytes.push(board.visible_height() as u8);
+    for x in 0..board.width() {
+        for y in 0..board.visible_height() {
+            bytes.push(cell_to_u8(board.get(x, y)));
+        }
+    }
+    match snapshot.active {
+        Some(active) => {
+            bytes.push(1);
+            encode_brick(bytes, &active.brick);
+            encode_position(bytes, &active.pos);
+        }
+        None => bytes.push(0),
+    }
+    encode_brick(bytes, &snapshot.next);
+    write_varint(bytes, snapshot.garbage_queued as u64);
+    write_varint(bytes, snapshot.lines_sent as u64);
+}
+
+fn decode_snapshot(reader: &mut ByteReader) -> io::Result<BoardSnapshot> {
+    let width = reader.take(1)?[0] as usize;
+    let visible_height = reader.take(1)?[0] as usize;
+    if width == 0 || visible_height == 0 {
+        return Err(invalid_data("empty board"));
+    }
+    let mut board = Board::new(width, visible_height);
+    for x in 0..width {
+        for y in 0..visible_height {
+            board.set(x, y, cell_from_u8(reader.take(1)?[0])?);
+        }
+    }
+    let active = match reader.take(1)?[0] {
+        0 => None,
+        _ => Some(ActiveBrick {
+            brick: decode_brick(reader)?,
+            pos: decode_position(reader)?,
+            movable: true,
+            rotated_last: false,
+        }),
+    };
+    Ok(BoardSnapshot {
+        board,
+        active,
+        next: decode_brick(reader)?,
+        garbage_queued: reader.varint()? as usize,
+        lines_sent: reader.varint()? as usize,
+    })
+}
+
+fn encode_brick(bytes: &mut Vec<u8>, brick: &Brick) {
+    bytes.push(cell_to_u8(brick.0));
+    for pos in brick.1 {
+        bytes.push(pos.x as i8 as u8);
+        bytes.push(pos.y as i8 as u8);
+    }
+}
+
+fn decode_brick(reader: &mut ByteReader) -> io::Result<Brick> {
+    let brick_type = cell_from_u8(reader.take(1)?[0])?;
+    let mut positions = [Position::default(); 4];
+    for pos in positions.iter_mut() {
+        let bytes = reader.take(2)?;
+        *pos = Position::new(bytes[0] as i8 as i32, bytes[1] as i8 as i32);
+    }
+    Ok(Brick(brick_type, positions))
+}
+
+fn encode_position(bytes: &mut Vec<u8>, pos: &Position) {
+    bytes.extend_from_slice(&(pos.x as i16).to_le_bytes());
+    bytes.extend_from_slice(&(pos.y as i16).to_le_bytes());
+}
+
+fn decode_position(reader: &mut ByteReader) -> io::Result<Position> {
+    let x = i16::from_le_bytes(reader.take(2)?.try_into().unwrap());
+    let y = i16::from_le_bytes(reader.take(2)?.try_into().unwrap());
+    Ok(Position::new(x as i32, y as i32))
+}
+
+fn cell_to_u8(brick_type: BrickType) -> u8 {
+    CELL_TYPES
+        .iter()
+        .position(|item| *item == brick_type)
+        .unwrap_or(CELL_TYPES.len() - 1) as u8
+}
+
+fn cell_from_u8(value: u8) -> io::Result<BrickType> {
+    CELL_TYPES
+        .get(value as usize)
+        .copied()
+        .ok_or_else(|| invalid_data("unknown cell"))
+}
+
+pub fn write_frame(stream: &mut impl Write, message: &NetMessage) -> io::Result<()> {
+    let bytes = message.encode();
+    let mut frame = Vec::with_capacity(4 + bytes.len());
+    frame.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    frame.extend_from_slice(&bytes);
+    stream.write_all(&frame)
+}
+
+pub fn read_frame(stream: &mut impl Read) -> io::Result<NetMessage> {
+    let mut len = [0; 4];
+    stream.read_exact(&mut len)?;
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(invalid_data("frame is too long"));
+    }
+    let mut bytes = vec![0; len];
+    stream.read_exact(&mut bytes)?;
+    NetMessage::decode(&bytes)
+}
+
+pub enum NetEvent {
+    Connected,
+    Message(NetMessage),
+    // the connection failed or was closed, with the reason
+    Disconnected(String),
+}
+
+/// A connection to the relay server.
+/// Connecting, reading and writing happen on background threads, so a stalled server never
+/// blocks the game: the messages to send are queued, the events are polled by the game.
+pub struct NetClient {
+    // kept to close the connection
+    stream: Arc<Mutex<Option<TcpStream>>>,
+    outgoing: Sender<NetMessage>,
+    events: Mutex<Receiver<NetEvent>>,
+}
+
+impl NetClient {
+    pub fn connect(addr: &str) -> Self {
+        let stream = Arc::new(Mutex::new(None));
+        let (sender, receiver) = mpsc::channel();
+        let (outgoing, outgoing_receiver) = mpsc::channel();
+        let addr = addr.to_string();
+        let thread_stream = stream.clone();
+        thread::spawn(move || {
+            if let Err(err) = run_connection(&addr, &thread_stream, outgoing_receiver, &sender) {
+                let reason = match err.kind() {
+                    ErrorKind::UnexpectedEof => "the connection was closed".to_string(),
+                    _ => err.to_string(),
+                };
+                let _ = sender.send(NetEvent::Disconnected(reason));
+            }
+        });
+        NetClient {
+            stream,
+            outgoing,
+            events: Mutex::new(receiver),
+        }
+    }
+
+    /// Queues a message, the errors are reported by the reading thread as a disconnection.
+    pub fn send(&self, message: &NetMessage) {
+        let _ = self.outgoing.send(message.clone());
+    }
+
+    /// Takes the events received since the last call.
+    pub fn poll(&self) -> Vec<NetEvent> {
+        self.events.lock().unwrap().try_iter().collect()
+    }
+}
+
+impl Drop for NetClient {
+    fn drop(&mut self) {
+        // also stops the reading thread, the writing one stops with the queue
+        if let Some(stream) = self.stream.lock().unwrap().take() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+fn run_connection(
+    addr: &str,
+    shared_stream: &Mutex<Option<TcpStream>>,
+    outgoing: Receiver<NetMessage>,
+    sender: &Sender<NetEvent>,
+) -> io::Result<()> {
+    let mut stream = TcpStream::connect(addr)?;
+    stream.set_nodelay(true)?;
+    *shared_stream.lock().unwrap() = Some(stream.try_clone()?);
+    let mut writer = stream.try_clone()?;
+    thread::spawn(move || {
+        for message in outgoing {
+            if write_frame(&mut writer, &message).is_err() {
+                // the reading thread reports the broken connection
+                let _ = writer.shutdown(Shutdown::Both);
+                return;
+            }
+        }
+    });
+    if sender.send(NetEvent::Connected).is_err() {
+        return Ok(());
+    }
+    loop {
+        let message = read_frame(&mut stream)?;
+        if sender.send(NetEvent::Message(message)).is_err() {
+            // the client was dropped
+            return Ok(());
+        }
+    }
+}
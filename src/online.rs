@@ -0,0 +1,402 @@
+use bevy::prelude::*;
+
+use crate::{
+    engine::{GameEngine, TICK_SECONDS},
+    game::{
+        apply_level_settings, spawn_board_view, EngineViewFilter, GameSystemSet,
+        MAX_TICKS_PER_FRAME,
+    },
+    input::{read_gamepad, KeyMap},
+    layout::ScreenLayoutRes,
+    locale::{Locale, LocaleRes},
+    menu::{GameLevelRes, GameSelectedLevel},
+    mode::GameMode,
+    net::{BoardSnapshot, NetClient, NetEvent, NetMessage, DEFAULT_SERVER_ADDR},
+    theme::ThemeRes,
+    utils::despawn_with_component,
+    versus::{
+        despawn_engine_views, player_layout, set_garbage_meter, spawn_versus_view, GarbageMeter,
+        ResultBanner, ResultText, SentText, VersusConfigRes, VersusPlayer, VersusView,
+    },
+    GameState,
+};
+
+// the local board is left of or above the board of the opponent
+const LOCAL: usize = 0;
+const REMOTE: usize = 1;
+const ONLINE_HINTS: [&str; 2] = ["versus-hints-single", ""];
+
+/// Where to find the relay server.
+#[derive(Resource)]
+pub struct OnlineConfigRes {
+    pub server_addr: String,
+}
+
+impl Default for OnlineConfigRes {
+    fn default() -> Self {
+        OnlineConfigRes {
+            server_addr: DEFAULT_SERVER_ADDR.to_string(),
+        }
+    }
+}
+
+enum OnlineStatus {
+    Connecting,
+    WaitingForOpponent,
+    Playing,
+    Won,
+    Lost,
+    // the hello of the opponent has another protocol version
+    OtherVersion,
+    Disconnected(String),
+}
+
+#[derive(Resource)]
+struct OnlineRes {
+    client: NetClient,
+    status: OnlineStatus,
+    difficulty: GameSelectedLevel,
+    // our half of the seed and the nonce sent in the hello
+    seed: u64,
+    nonce: u64,
+    local: Option<VersusPlayer>,
+    remote: Option<BoardSnapshot>,
+    remote_dirty: bool,
+    last_sent: Option<BoardSnapshot>,
+    accumulator: f32,
+    // the orientation the boards are laid out for
+    screen: ScreenLayoutRes,
+}
+
+impl OnlineRes {
+    /// Starts the match once the hello of the opponent arrived,
+    /// both clients mix the two seeds the same way so they get the same bricks.
+    fn start(
+        &mut self,
+        difficulty: GameSelectedLevel,
+        seed: u64,
+        nonce: u64,
+        config: &VersusConfigRes,
+    ) {
+        let difficulty = if nonce < self.nonce {
+            difficulty
+        } else {
+            self.difficulty
+        };
+        apply_level_settings(difficulty);
+        let engine = GameEngine::new(difficulty, GameMode::Marathon, self.seed ^ seed);
+        self.local = Some(VersusPlayer::new(
+            engine,
+            player_layout(LOCAL, self.screen),
+            config,
+        ));
+        self.status = OnlineStatus::Playing;
+    }
+}
+
+pub struct OnlinePlugin;
+
+impl Plugin for OnlinePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OnlineConfigRes>()
+            .add_systems(OnEnter(GameState::Online), setup_online)
+            .add_systems(
+                OnExit(GameState::Online),
+                (
+                    despawn_with_component::<VersusView>,
+                    despawn_engine_views,
+                    remove_online,
+                ),
+            )
+            .add_systems(
+                Update,
+                relayout_online_system
+                    .in_set(GameSystemSet::Layout)
+                    .run_if(in_state(GameState::Online)),
+            )
+            .add_systems(
+                Update,
+                (online_network_system, online_tick_system)
+                    .chain()
+                    .in_set(GameSystemSet::Tick)
+                    .run_if(in_state(GameState::Online)),
+            )
+            .add_systems(
+                Update,
+                (online_render_system, online_info_system)
+                    .in_set(GameSystemSet::Render)
+                    .run_if(in_state(GameState::Online)),
+            )
+            .add_systems(
+                Update,
+                online_control_system.run_if(in_state(GameState::Online)),
+            );
+    }
+}
+
+fn setup_online(
+    mut commands: Commands,
+    theme_res: Res<ThemeRes>,
+    locale: Res<LocaleRes>,
+    screen: Res<ScreenLayoutRes>,
+    game_level: Res<GameLevelRes>,
+    config: Res<OnlineConfigRes>,
+) {
+    spawn_online_view(&mut commands, &theme_res, &locale, *screen);
+    commands.insert_resource(OnlineRes {
+        client: NetClient::connect(&config.server_addr),
+        status: OnlineStatus::Connecting,
+        difficulty: game_level.0,
+        seed: rand::random(),
+        nonce: rand::random(),
+        local: None,
+        remote: None,
+        remote_dirty: false,
+        last_sent: None,
+        accumulator: 0.,
+        screen: *screen,
+    });
+}
+
+fn spawn_online_view(
+    commands: &mut Commands,
+    theme_res: &ThemeRes,
+    locale: &Locale,
+    screen: ScreenLayoutRes,
+) {
+    spawn_versus_view(
+        commands,
+        theme_res,
+        locale,
+        screen,
+        [locale.text("versus-you"), locale.text("versus-opponent")],
+        ONLINE_HINTS.map(|hint| locale.text(hint)),
+    );
+}
+
+// the match goes on, only its view is drawn again
+fn relayout_online_system(
+    mut commands: Commands,
+    screen: Res<ScreenLayoutRes>,
+    theme_res: Res<ThemeRes>,
+    locale: Res<LocaleRes>,
+    mut online: ResMut<OnlineRes>,
+    query: Query<Entity, Or<(With<VersusView>, EngineViewFilter)>>,
+) {
+    if online.screen == *screen {
+        return;
+    }
+    online.screen = *screen;
+    if let Some(local) = online.local.as_mut() {
+        local.layout = player_layout(LOCAL, *screen);
+        local.engine.mark_dirty();
+    }
+    online.remote_dirty = true;
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+    spawn_online_view(&mut commands, &theme_res, &locale, *screen);
+}
+
+// dropping the client closes the connection
+fn remove_online(mut commands: Commands) {
+    commands.remove_resource::<OnlineRes>();
+}
+
+fn online_network_system(mut online: ResMut<OnlineRes>, config: Res<VersusConfigRes>) {
+    let online = &mut *online;
+    for event in online.client.poll() {
+        match event {
+            NetEvent::Connected => {
+                let hello = NetMessage::hello(online.difficulty, online.seed, online.nonce);
+                online.client.send(&hello);
+                online.status = OnlineStatus::WaitingForOpponent;
+            }
+            NetEvent::Message(message) if !message.is_supported_version() => {
+                online.status = OnlineStatus::OtherVersion;
+            }
+            NetEvent::Message(NetMessage::Hello {
+                difficulty,
+                seed,
+                nonce,
+                ..
+            }) => {
+                if matches!(online.status, OnlineStatus::WaitingForOpponent) {
+                    online.start(difficulty, seed, nonce, &config);
+                }
+            }
+            NetEvent::Message(NetMessage::Board(snapshot)) => {
+                online.remote = Some(snapshot);
+                online.remote_dirty = true;
+            }
+            NetEvent::Message(NetMessage::Attack(lines)) => {
+                if let Some(local) = online.local.as_mut() {
+                    local.garbage_queued += lines;
+                }
+            }
+            NetEvent::Message(NetMessage::GameOver) => {
+                if matches!(online.status, OnlineStatus::Playing) {
+                    online.status = OnlineStatus::Won;
+                }
+            }
+            NetEvent::Disconnected(reason) => {
+                // the result of a finished match stays, and so does a version mismatch
+                if !matches!(
+                    online.status,
+                    OnlineStatus::Won | OnlineStatus::Lost | OnlineStatus::OtherVersion
+                ) {
+                    online.status = OnlineStatus::Disconnected(reason);
+                }
+            }
+        }
+    }
+}
+
+fn online_tick_system(
+    time: Res<Time>,
+    keyboard_input: Res<Input<KeyCode>>,
+    button_input: Res<Input<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+    config: Res<VersusConfigRes>,
+    mut online: ResMut<OnlineRes>,
+) {
+    if !matches!(online.status, OnlineStatus::Playing) {
+        return;
+    }
+    let online = &mut *online;
+    let Some(local) = online.local.as_mut() else {
+        return;
+    };
+    // keep the keys pressed during frames without any tick for the next tick
+    local.pending_input.0 |= KeyMap::SINGLE_PLAYER.read(&keyboard_input).0;
+    if let Some(gamepad) = gamepads.iter().next() {
+        local.pending_input.0 |= read_gamepad(gamepad, &button_input).0;
+    }
+    online.accumulator =
+        (online.accumulator + time.delta_seconds()).min(MAX_TICKS_PER_FRAME * TICK_SECONDS);
+
+    while online.accumulator >= TICK_SECONDS && !local.engine.is_game_over() {
+        online.accumulator -= TICK_SECONDS;
+        let attack = local.step(&config.attack_table);
+        if attack > 0 {
+            online.client.send(&NetMessage::Attack(attack));
+        }
+    }
+
+    let snapshot = BoardSnapshot::new(&local.engine, local.garbage_queued, local.lines_sent);
+    if online.last_sent.as_ref() != Some(&snapshot) {
+        online.client.send(&NetMessage::Board(snapshot.clone()));
+        online.last_sent = Some(snapshot);
+    }
+    if local.engine.is_game_over() {
+        online.client.send(&NetMessage::GameOver);
+        online.status = OnlineStatus::Lost;
+    }
+}
+
+fn online_render_system(
+    mut commands: Commands,
+    mut online: ResMut<OnlineRes>,
+    theme_res: Res<ThemeRes>,
+    view_query: Query<Entity, EngineViewFilter>,
+    mut meter_query: Query<(&mut Sprite, &GarbageMeter)>,
+) {
+    let online = &mut *online;
+    let local_dirty = online
+        .local
+        .as_mut()
+        .is_some_and(|local| local.engine.take_dirty());
+    let remote_dirty = std::mem::take(&mut online.remote_dirty);
+    if local_dirty || remote_dirty {
+        for entity in &view_query {
+            commands.entity(entity).despawn_recursive();
+        }
+        if let Some(local) = &online.local {
+            spawn_board_view(
+                &mut commands,
+                &local.engine.board,
+                local.engine.active.as_ref(),
+                Some(local.engine.next),
+                None,
+                &local.layout,
+                &theme_res,
+            );
+        }
+        if let Some(remote) = &online.remote {
+            spawn_board_view(
+                &mut commands,
+                &remote.board,
+                remote.active.as_ref(),
+                Some(remote.next),
+                None,
+                &player_layout(REMOTE, online.screen),
+                &theme_res,
+            );
+        }
+    }
+
+    for (mut sprite, meter) in &mut meter_query {
+        let garbage_queued = match meter.0 {
+            LOCAL => online.local.as_ref().map(|local| local.garbage_queued),
+            _ => online.remote.as_ref().map(|remote| remote.garbage_queued),
+        };
+        set_garbage_meter(
+            &mut sprite,
+            garbage_queued.unwrap_or_default(),
+            &player_layout(meter.0, online.screen),
+        );
+    }
+}
+
+fn online_info_system(
+    online: Res<OnlineRes>,
+    server_config: Res<OnlineConfigRes>,
+    locale: Res<LocaleRes>,
+    mut sent_query: Query<(&mut Text, &SentText), Without<ResultText>>,
+    mut banner_query: Query<&mut Visibility, With<ResultBanner>>,
+    mut result_query: Query<&mut Text, With<ResultText>>,
+) {
+    for (mut text, sent) in &mut sent_query {
+        let lines_sent = match sent.0 {
+            LOCAL => online.local.as_ref().map(|local| local.lines_sent),
+            _ => online.remote.as_ref().map(|remote| remote.lines_sent),
+        };
+        let lines = lines_sent.unwrap_or_default();
+        text.sections[0].value = locale.format("versus-sent", &[("lines", &lines)]);
+    }
+
+    let message = match &online.status {
+        OnlineStatus::Connecting => Some(locale.format(
+            "online-connecting",
+            &[("address", &server_config.server_addr)],
+        )),
+        OnlineStatus::WaitingForOpponent => Some(locale.text("online-waiting").to_string()),
+        OnlineStatus::Playing => None,
+        OnlineStatus::Won => Some(locale.text("versus-you-win").to_string()),
+        OnlineStatus::Lost => Some(locale.text("versus-you-lose").to_string()),
+        OnlineStatus::OtherVersion => Some(locale.text("online-other-version").to_string()),
+        OnlineStatus::Disconnected(reason) => {
+            Some(locale.format("online-disconnected", &[("reason", reason)]))
+        }
+    };
+    let Ok(mut visibility) = banner_query.get_single_mut() else {
+        return;
+    };
+    let Some(message) = message else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    *visibility = Visibility::Visible;
+    if let Ok(mut text) = result_query.get_single_mut() {
+        text.sections[0].value = format!("{}\n{}", message, locale.text("online-leave"));
+    }
+}
+
+fn online_control_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        game_state.set(GameState::Menu);
+    }
+}
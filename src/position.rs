@@ -6,7 +6,6 @@ pub struct Position {
     pub y: i32,
 }
 
-
 impl std::ops::Add for Position {
     type Output = Self;
 
@@ -75,4 +74,12 @@ impl Position {
     pub fn down(&self) -> Self {
         Position::new(self.x, self.y - 1)
     }
-}
\ No newline at end of file
+
+    /// The cells of the board covered by a block at this position, when every block is a
+    /// square of `block_size` cells, e.g. the 4 cells of a block in the big mode.
+    pub fn block_cells(self, block_size: usize) -> impl Iterator<Item = Position> {
+        let size = block_size as i32;
+        (0..size * size)
+            .map(move |i| Position::new(self.x * size + i % size, self.y * size + i / size))
+    }
+}
@@ -0,0 +1,214 @@
+use std::{collections::HashSet, io, path::Path};
+
+use crate::{
+    board::Board,
+    brick::BrickType,
+    constants::{DEFAULT_BOARD_HEIGHT, DEFAULT_BOARD_WIDTH},
+    engine::LineClear,
+    locale::{clear_message_id, Locale},
+    replay::invalid_data,
+};
+
+// the puzzles of the user, loaded after the starter ones
+pub const PUZZLE_DIR: &str = "puzzles";
+pub const PUZZLE_PROGRESS_PATH: &str = "saves/puzzles.txt";
+
+/// The puzzles shipped with the game.
+const STARTER_PUZZLES: [&str; 6] = [
+    include_str!("../assets/puzzles/01-tetris.txt"),
+    include_str!("../assets/puzzles/02-perfect-clear.txt"),
+    include_str!("../assets/puzzles/03-two-wells.txt"),
+    include_str!("../assets/puzzles/04-tspin-single.txt"),
+    include_str!("../assets/puzzles/05-tspin-double.txt"),
+    include_str!("../assets/puzzles/06-dig.txt"),
+];
+
+/// What the player has to do with the given bricks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PuzzleGoal {
+    // leave the board empty
+    ClearAll,
+    // clear this many lines in total
+    Lines(usize),
+    // clear this many lines at once with a T-spin
+    TSpin(usize),
+}
+
+impl PuzzleGoal {
+    /// Parses "clear all", "lines 4" or "tspin 2".
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let count = |word: &str| {
+            word.parse::<usize>()
+                .map_err(|_| invalid_data("invalid goal count"))
+        };
+        match words.as_slice() {
+            ["clear", "all"] => Ok(PuzzleGoal::ClearAll),
+            ["lines", n] => Ok(PuzzleGoal::Lines(count(n)?)),
+            ["tspin", n] => Ok(PuzzleGoal::TSpin(count(n)?)),
+            _ => Err(invalid_data("unknown goal")),
+        }
+    }
+
+    pub fn describe(&self, locale: &Locale) -> String {
+        match self {
+            PuzzleGoal::ClearAll => locale.text("goal-clear-all").to_string(),
+            PuzzleGoal::Lines(1) => locale.text("goal-one-line").to_string(),
+            PuzzleGoal::Lines(lines) => locale.format("goal-lines", &[("lines", lines)]),
+            PuzzleGoal::TSpin(lines @ 1..=3) => {
+                let name = locale.text(clear_message_id(*lines));
+                locale.format("action-tspin", &[("clear", &name)])
+            }
+            PuzzleGoal::TSpin(_) => locale.text("goal-tspin").to_string(),
+        }
+    }
+
+    /// Whether the goal is met by a line clear, `total_lines` includes it.
+    pub fn is_met(&self, clear: &LineClear, total_lines: usize) -> bool {
+        match self {
+            PuzzleGoal::ClearAll => clear.perfect_clear,
+            PuzzleGoal::Lines(lines) => total_lines >= *lines,
+            PuzzleGoal::TSpin(lines) => clear.is_tspin() && clear.count == *lines,
+        }
+    }
+}
+
+/// A board to start from, the bricks to play in order and a goal.
+///
+/// The text format has `key = value` lines, `#` comments, then the rows of the board
+/// from the top to the bottom after a `board:` line, e.g.
+/// ```text
+/// name = Tetris
+/// goal = lines 4
+/// pieces = I
+/// board:
+/// GGGGGGGGG.
+/// ```
+#[derive(Clone, Debug)]
+pub struct Puzzle {
+    pub name: String,
+    pub goal: PuzzleGoal,
+    pub pieces: Vec<BrickType>,
+    pub board: Board,
+}
+
+impl Puzzle {
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let mut name = None;
+        let mut goal = None;
+        let mut pieces = None;
+        let mut rows: Option<Vec<Vec<BrickType>>> = None;
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(rows) = rows.as_mut() {
+                rows.push(parse_row(line)?);
+                continue;
+            }
+            if line == "board:" {
+                rows = Some(Vec::new());
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(invalid_data("expected a `key = value` line"));
+            };
+            let value = value.trim();
+            match key.trim() {
+                "name" => name = Some(value.to_string()),
+                "goal" => goal = Some(PuzzleGoal::parse(value)?),
+                "pieces" => pieces = Some(parse_pieces(value)?),
+                _ => return Err(invalid_data("unknown key")),
+            }
+        }
+
+        let pieces = pieces.filter(|pieces| !pieces.is_empty());
+        let pieces = pieces.ok_or_else(|| invalid_data("no pieces"))?;
+        let board = board_from_rows(&rows.unwrap_or_default())?;
+        Ok(Puzzle {
+            name: name.ok_or_else(|| invalid_data("no name"))?,
+            goal: goal.ok_or_else(|| invalid_data("no goal"))?,
+            pieces,
+            board,
+        })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Puzzle::parse(&std::fs::read_to_string(path)?)
+    }
+}
+
+/// A row of a text board, e.g. `GGGG.GGGGG`.
+pub(crate) fn parse_row(line: &str) -> io::Result<Vec<BrickType>> {
+    line.chars()
+        .map(|c| BrickType::from_char(c).ok_or_else(|| invalid_data("unknown cell")))
+        .collect()
+}
+
+/// A list of bricks, e.g. `TIO` or `T I O`.
+pub(crate) fn parse_pieces(text: &str) -> io::Result<Vec<BrickType>> {
+    text.chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match BrickType::from_char(c) {
+            Some(brick_type) if BrickType::ALL.contains(&brick_type) => Ok(brick_type),
+            _ => Err(invalid_data("unknown piece")),
+        })
+        .collect()
+}
+
+/// Puts the rows, from the top to the bottom, at the bottom of a board of the default height,
+/// taller when they do not fit.
+pub(crate) fn board_from_rows(rows: &[Vec<BrickType>]) -> io::Result<Board> {
+    let width = rows.first().map_or(DEFAULT_BOARD_WIDTH, |row| row.len());
+    if width < 4 || rows.iter().any(|row| row.len() != width) {
+        return Err(invalid_data("the rows of the board differ in width"));
+    }
+    let mut board = Board::new(width, DEFAULT_BOARD_HEIGHT.max(rows.len()));
+    for (y, row) in rows.iter().rev().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            board.set(x, y, *cell);
+        }
+    }
+    Ok(board)
+}
+
+/// The starter puzzles, followed by the ones found in `PUZZLE_DIR`.
+pub fn load_puzzles() -> Vec<Puzzle> {
+    let mut puzzles: Vec<Puzzle> = STARTER_PUZZLES
+        .iter()
+        .map(|text| Puzzle::parse(text).expect("invalid starter puzzle"))
+        .collect();
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Ok(entries) = std::fs::read_dir(PUZZLE_DIR) {
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+            .collect();
+        paths.sort();
+        for path in paths {
+            match Puzzle::load(&path) {
+                Ok(puzzle) => puzzles.push(puzzle),
+                Err(err) => eprintln!("failed to load puzzle {}: {}", path.display(), err),
+            }
+        }
+    }
+    puzzles
+}
+
+/// The names of the solved puzzles, one per line.
+pub fn load_progress(path: impl AsRef<Path>) -> HashSet<String> {
+    std::fs::read_to_string(path)
+        .map(|text| text.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+pub fn save_progress(path: impl AsRef<Path>, solved: &HashSet<String>) -> io::Result<()> {
+    if let Some(parent) = path.as_ref().parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut names: Vec<&String> = solved.iter().collect();
+    names.sort();
+    let text: String = names.iter().map(|name| format!("{}\n", name)).collect();
+    std::fs::write(path, text)
+}
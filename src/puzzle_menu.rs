@@ -0,0 +1,233 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::{
+    common_entity::EntitySpawner,
+    events::GoalReached,
+    game::GameSystemSet,
+    locale::LocaleRes,
+    menu::{GameLevelRes, GameSelectedLevel, WatchAiRes},
+    puzzle::{load_puzzles, Puzzle},
+    theme::ThemeRes,
+    utils::{common_button_system, despawn_with_component},
+    GameState,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::puzzle::{load_progress, save_progress, PUZZLE_PROGRESS_PATH};
+
+// three puzzles per row
+const PUZZLE_LIST_WIDTH: f32 = 870.;
+const SOLVED_COLOR: Color = Color::rgb(0.3, 0.8, 0.4);
+
+/// The puzzles to choose from, the one being played and the solved ones.
+#[derive(Resource, Default)]
+pub struct PuzzleRes {
+    pub puzzles: Vec<Puzzle>,
+    pub current: Option<usize>,
+    pub solved: HashSet<String>,
+}
+
+impl PuzzleRes {
+    pub fn current_puzzle(&self) -> Option<&Puzzle> {
+        self.current.and_then(|index| self.puzzles.get(index))
+    }
+}
+
+#[derive(Component)]
+struct PuzzleMenuScreen;
+
+#[derive(Component)]
+enum PuzzleMenuButtonAction {
+    Play(usize),
+    Back,
+}
+
+pub struct PuzzleMenuPlugin;
+
+impl Plugin for PuzzleMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PuzzleRes>()
+            .add_systems(OnEnter(GameState::Puzzles), puzzle_menu_setup)
+            .add_systems(
+                OnExit(GameState::Puzzles),
+                despawn_with_component::<PuzzleMenuScreen>,
+            )
+            .add_systems(Update, (menu_action, common_button_system))
+            .add_systems(
+                Update,
+                puzzle_solved_system
+                    .in_set(GameSystemSet::Events)
+                    .run_if(in_state(GameState::Game)),
+            );
+    }
+}
+
+fn puzzle_menu_setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    theme_res: Res<ThemeRes>,
+    locale: Res<LocaleRes>,
+    mut puzzle_res: ResMut<PuzzleRes>,
+) {
+    // loaded again every time, so new puzzle files show up without a restart
+    puzzle_res.puzzles = load_puzzles();
+    #[cfg(not(target_arch = "wasm32"))]
+    puzzle_res
+        .solved
+        .extend(load_progress(PUZZLE_PROGRESS_PATH));
+
+    let font = theme_res.font();
+    let ui = theme_res.theme().ui;
+    let status_style = TextStyle {
+        font: font.clone(),
+        font_size: 24.0,
+        color: SOLVED_COLOR,
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            PuzzleMenuScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        padding: UiRect::px(20., 20., 10., 10.),
+                        ..default()
+                    },
+                    background_color: ui.panel.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(
+                        TextBundle::from_section(
+                            locale.text("puzzles-title"),
+                            TextStyle {
+                                font: font.clone(),
+                                font_size: 60.0,
+                                color: ui.text,
+                            },
+                        )
+                        .with_style(Style {
+                            margin: UiRect::all(Val::Px(10.0)),
+                            ..default()
+                        }),
+                    );
+
+                    // Display one button per puzzle, with its goal and whether it was solved
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                width: Val::Px(PUZZLE_LIST_WIDTH),
+                                flex_direction: FlexDirection::Row,
+                                flex_wrap: FlexWrap::Wrap,
+                                justify_content: JustifyContent::Center,
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for (index, puzzle) in puzzle_res.puzzles.iter().enumerate() {
+                                parent
+                                    .spawn(NodeBundle {
+                                        style: Style {
+                                            flex_direction: FlexDirection::Column,
+                                            align_items: AlignItems::Center,
+                                            ..default()
+                                        },
+                                        ..default()
+                                    })
+                                    .with_children(|parent| {
+                                        parent.spawn_button(
+                                            PuzzleMenuButtonAction::Play(index),
+                                            "right.png",
+                                            &puzzle.name,
+                                            &asset_server,
+                                            &theme_res,
+                                        );
+                                        let goal = puzzle.goal.describe(&locale);
+                                        let status = if puzzle_res.solved.contains(&puzzle.name) {
+                                            locale.format("puzzles-solved", &[("goal", &goal)])
+                                        } else {
+                                            goal
+                                        };
+                                        parent.spawn(TextBundle::from_section(
+                                            status,
+                                            status_style.clone(),
+                                        ));
+                                    });
+                            }
+                        });
+
+                    parent.spawn_button(
+                        PuzzleMenuButtonAction::Back,
+                        "exitRight.png",
+                        locale.text("menu-back"),
+                        &asset_server,
+                        &theme_res,
+                    );
+                });
+        });
+}
+
+fn menu_action(
+    interaction_query: Query<
+        (&Interaction, &PuzzleMenuButtonAction),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut game_level: ResMut<GameLevelRes>,
+    mut watch_ai: ResMut<WatchAiRes>,
+    mut puzzle_res: ResMut<PuzzleRes>,
+) {
+    for (interaction, menu_button_action) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            match menu_button_action {
+                PuzzleMenuButtonAction::Play(index) => {
+                    game_state.set(GameState::Game);
+                    // the puzzles are played with the ghost and the grid
+                    game_level.0 = GameSelectedLevel::Easy;
+                    watch_ai.0 = false;
+                    puzzle_res.current = Some(*index);
+                }
+                PuzzleMenuButtonAction::Back => {
+                    game_state.set(GameState::Menu);
+                }
+            }
+        }
+    }
+}
+
+fn puzzle_solved_system(
+    mut goal_events: EventReader<GoalReached>,
+    mut puzzle_res: ResMut<PuzzleRes>,
+) {
+    if goal_events.read().count() == 0 {
+        return;
+    }
+    let Some(name) = puzzle_res
+        .current_puzzle()
+        .map(|puzzle| puzzle.name.clone())
+    else {
+        return;
+    };
+    puzzle_res.solved.insert(name);
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Err(err) = save_progress(PUZZLE_PROGRESS_PATH, &puzzle_res.solved) {
+        warn!("failed to save the puzzle progress: {}", err);
+    }
+}
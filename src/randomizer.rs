@@ -0,0 +1,53 @@
+use std::collections::VecDeque;
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::brick::{Brick, BrickType};
+
+/// Seeded brick generator.
+/// The same seed always produces the same sequence of bricks, so a game can be re-simulated.
+#[derive(Clone)]
+pub struct Randomizer {
+    use_bag7: bool,
+    rng: StdRng,
+    bag: VecDeque<BrickType>,
+    // given bricks, e.g. of a puzzle, dealt before the random ones
+    queued: VecDeque<BrickType>,
+}
+
+impl Randomizer {
+    pub fn new(seed: u64, use_bag7: bool) -> Self {
+        Randomizer {
+            use_bag7,
+            rng: StdRng::seed_from_u64(seed),
+            bag: VecDeque::with_capacity(BrickType::ALL.len()),
+            queued: VecDeque::new(),
+        }
+    }
+
+    /// Deals these bricks in order before any random one.
+    pub fn queue(&mut self, bricks: impl IntoIterator<Item = BrickType>) {
+        self.queued = bricks.into_iter().collect();
+    }
+
+    pub fn next_type(&mut self) -> BrickType {
+        if let Some(brick_type) = self.queued.pop_front() {
+            return brick_type;
+        }
+        if !self.use_bag7 {
+            // use normal randomization algorithm
+            return BrickType::ALL[self.rng.gen_range(0..BrickType::ALL.len())];
+        }
+        // use 7 bag randomization algorithm
+        if self.bag.is_empty() {
+            let mut items = BrickType::ALL;
+            items.shuffle(&mut self.rng);
+            self.bag.extend(items);
+        }
+        self.bag.pop_front().unwrap_or(BrickType::J)
+    }
+
+    pub fn next_brick(&mut self) -> Brick {
+        Brick::from_type(self.next_type())
+    }
+}
@@ -0,0 +1,625 @@
+use std::{
+    io::{self, ErrorKind},
+    path::Path,
+};
+
+use bevy::{prelude::*, sprite::Anchor, text::Text2dBounds};
+
+use crate::{
+    engine::{GameEngine, InputFrame, TICKS_PER_SECOND},
+    events::{GameOver, GameTicked, GoalReached},
+    game::{
+        apply_level_settings, setup_tetris, BoardLayoutRes, GameEngineRes, GameSystemSet,
+        GameViewRelayout,
+    },
+    layout::ScreenLayoutRes,
+    locale::{Locale, LocaleRes},
+    menu::GameSelectedLevel,
+    mode::{GameMode, GameOptions},
+    theme::ThemeRes,
+    utils::despawn_with_component,
+    GameState,
+};
+
+const REPLAY_MAGIC: &[u8; 4] = b"TTRP";
+const REPLAY_VERSION: u8 = 5;
+// the replays from before the game options, played with the default ones
+const REPLAY_VERSION_WITHOUT_OPTIONS: u8 = 4;
+
+const REPLAY_SPEEDS: [f32; 6] = [0.25, 0.5, 1., 2., 4., 8.];
+const DEFAULT_SPEED_INDEX: usize = 2;
+const SEEK_SECONDS: u64 = 5;
+
+/// A recorded game: the randomizer seed plus every tick that had some input.
+///
+/// File layout (little endian):
+/// magic "TTRP", version u8, difficulty u8, mode u8, seed u64, start level varint,
+/// board columns and rows varint (0 for the size of the mode), total ticks varint, input count varint, then for every input the tick delta as varint and the input bits as u8.
+#[derive(Clone, Debug)]
+pub struct Replay {
+    pub difficulty: GameSelectedLevel,
+    pub mode: GameMode,
+    pub seed: u64,
+    pub options: GameOptions,
+    pub total_ticks: u64,
+    pub inputs: Vec<(u64, InputFrame)>,
+}
+
+impl Replay {
+    pub fn new(
+        difficulty: GameSelectedLevel,
+        mode: GameMode,
+        seed: u64,
+        options: GameOptions,
+    ) -> Self {
+        Replay {
+            difficulty,
+            mode,
+            seed,
+            options,
+            total_ticks: 0,
+            inputs: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, tick: u64, input: InputFrame) {
+        self.total_ticks = tick;
+        if !input.is_empty() {
+            self.inputs.push((tick, input));
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(16 + self.inputs.len() * 2);
+        bytes.extend_from_slice(REPLAY_MAGIC);
+        bytes.push(REPLAY_VERSION);
+        bytes.push(difficulty_to_u8(self.difficulty));
+        bytes.push(mode_to_u8(self.mode));
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        let (columns, rows) = self.options.board_size.unwrap_or((0, 0));
+        write_varint(&mut bytes, self.options.start_level as u64);
+        write_varint(&mut bytes, columns as u64);
+        write_varint(&mut bytes, rows as u64);
+        write_varint(&mut bytes, self.total_ticks);
+        write_varint(&mut bytes, self.inputs.len() as u64);
+        let mut last_tick = 0;
+        for (tick, input) in &self.inputs {
+            write_varint(&mut bytes, tick - last_tick);
+            bytes.push(input.0);
+            last_tick = *tick;
+        }
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> io::Result<Self> {
+        let mut reader = ByteReader::new(bytes);
+        if reader.take(4)? != REPLAY_MAGIC {
+            return Err(invalid_data("not a tetris replay file"));
+        }
+        let version = reader.take(1)?[0];
+        // the older replays were played without the vanish zone, they would desync
+        if version < REPLAY_VERSION_WITHOUT_OPTIONS {
+            return Err(invalid_data("replay from an older version of the game"));
+        }
+        if version > REPLAY_VERSION {
+            return Err(invalid_data("unsupported replay version"));
+        }
+        let difficulty = difficulty_from_u8(reader.take(1)?[0])?;
+        let mode = mode_from_u8(reader.take(1)?[0])?;
+        let seed = u64::from_le_bytes(reader.take(8)?.try_into().unwrap());
+        let mut options = GameOptions::default();
+        if version == REPLAY_VERSION {
+            options.start_level = reader.varint()?.max(1) as usize;
+            let columns = reader.varint()? as usize;
+            let rows = reader.varint()? as usize;
+            if columns > 0 && rows > 0 {
+                options.board_size = Some((columns, rows));
+            }
+        }
+        let total_ticks = reader.varint()?;
+        let count = reader.varint()?;
+
+        let mut inputs = Vec::new();
+        let mut tick = 0;
+        for _ in 0..count {
+            tick += reader.varint()?;
+            inputs.push((tick, InputFrame(reader.take(1)?[0])));
+        }
+        Ok(Replay {
+            difficulty,
+            mode,
+            seed,
+            options,
+            total_ticks,
+            inputs,
+        })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Replay::decode(&std::fs::read(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, self.encode())
+    }
+}
+
+pub(crate) fn difficulty_to_u8(difficulty: GameSelectedLevel) -> u8 {
+    match difficulty {
+        GameSelectedLevel::Easy => 0,
+        GameSelectedLevel::Normal => 1,
+        GameSelectedLevel::Hard => 2,
+    }
+}
+
+pub(crate) fn difficulty_from_u8(value: u8) -> io::Result<GameSelectedLevel> {
+    match value {
+        0 => Ok(GameSelectedLevel::Easy),
+        1 => Ok(GameSelectedLevel::Normal),
+        2 => Ok(GameSelectedLevel::Hard),
+        _ => Err(invalid_data("unknown difficulty")),
+    }
+}
+
+fn mode_to_u8(mode: GameMode) -> u8 {
+    GameMode::ALL
+        .iter()
+        .position(|item| *item == mode)
+        .unwrap_or(0) as u8
+}
+
+fn mode_from_u8(value: u8) -> io::Result<GameMode> {
+    GameMode::ALL
+        .get(value as usize)
+        .copied()
+        .ok_or_else(|| invalid_data("unknown game mode"))
+}
+
+pub(crate) fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, msg)
+}
+
+pub(crate) fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+/// Reads the binary formats of the replays and of the online messages.
+pub(crate) struct ByteReader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        ByteReader { bytes, offset: 0 }
+    }
+
+    pub(crate) fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        let end = self.offset + len;
+        if end > self.bytes.len() {
+            return Err(io::Error::from(ErrorKind::UnexpectedEof));
+        }
+        let slice = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(slice)
+    }
+
+    pub(crate) fn varint(&mut self) -> io::Result<u64> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid_data("varint is too long"))
+    }
+}
+
+/// The replay being recorded while playing.
+#[derive(Resource, Default)]
+pub struct ReplayRecorderRes(pub Option<Replay>);
+
+/// The replay shown by the replay viewer, the last finished game or a file from the command line.
+#[derive(Resource, Default)]
+pub struct LastReplayRes(pub Option<Replay>);
+
+#[derive(Resource)]
+struct ReplayPlaybackRes {
+    cursor: usize,
+    speed_index: usize,
+    paused: bool,
+    tick_accumulator: f32,
+}
+
+#[derive(Component)]
+struct ReplayInfoBundle;
+
+#[derive(Component)]
+struct ReplayInfoText;
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReplayRecorderRes>()
+            .init_resource::<LastReplayRes>()
+            .add_systems(
+                OnEnter(GameState::Replay),
+                (setup_replay, apply_deferred, setup_tetris).chain(),
+            )
+            .add_systems(
+                OnExit(GameState::Replay),
+                despawn_with_component::<ReplayInfoBundle>,
+            )
+            .add_systems(
+                Update,
+                record_replay_system
+                    .in_set(GameSystemSet::Events)
+                    .run_if(in_state(GameState::Game)),
+            )
+            .add_systems(
+                Update,
+                (
+                    replay_control_system,
+                    replay_tick_system,
+                    replay_info_system,
+                )
+                    .chain()
+                    .in_set(GameSystemSet::Tick)
+                    .run_if(in_state(GameState::Replay)),
+            )
+            .add_systems(
+                Update,
+                relayout_replay_info_system
+                    .after(GameSystemSet::Layout)
+                    .run_if(in_state(GameState::Replay)),
+            );
+    }
+}
+
+fn record_replay_system(
+    mut ticked_events: EventReader<GameTicked>,
+    mut game_over_events: EventReader<GameOver>,
+    mut goal_events: EventReader<GoalReached>,
+    mut replay_recorder: ResMut<ReplayRecorderRes>,
+    mut last_replay: ResMut<LastReplayRes>,
+) {
+    let Some(replay) = replay_recorder.0.as_mut() else {
+        return;
+    };
+    for event in ticked_events.read() {
+        replay.record(event.tick, event.input);
+    }
+    if game_over_events.read().count() + goal_events.read().count() > 0 {
+        let replay = replay_recorder.0.take().unwrap();
+        save_finished_replay(&replay);
+        last_replay.0 = Some(replay);
+    }
+}
+
+/// Saves the replay of a finished game next to the executable's working directory.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_finished_replay(replay: &Replay) {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let path = format!("replays/replay-{}.ttr", secs);
+    if let Err(err) = replay.save(&path) {
+        warn!("failed to save replay to {}: {}", path, err);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn save_finished_replay(_replay: &Replay) {}
+
+fn setup_replay(
+    mut commands: Commands,
+    theme_res: Res<ThemeRes>,
+    locale: Res<LocaleRes>,
+    screen: Res<ScreenLayoutRes>,
+    last_replay: Res<LastReplayRes>,
+    mut layout: ResMut<BoardLayoutRes>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    let Some(replay) = &last_replay.0 else {
+        game_state.set(GameState::Menu);
+        return;
+    };
+    apply_level_settings(replay.difficulty);
+    let (columns, rows) = replay.options.board_size(replay.mode);
+    *layout = BoardLayoutRes::for_screen(columns, rows, *screen);
+    commands.insert_resource(GameEngineRes(GameEngine::with_options(
+        replay.difficulty,
+        replay.mode,
+        replay.seed,
+        replay.options,
+    )));
+    commands.insert_resource(ReplayPlaybackRes {
+        cursor: 0,
+        speed_index: DEFAULT_SPEED_INDEX,
+        paused: false,
+        tick_accumulator: 0.,
+    });
+    spawn_replay_info(&mut commands, &theme_res, &locale, *screen);
+}
+
+// the info at the top of the screen and the controls at the bottom
+fn spawn_replay_info(
+    commands: &mut Commands,
+    theme_res: &ThemeRes,
+    locale: &Locale,
+    screen: ScreenLayoutRes,
+) {
+    let size = screen.size();
+    commands
+        .spawn((
+            SpriteBundle {
+                transform: Transform::from_xyz(0., size.y / 2. - 10., 0.),
+                ..default()
+            },
+            ReplayInfoBundle,
+        ))
+        .with_children(|parent| {
+            let text_style = TextStyle {
+                font: theme_res.font(),
+                font_size: 24.0,
+                color: theme_res.theme().ui.game_text,
+            };
+            parent.spawn((
+                Text2dBundle {
+                    text: Text::from_section("", text_style.clone())
+                        .with_alignment(TextAlignment::Center),
+                    text_anchor: Anchor::TopCenter,
+                    ..default()
+                },
+                ReplayInfoText,
+            ));
+            parent.spawn(Text2dBundle {
+                text: Text::from_section(locale.text("replay-controls"), text_style)
+                    .with_alignment(TextAlignment::Center),
+                transform: Transform::from_xyz(0., -(size.y - 40.), 0.),
+                text_anchor: Anchor::BottomCenter,
+                // wrapped in a portrait window
+                text_2d_bounds: Text2dBounds {
+                    size: Vec2::new(size.x - 40., f32::INFINITY),
+                },
+                ..default()
+            });
+        });
+}
+
+fn relayout_replay_info_system(
+    mut commands: Commands,
+    mut relayout_events: EventReader<GameViewRelayout>,
+    screen: Res<ScreenLayoutRes>,
+    theme_res: Res<ThemeRes>,
+    locale: Res<LocaleRes>,
+    query: Query<Entity, With<ReplayInfoBundle>>,
+) {
+    if relayout_events.read().count() == 0 {
+        return;
+    }
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+    spawn_replay_info(&mut commands, &theme_res, &locale, *screen);
+}
+
+fn replay_control_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    last_replay: Res<LastReplayRes>,
+    mut playback: ResMut<ReplayPlaybackRes>,
+    mut engine: ResMut<GameEngineRes>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    let Some(replay) = &last_replay.0 else {
+        return;
+    };
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        game_state.set(GameState::Menu);
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::Space) {
+        playback.paused = !playback.paused;
+    }
+    if keyboard_input.just_pressed(KeyCode::Up) {
+        playback.speed_index = (playback.speed_index + 1).min(REPLAY_SPEEDS.len() - 1);
+    }
+    if keyboard_input.just_pressed(KeyCode::Down) {
+        playback.speed_index = playback.speed_index.saturating_sub(1);
+    }
+
+    let seek_ticks = SEEK_SECONDS * TICKS_PER_SECOND as u64;
+    let current_tick = engine.0.tick;
+    if keyboard_input.just_pressed(KeyCode::Left) {
+        seek(
+            replay,
+            &mut engine.0,
+            &mut playback.cursor,
+            current_tick.saturating_sub(seek_ticks),
+        );
+    } else if keyboard_input.just_pressed(KeyCode::Right) {
+        seek(
+            replay,
+            &mut engine.0,
+            &mut playback.cursor,
+            current_tick + seek_ticks,
+        );
+    }
+}
+
+fn replay_tick_system(
+    time: Res<Time>,
+    last_replay: Res<LastReplayRes>,
+    mut playback: ResMut<ReplayPlaybackRes>,
+    mut engine: ResMut<GameEngineRes>,
+) {
+    let Some(replay) = &last_replay.0 else {
+        return;
+    };
+    if playback.paused {
+        return;
+    }
+    playback.tick_accumulator +=
+        time.delta_seconds() * REPLAY_SPEEDS[playback.speed_index] * TICKS_PER_SECOND as f32;
+    let ticks = playback.tick_accumulator as u64;
+    playback.tick_accumulator -= ticks as f32;
+
+    let target_tick = engine.0.tick + ticks;
+    seek(replay, &mut engine.0, &mut playback.cursor, target_tick);
+}
+
+fn replay_info_system(
+    last_replay: Res<LastReplayRes>,
+    playback: Res<ReplayPlaybackRes>,
+    engine: Res<GameEngineRes>,
+    locale: Res<LocaleRes>,
+    mut text_query: Query<&mut Text, With<ReplayInfoText>>,
+) {
+    let Some(replay) = &last_replay.0 else {
+        return;
+    };
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+    let state = if engine.0.tick >= replay.total_ticks || engine.0.is_game_over() {
+        locale.text("replay-end")
+    } else if playback.paused {
+        locale.text("replay-paused")
+    } else {
+        locale.text("replay-playing")
+    };
+    text.sections[0].value = locale.format(
+        "replay-status",
+        &[
+            ("state", &state),
+            ("speed", &REPLAY_SPEEDS[playback.speed_index]),
+            ("time", &format_ticks(engine.0.tick)),
+            ("total", &format_ticks(replay.total_ticks)),
+        ],
+    );
+}
+
+/// Re-simulates the replay until the engine reaches the target tick.
+/// Seeking backwards restarts the game from the seed, since the engine cannot be rewound.
+fn seek(replay: &Replay, engine: &mut GameEngine, cursor: &mut usize, target_tick: u64) {
+    let target_tick = target_tick.min(replay.total_ticks);
+    if target_tick < engine.tick {
+        *engine =
+            GameEngine::with_options(replay.difficulty, replay.mode, replay.seed, replay.options);
+        *cursor = 0;
+    }
+    while engine.tick < target_tick && !engine.is_game_over() {
+        let tick = engine.tick + 1;
+        let mut input = InputFrame::default();
+        if let Some((input_tick, recorded)) = replay.inputs.get(*cursor) {
+            if *input_tick == tick {
+                input = *recorded;
+                *cursor += 1;
+            }
+        }
+        engine.step(input);
+        // the viewer does not collect statistics
+        engine.drain_events();
+    }
+}
+
+fn format_ticks(ticks: u64) -> String {
+    let secs = ticks / TICKS_PER_SECOND as u64;
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{engine::InputAction, save::restore_engine};
+
+    // a few seconds of play, with a move, a rotation and a hard drop now and then
+    fn played_replay() -> (Replay, GameEngine) {
+        let options = GameOptions {
+            start_level: 3,
+            board_size: Some((12, 22)),
+        };
+        let mut replay = Replay::new(GameSelectedLevel::Normal, GameMode::Marathon, 42, options);
+        let mut engine =
+            GameEngine::with_options(replay.difficulty, replay.mode, replay.seed, replay.options);
+        while engine.tick < 600 && !engine.is_game_over() {
+            let tick = engine.tick + 1;
+            let mut input = InputFrame::default();
+            match tick % 40 {
+                5 => input.insert(InputAction::MoveLeft),
+                10 => input.insert(InputAction::Rotate),
+                20 if tick % 80 == 20 => input.insert(InputAction::MoveRight),
+                30 => input.insert(InputAction::HardDrop),
+                _ => {}
+            }
+            engine.step(input);
+            engine.drain_events().for_each(drop);
+            replay.record(tick, input);
+        }
+        (replay, engine)
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let (replay, engine) = played_replay();
+        assert!(!engine.board.is_line_empty(0));
+        let path = std::env::temp_dir().join(format!("tetris-replay-{}.ttr", std::process::id()));
+        replay.save(&path).unwrap();
+        let loaded = Replay::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+
+        assert_eq!(loaded.difficulty, replay.difficulty);
+        assert_eq!(loaded.mode, replay.mode);
+        assert_eq!(loaded.seed, replay.seed);
+        assert_eq!(loaded.options, replay.options);
+        assert_eq!(loaded.total_ticks, replay.total_ticks);
+        assert_eq!(loaded.inputs, replay.inputs);
+
+        // the loaded replay plays the same game again
+        let restored = restore_engine(&loaded, drop);
+        assert_eq!(restored.tick, engine.tick);
+        assert_eq!(restored.board, engine.board);
+        assert_eq!(restored.score, engine.score);
+        assert_eq!(restored.lines, engine.lines);
+    }
+
+    #[test]
+    fn decodes_replays_without_options() {
+        let (mut replay, _) = played_replay();
+        replay.options = GameOptions::default();
+        let mut bytes = replay.encode();
+        bytes[4] = REPLAY_VERSION_WITHOUT_OPTIONS;
+        // the start level and the board size are not in the older replays
+        bytes.drain(15..18);
+        let decoded = Replay::decode(&bytes).unwrap();
+        assert_eq!(decoded.options, GameOptions::default());
+        assert_eq!(decoded.inputs, replay.inputs);
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let (replay, _) = played_replay();
+        let mut bytes = replay.encode();
+        for version in [REPLAY_VERSION_WITHOUT_OPTIONS - 1, REPLAY_VERSION + 1] {
+            bytes[4] = version;
+            assert!(Replay::decode(&bytes).is_err());
+        }
+        assert!(Replay::decode(b"TTRX").is_err());
+    }
+}
@@ -0,0 +1,113 @@
+use bevy::{prelude::*, window::WindowCloseRequested};
+
+use crate::{
+    engine::{EngineEvent, GameEngine, InputFrame},
+    game::GameSystemSet,
+    replay::{LastReplayRes, Replay, ReplayRecorderRes},
+    GameState,
+};
+
+pub const SAVE_PATH: &str = "saves/game.ttr";
+const SAVE_KEY: KeyCode = KeyCode::F5;
+
+/// The game saved to be continued from the main menu.
+///
+/// A game is saved as the replay recorded so far: the engine is deterministic, so playing
+/// the inputs again restores the board, the bricks, the randomizer, the score and the timers.
+#[derive(Resource, Default)]
+pub struct SavedGameRes {
+    pub replay: Option<Replay>,
+    // the next game continues the saved one
+    pub resume: bool,
+}
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SavedGameRes>()
+            .add_systems(Startup, load_saved_game)
+            .add_systems(OnEnter(GameState::GameOver), discard_finished_game)
+            .add_systems(
+                Update,
+                save_game_system
+                    .in_set(GameSystemSet::Events)
+                    .run_if(in_state(GameState::Game)),
+            );
+    }
+}
+
+/// Plays the inputs of a saved game again, passing on every event of the engine.
+pub fn restore_engine(replay: &Replay, mut on_event: impl FnMut(EngineEvent)) -> GameEngine {
+    let mut engine =
+        GameEngine::with_options(replay.difficulty, replay.mode, replay.seed, replay.options);
+    let mut inputs = replay.inputs.iter().peekable();
+    while engine.tick < replay.total_ticks && !engine.is_game_over() {
+        let tick = engine.tick + 1;
+        let input = inputs
+            .next_if(|(input_tick, _)| *input_tick == tick)
+            .map_or_else(InputFrame::default, |(_, input)| *input);
+        engine.step(input);
+        for event in engine.drain_events() {
+            on_event(event);
+        }
+    }
+    engine
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn load_saved_game(mut saved_game: ResMut<SavedGameRes>) {
+    saved_game.replay = Replay::load(SAVE_PATH).ok();
+}
+
+#[cfg(target_arch = "wasm32")]
+fn load_saved_game() {}
+
+// saved on demand and when the window is closed
+fn save_game_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut close_events: EventReader<WindowCloseRequested>,
+    replay_recorder: Res<ReplayRecorderRes>,
+    mut saved_game: ResMut<SavedGameRes>,
+) {
+    let closed = close_events.read().count() > 0;
+    if !closed && !keyboard_input.just_pressed(SAVE_KEY) {
+        return;
+    }
+    // puzzles and pasted boards are not recorded, so they cannot be saved
+    let Some(replay) = &replay_recorder.0 else {
+        return;
+    };
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Err(err) = replay.save(SAVE_PATH) {
+        warn!("failed to save the game to {}: {}", SAVE_PATH, err);
+        return;
+    }
+    saved_game.replay = Some(replay.clone());
+}
+
+// a saved game which was continued until its end cannot be continued again
+fn discard_finished_game(last_replay: Res<LastReplayRes>, mut saved_game: ResMut<SavedGameRes>) {
+    let same_game = matches!(
+        (&saved_game.replay, &last_replay.0),
+        (Some(saved), Some(finished)) if is_continued_by(saved, finished)
+    );
+    if !same_game {
+        return;
+    }
+    saved_game.replay = None;
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Err(err) = std::fs::remove_file(SAVE_PATH) {
+        warn!("failed to remove the saved game {}: {}", SAVE_PATH, err);
+    }
+}
+
+// the finished game started as the saved one, e.g. not only with the same `--seed`
+fn is_continued_by(saved: &Replay, finished: &Replay) -> bool {
+    saved.difficulty == finished.difficulty
+        && saved.mode == finished.mode
+        && saved.seed == finished.seed
+        && saved.options == finished.options
+        && saved.total_ticks <= finished.total_ticks
+        && finished.inputs.starts_with(&saved.inputs)
+}
@@ -0,0 +1,313 @@
+use std::{io, path::Path};
+
+use bevy::prelude::*;
+
+use crate::{
+    common_entity::EntitySpawner,
+    locale::LocaleRes,
+    theme::{Accessibility, ColorVision, ThemeRes},
+    utils::{common_button_system, despawn_with_component},
+    GameState,
+};
+
+pub const SETTINGS_PATH: &str = "saves/settings.txt";
+
+/// The choices made on the settings screen, saved as `name = value` lines.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct SettingsRes {
+    // the name of the theme
+    pub theme: String,
+    // the code of the language, e.g. `zh`
+    pub language: String,
+    pub accessibility: Accessibility,
+}
+
+impl SettingsRes {
+    /// Unknown lines are skipped, so older and newer versions can read the file.
+    pub fn parse(text: &str) -> Self {
+        let mut settings = SettingsRes::default();
+        for line in text.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+            let accessibility = &mut settings.accessibility;
+            match key.trim() {
+                "theme" => settings.theme = value.to_string(),
+                "language" => settings.language = value.to_string(),
+                "palette" => {
+                    if let Some(color_vision) = ColorVision::from_name(value) {
+                        accessibility.color_vision = color_vision;
+                    }
+                }
+                "patterns" => accessibility.block_patterns = value == "true",
+                "high_contrast_ghost" => accessibility.high_contrast_ghost = value == "true",
+                _ => {}
+            }
+        }
+        settings
+    }
+
+    pub fn to_text(&self) -> String {
+        let accessibility = &self.accessibility;
+        format!(
+            "theme = {}\nlanguage = {}\npalette = {}\npatterns = {}\nhigh_contrast_ghost = {}\n",
+            self.theme,
+            self.language,
+            accessibility.color_vision.name(),
+            accessibility.block_patterns,
+            accessibility.high_contrast_ghost,
+        )
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(SettingsRes::parse(&std::fs::read_to_string(path)?))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, self.to_text())
+    }
+}
+
+#[derive(Component)]
+struct SettingsScreen;
+
+#[derive(Component)]
+enum SettingsButtonAction {
+    ChangeTheme,
+    ChangeLanguage,
+    ChangePalette,
+    TogglePatterns,
+    ToggleGhost,
+    Back,
+}
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        // loaded right away, the theme is chosen from it when the app is built
+        #[cfg(not(target_arch = "wasm32"))]
+        app.insert_resource(SettingsRes::load(SETTINGS_PATH).unwrap_or_default());
+        #[cfg(target_arch = "wasm32")]
+        app.init_resource::<SettingsRes>();
+
+        app.add_systems(OnEnter(GameState::Settings), settings_setup)
+            .add_systems(
+                OnExit(GameState::Settings),
+                despawn_with_component::<SettingsScreen>,
+            )
+            .add_systems(Update, (menu_action, common_button_system))
+            .add_systems(
+                Update,
+                refresh_settings_system.run_if(in_state(GameState::Settings)),
+            );
+    }
+}
+
+fn settings_setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    theme_res: Res<ThemeRes>,
+    locale: Res<LocaleRes>,
+) {
+    spawn_settings_screen(&mut commands, &asset_server, &theme_res, &locale);
+}
+
+fn spawn_settings_screen(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    theme_res: &ThemeRes,
+    locale: &LocaleRes,
+) {
+    let theme = theme_res.theme();
+    let accessibility = theme_res.accessibility();
+    let on_off = |on: bool| locale.text(if on { "settings-on" } else { "settings-off" });
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            SettingsScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        padding: UiRect::px(20., 20., 10., 10.),
+                        ..default()
+                    },
+                    background_color: theme.ui.panel.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(
+                        TextBundle::from_section(
+                            locale.text("settings-title"),
+                            TextStyle {
+                                font: theme_res.font(),
+                                font_size: 60.0,
+                                color: theme.ui.text,
+                            },
+                        )
+                        .with_style(Style {
+                            margin: UiRect::all(Val::Px(10.0)),
+                            ..default()
+                        }),
+                    );
+                    for (label, action, value) in [
+                        (
+                            "settings-theme",
+                            SettingsButtonAction::ChangeTheme,
+                            theme.name.as_str(),
+                        ),
+                        (
+                            "settings-language",
+                            SettingsButtonAction::ChangeLanguage,
+                            locale.name.as_str(),
+                        ),
+                        (
+                            "settings-palette",
+                            SettingsButtonAction::ChangePalette,
+                            locale.text(accessibility.color_vision.message_id()),
+                        ),
+                        (
+                            "settings-patterns",
+                            SettingsButtonAction::TogglePatterns,
+                            on_off(accessibility.block_patterns),
+                        ),
+                        (
+                            "settings-bright-ghost",
+                            SettingsButtonAction::ToggleGhost,
+                            on_off(accessibility.high_contrast_ghost),
+                        ),
+                    ] {
+                        let label = locale.text(label);
+                        spawn_setting_row(parent, label, action, value, asset_server, theme_res);
+                    }
+                    parent.spawn_button(
+                        SettingsButtonAction::Back,
+                        "exitRight.png",
+                        locale.text("menu-back"),
+                        asset_server,
+                        theme_res,
+                    );
+                });
+        });
+}
+
+fn menu_action(
+    interaction_query: Query<
+        (&Interaction, &SettingsButtonAction),
+        (Changed<Interaction>, With<Button>),
+    >,
+    asset_server: Res<AssetServer>,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut theme_res: ResMut<ThemeRes>,
+    mut locale: ResMut<LocaleRes>,
+    mut settings: ResMut<SettingsRes>,
+) {
+    for (interaction, action) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match action {
+            SettingsButtonAction::ChangeTheme => {
+                let next = theme_res.current + 1;
+                theme_res.select(next, &asset_server);
+                settings.theme = theme_res.theme().name.clone();
+            }
+            SettingsButtonAction::ChangeLanguage => {
+                let next = locale.current + 1;
+                locale.select(next);
+                theme_res.set_language_font(locale.installed_font(), &asset_server);
+                settings.language = locale.code.clone();
+            }
+            SettingsButtonAction::ChangePalette => {
+                settings.accessibility.color_vision = settings.accessibility.color_vision.next();
+            }
+            SettingsButtonAction::TogglePatterns => {
+                settings.accessibility.block_patterns ^= true;
+            }
+            SettingsButtonAction::ToggleGhost => {
+                settings.accessibility.high_contrast_ghost ^= true;
+            }
+            SettingsButtonAction::Back => {
+                game_state.set(GameState::Menu);
+            }
+        }
+    }
+}
+
+// the name of the setting, left of the button showing its value
+fn spawn_setting_row(
+    parent: &mut ChildBuilder,
+    label: &str,
+    action: SettingsButtonAction,
+    value: &str,
+    asset_server: &Res<AssetServer>,
+    theme_res: &ThemeRes,
+) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(
+                    label,
+                    TextStyle {
+                        font: theme_res.font(),
+                        font_size: 40.0,
+                        color: theme_res.theme().ui.text,
+                    },
+                )
+                .with_style(Style {
+                    width: Val::Px(220.0),
+                    ..default()
+                }),
+            );
+            parent.spawn_button(action, "wrench.png", value, asset_server, theme_res);
+        });
+}
+
+// drawn again with the new choices, so the theme is seen at once
+fn refresh_settings_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut theme_res: ResMut<ThemeRes>,
+    locale: Res<LocaleRes>,
+    settings: Res<SettingsRes>,
+    query: Query<Entity, With<SettingsScreen>>,
+) {
+    if !settings.is_changed() || settings.is_added() {
+        return;
+    }
+    if theme_res.accessibility() != settings.accessibility {
+        theme_res.set_accessibility(settings.accessibility);
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Err(err) = settings.save(SETTINGS_PATH) {
+        warn!("failed to save the settings to {}: {}", SETTINGS_PATH, err);
+    }
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+    spawn_settings_screen(&mut commands, &asset_server, &theme_res, &locale);
+}
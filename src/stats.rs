@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::{
+    brick::BrickType,
+    engine::{InputAction, InputFrame, LineClear, TICKS_PER_SECOND},
+    events::{FinesseFault, GameTicked, LinesCleared, PieceLocked},
+    game::{setup_tetris, GameEngineRes, GameSystemSet},
+    hud::HudConfigRes,
+    utils::get_attack,
+    GameState,
+};
+
+// the actions which count as a key press, releasing soft drop is not one
+const KEY_PRESS_ACTIONS: [InputAction; 5] = [
+    InputAction::MoveLeft,
+    InputAction::MoveRight,
+    InputAction::Rotate,
+    InputAction::HardDrop,
+    InputAction::SoftDropPressed,
+];
+
+/// Statistics of one game, shown on the game over screen.
+#[derive(Clone, Debug, Default)]
+pub struct GameStats {
+    pub ticks: u64,
+    pub pieces: usize,
+    pub key_presses: usize,
+    // indexed by the number of cleared lines - 1
+    pub clears: [usize; 4],
+    pub tspins: usize,
+    pub max_combo: usize,
+    pub perfect_clears: usize,
+    pub attack: usize,
+    pub piece_counts: HashMap<BrickType, usize>,
+    // only known when the finesse of every placement was checked
+    pub finesse_faults: Option<usize>,
+    // the targets hit and the bricks placed in the finesse trainer
+    pub targets: Option<(usize, usize)>,
+}
+
+impl GameStats {
+    pub fn record_tick(&mut self, input: InputFrame) {
+        self.ticks += 1;
+        self.key_presses += KEY_PRESS_ACTIONS
+            .iter()
+            .filter(|action| input.contains(**action))
+            .count();
+    }
+
+    pub fn record_piece(&mut self, brick_type: BrickType) {
+        self.pieces += 1;
+        *self.piece_counts.entry(brick_type).or_default() += 1;
+    }
+
+    pub fn record_clear(&mut self, clear: &LineClear) {
+        if clear.count > 0 {
+            self.clears[clear.count.min(4) - 1] += 1;
+        }
+        if clear.is_tspin() {
+            self.tspins += 1;
+        }
+        if clear.perfect_clear {
+            self.perfect_clears += 1;
+        }
+        self.max_combo = self.max_combo.max(clear.combo);
+        self.attack += get_attack(clear);
+    }
+
+    pub fn play_seconds(&self) -> f32 {
+        self.ticks as f32 / TICKS_PER_SECOND as f32
+    }
+
+    pub fn pieces_per_second(&self) -> f32 {
+        if self.ticks == 0 {
+            return 0.;
+        }
+        self.pieces as f32 / self.play_seconds()
+    }
+
+    pub fn attack_per_minute(&self) -> f32 {
+        if self.ticks == 0 {
+            return 0.;
+        }
+        self.attack as f32 * 60. / self.play_seconds()
+    }
+
+    pub fn keys_per_piece(&self) -> f32 {
+        if self.pieces == 0 {
+            return 0.;
+        }
+        self.key_presses as f32 / self.pieces as f32
+    }
+
+    /// The statistics as (label, value) rows, in display order; the labels are message ids.
+    pub fn summary(&self) -> Vec<(&'static str, String)> {
+        let secs = self.play_seconds() as u64;
+        let mut rows = vec![
+            ("stats-time", format!("{:02}:{:02}", secs / 60, secs % 60)),
+            ("stats-pieces", format!("{}", self.pieces)),
+            ("stats-pps", format!("{:.2}", self.pieces_per_second())),
+            ("stats-kpp", format!("{:.2}", self.keys_per_piece())),
+            ("stats-apm", format!("{:.1}", self.attack_per_minute())),
+            ("stats-singles", format!("{}", self.clears[0])),
+            ("stats-doubles", format!("{}", self.clears[1])),
+            ("stats-triples", format!("{}", self.clears[2])),
+            ("stats-tetrises", format!("{}", self.clears[3])),
+            ("stats-tspins", format!("{}", self.tspins)),
+            ("stats-max-combo", format!("{}", self.max_combo)),
+            ("stats-perfect-clears", format!("{}", self.perfect_clears)),
+        ];
+        if let Some(faults) = self.finesse_faults {
+            rows.push(("stats-finesse-faults", format!("{}", faults)));
+        }
+        if let Some((hit, placed)) = self.targets {
+            let accuracy = if placed == 0 {
+                0.
+            } else {
+                hit as f32 * 100. / placed as f32
+            };
+            rows.push((
+                "stats-targets-hit",
+                format!("{}/{} ({:.0}%)", hit, placed, accuracy),
+            ));
+        }
+        rows
+    }
+
+    /// The number of placed bricks of every type, as a single line like "I 3  O 2 ...".
+    pub fn piece_distribution(&self) -> String {
+        BrickType::ALL
+            .iter()
+            .map(|brick_type| {
+                let count = self.piece_counts.get(brick_type).copied().unwrap_or(0);
+                format!("{:?} {}", brick_type, count)
+            })
+            .collect::<Vec<_>>()
+            .join("   ")
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct GameStatsRes(pub GameStats);
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameStatsRes>()
+            .add_systems(OnEnter(GameState::Game), reset_stats.after(setup_tetris))
+            .add_systems(
+                Update,
+                record_stats_system
+                    .in_set(GameSystemSet::Events)
+                    .run_if(in_state(GameState::Game)),
+            );
+    }
+}
+
+fn reset_stats(
+    mut game_stats: ResMut<GameStatsRes>,
+    hud_config: Res<HudConfigRes>,
+    engine: Res<GameEngineRes>,
+) {
+    // the faults are only counted when the player follows them, in the HUD or the trainer
+    let tracks_finesse = hud_config.show_finesse || engine.0.is_finesse_trainer();
+    game_stats.0 = GameStats {
+        finesse_faults: tracks_finesse.then_some(0),
+        ..default()
+    };
+}
+
+fn record_stats_system(
+    mut ticked_events: EventReader<GameTicked>,
+    mut locked_events: EventReader<PieceLocked>,
+    mut cleared_events: EventReader<LinesCleared>,
+    mut fault_events: EventReader<FinesseFault>,
+    mut game_stats: ResMut<GameStatsRes>,
+) {
+    for event in ticked_events.read() {
+        game_stats.0.record_tick(event.input);
+    }
+    for event in locked_events.read() {
+        game_stats.0.record_piece(event.0 .0);
+    }
+    for _ in fault_events.read() {
+        if let Some(faults) = &mut game_stats.0.finesse_faults {
+            *faults += 1;
+        }
+    }
+    for event in cleared_events.read() {
+        game_stats.0.record_clear(&event.0);
+    }
+}
@@ -0,0 +1,569 @@
+use std::{collections::HashMap, f32::consts::FRAC_PI_4, io, path::Path};
+
+use bevy::{asset::LoadState, prelude::*};
+
+use crate::{
+    brick::BrickType,
+    constants::{
+        BACKGROUND, BACKGROUND_COLOR, GAME_DATA_TEXT_COLOR, HOVERED_BUTTON, HOVERED_PRESSED_BUTTON,
+        NORMAL_BUTTON, PRESSED_BUTTON, TEXT_COLOR,
+    },
+    game::BRICK_COLOR_MAP,
+    locale::LocaleRes,
+    replay::invalid_data,
+    settings::SettingsRes,
+};
+
+pub const THEME_DIR: &str = "assets/themes";
+const DEFAULT_FONT: &str = "fonts/FiraSans-Bold.ttf";
+
+/// The colours of the window and the menus.
+#[derive(Clone, Copy, Debug)]
+pub struct UiPalette {
+    // the window, around the board and behind the menus
+    pub background: Color,
+    pub panel: Color,
+    pub text: Color,
+    // the score and the HUD, drawn on the background
+    pub game_text: Color,
+    pub button: Color,
+    pub button_hovered: Color,
+    pub button_pressed: Color,
+    pub button_hovered_pressed: Color,
+}
+
+/// How the blocks, the board and the menus look.
+///
+/// Theme packs are text files of `name = value` lines in `THEME_DIR`, any value left out is
+/// taken from the classic theme. Colours are hex strings, e.g. `T = #9A00CD`, and `block`
+/// or `T block` give the texture of the blocks, tinted with their colour.
+#[derive(Clone, Debug)]
+pub struct Theme {
+    pub name: String,
+    pub brick_colors: HashMap<BrickType, Color>,
+    // the asset paths of the textures, the blocks without one are plain squares
+    pub block_textures: HashMap<BrickType, String>,
+    pub ghost: Color,
+    // the lines between the blocks of the board
+    pub grid: Color,
+    pub font: String,
+    pub ui: UiPalette,
+}
+
+impl Theme {
+    pub fn classic() -> Self {
+        Theme {
+            name: "Classic".to_string(),
+            brick_colors: BRICK_COLOR_MAP
+                .iter()
+                .map(|(brick_type, hex)| (*brick_type, Color::hex(hex).unwrap()))
+                .collect(),
+            block_textures: HashMap::new(),
+            ghost: Color::rgb_u8(90, 90, 90),
+            grid: Color::rgb_u8(32, 31, 30),
+            font: DEFAULT_FONT.to_string(),
+            ui: UiPalette {
+                background: BACKGROUND_COLOR,
+                panel: BACKGROUND,
+                text: TEXT_COLOR,
+                game_text: GAME_DATA_TEXT_COLOR,
+                button: NORMAL_BUTTON,
+                button_hovered: HOVERED_BUTTON,
+                button_pressed: PRESSED_BUTTON,
+                button_hovered_pressed: HOVERED_PRESSED_BUTTON,
+            },
+        }
+    }
+
+    /// The colours of the first levels of the NES game, on a black screen.
+    pub fn nes() -> Self {
+        Theme::classic()
+            .with_name("NES")
+            .with_colors(&[
+                (BrickType::O, "#FCFCFC"),
+                (BrickType::I, "#FCFCFC"),
+                (BrickType::T, "#FCFCFC"),
+                (BrickType::J, "#0058F8"),
+                (BrickType::S, "#0058F8"),
+                (BrickType::L, "#3CBCFC"),
+                (BrickType::Z, "#3CBCFC"),
+                (BrickType::Garbage, "#7C7C7C"),
+                (BrickType::None, "#000000"),
+            ])
+            .with_ghost(Color::rgb_u8(60, 60, 60))
+            .with_grid(Color::rgb_u8(0, 0, 0))
+            .with_ui(UiPalette {
+                background: Color::rgb_u8(0, 0, 0),
+                panel: Color::rgb_u8(116, 116, 116),
+                text: Color::rgb_u8(252, 252, 252),
+                game_text: Color::rgb_u8(252, 252, 252),
+                button: Color::rgb_u8(0, 0, 0),
+                button_hovered: Color::rgb_u8(60, 60, 60),
+                button_pressed: Color::rgb_u8(0, 88, 248),
+                button_hovered_pressed: Color::rgb_u8(60, 188, 252),
+            })
+    }
+
+    /// Saturated blocks and white text on black.
+    pub fn high_contrast() -> Self {
+        Theme::classic()
+            .with_name("High Contrast")
+            .with_colors(&[
+                (BrickType::O, "#FFFF00"),
+                (BrickType::I, "#00FFFF"),
+                (BrickType::J, "#3C64FF"),
+                (BrickType::L, "#FF8C00"),
+                (BrickType::S, "#00FF00"),
+                (BrickType::Z, "#FF0000"),
+                (BrickType::T, "#FF00FF"),
+                (BrickType::Garbage, "#C0C0C0"),
+                (BrickType::None, "#000000"),
+            ])
+            .with_ghost(Color::rgb_u8(200, 200, 200))
+            .with_grid(Color::rgb_u8(80, 80, 80))
+            .with_ui(UiPalette {
+                background: Color::rgb_u8(0, 0, 0),
+                panel: Color::rgb_u8(0, 0, 0),
+                text: Color::rgb_u8(255, 255, 255),
+                game_text: Color::rgb_u8(255, 255, 0),
+                button: Color::rgb_u8(48, 48, 48),
+                button_hovered: Color::rgb_u8(96, 96, 96),
+                button_pressed: Color::rgb_u8(0, 96, 192),
+                button_hovered_pressed: Color::rgb_u8(32, 128, 224),
+            })
+    }
+
+    pub fn builtin() -> Vec<Theme> {
+        vec![Theme::classic(), Theme::nes(), Theme::high_contrast()]
+    }
+
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let mut theme = Theme::classic();
+        let mut named = false;
+        for line in text.lines() {
+            // the colours start with # too, so only whole lines are comments
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| invalid_data("expected a `name = value` line"))?;
+            let (key, value) = (key.trim(), value.trim());
+            let ui = &mut theme.ui;
+            match key.split_whitespace().collect::<Vec<_>>().as_slice() {
+                ["name"] => {
+                    theme.name = value.to_string();
+                    named = true;
+                }
+                ["font"] => theme.font = value.to_string(),
+                ["ghost"] => theme.ghost = parse_color(value)?,
+                ["grid"] => theme.grid = parse_color(value)?,
+                ["background"] => ui.background = parse_color(value)?,
+                ["panel"] => ui.panel = parse_color(value)?,
+                ["text"] => ui.text = parse_color(value)?,
+                ["game_text"] => ui.game_text = parse_color(value)?,
+                ["button"] => ui.button = parse_color(value)?,
+                ["button_hovered"] => ui.button_hovered = parse_color(value)?,
+                ["button_pressed"] => ui.button_pressed = parse_color(value)?,
+                ["button_hovered_pressed"] => ui.button_hovered_pressed = parse_color(value)?,
+                ["block"] => {
+                    for brick_type in BrickType::ALL.into_iter().chain([BrickType::Garbage]) {
+                        theme.block_textures.insert(brick_type, value.to_string());
+                    }
+                }
+                [brick, "block"] => {
+                    theme
+                        .block_textures
+                        .insert(parse_brick_type(brick)?, value.to_string());
+                }
+                [brick] => {
+                    theme
+                        .brick_colors
+                        .insert(parse_brick_type(brick)?, parse_color(value)?);
+                }
+                _ => return Err(invalid_data("unknown theme key")),
+            }
+        }
+        if !named {
+            return Err(invalid_data("a theme needs a name"));
+        }
+        Ok(theme)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Theme::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn brick_color(&self, brick_type: BrickType) -> Color {
+        self.brick_colors[&brick_type]
+    }
+
+    fn with_name(self, name: &str) -> Self {
+        Theme {
+            name: name.to_string(),
+            ..self
+        }
+    }
+
+    fn with_colors(mut self, colors: &[(BrickType, &str)]) -> Self {
+        for (brick_type, hex) in colors {
+            self.brick_colors
+                .insert(*brick_type, Color::hex(hex).unwrap());
+        }
+        self
+    }
+
+    fn with_ghost(self, ghost: Color) -> Self {
+        Theme { ghost, ..self }
+    }
+
+    fn with_grid(self, grid: Color) -> Self {
+        Theme { grid, ..self }
+    }
+
+    fn with_ui(self, ui: UiPalette) -> Self {
+        Theme { ui, ..self }
+    }
+}
+
+fn parse_color(value: &str) -> io::Result<Color> {
+    Color::hex(value).map_err(|_| invalid_data("invalid colour"))
+}
+
+// `garbage` and `board`, the empty cells, besides the bricks
+fn parse_brick_type(name: &str) -> io::Result<BrickType> {
+    match name {
+        "garbage" => Ok(BrickType::Garbage),
+        "board" => Ok(BrickType::None),
+        _ => {
+            let mut chars = name.chars();
+            match (chars.next().and_then(BrickType::from_char), chars.next()) {
+                (Some(brick_type), None) if BrickType::ALL.contains(&brick_type) => Ok(brick_type),
+                _ => Err(invalid_data("unknown brick type")),
+            }
+        }
+    }
+}
+
+/// Brick colours told apart with a colour vision deficiency, used instead of those of the theme.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorVision {
+    #[default]
+    Normal,
+    // red-green, without green cones
+    Deuteranopia,
+    // red-green, without red cones
+    Protanopia,
+    // blue-yellow
+    Tritanopia,
+}
+
+impl ColorVision {
+    pub const ALL: [ColorVision; 4] = [
+        ColorVision::Normal,
+        ColorVision::Deuteranopia,
+        ColorVision::Protanopia,
+        ColorVision::Tritanopia,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorVision::Normal => "Normal",
+            ColorVision::Deuteranopia => "Deuteranopia",
+            ColorVision::Protanopia => "Protanopia",
+            ColorVision::Tritanopia => "Tritanopia",
+        }
+    }
+
+    /// The id of its short name in the texts, which fits on a button.
+    pub fn message_id(self) -> &'static str {
+        match self {
+            ColorVision::Normal => "palette-normal",
+            ColorVision::Deuteranopia => "palette-deuteranopia",
+            ColorVision::Protanopia => "palette-protanopia",
+            ColorVision::Tritanopia => "palette-tritanopia",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        ColorVision::ALL
+            .into_iter()
+            .find(|vision| vision.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn next(self) -> Self {
+        let index = ColorVision::ALL.iter().position(|vision| *vision == self);
+        ColorVision::ALL[(index.unwrap_or(0) + 1) % ColorVision::ALL.len()]
+    }
+
+    // the pieces differ in lightness too, the garbage and the board keep the colours of the theme
+    fn brick_colors(self) -> &'static [(BrickType, &'static str)] {
+        match self {
+            ColorVision::Normal => &[],
+            // the Okabe-Ito colours
+            ColorVision::Deuteranopia => &[
+                (BrickType::O, "#F0E442"),
+                (BrickType::I, "#56B4E9"),
+                (BrickType::J, "#0072B2"),
+                (BrickType::L, "#E69F00"),
+                (BrickType::S, "#009E73"),
+                (BrickType::Z, "#D55E00"),
+                (BrickType::T, "#CC79A7"),
+            ],
+            // red looks dark, so the red of Z is a dark brown next to the orange of L
+            ColorVision::Protanopia => &[
+                (BrickType::O, "#F0E442"),
+                (BrickType::I, "#56B4E9"),
+                (BrickType::J, "#0072B2"),
+                (BrickType::L, "#E69F00"),
+                (BrickType::S, "#B8E0D2"),
+                (BrickType::Z, "#8C510A"),
+                (BrickType::T, "#B4A0FF"),
+            ],
+            // built on red and teal, which stay apart without blue cones
+            ColorVision::Tritanopia => &[
+                (BrickType::O, "#F0F0F0"),
+                (BrickType::I, "#4FC3C8"),
+                (BrickType::J, "#2A4B5C"),
+                (BrickType::L, "#A0522D"),
+                (BrickType::S, "#006D6F"),
+                (BrickType::Z, "#D7191C"),
+                (BrickType::T, "#FFB6C1"),
+            ],
+        }
+    }
+}
+
+/// The accessibility options of the settings, drawn over any theme.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Accessibility {
+    pub color_vision: ColorVision,
+    // a pattern on every block, so the bricks are told apart without their colours
+    pub block_patterns: bool,
+    // the ghost drawn as a bright outline instead of a dim block
+    pub high_contrast_ghost: bool,
+}
+
+/// A bar of a block pattern, in units of the block width from its center.
+#[derive(Clone, Copy, Debug)]
+pub struct PatternBar {
+    pub center: Vec2,
+    pub size: Vec2,
+    // counterclockwise, in radians
+    pub angle: f32,
+}
+
+const PATTERN_WIDTH: f32 = 0.14;
+
+const fn bar(x: f32, y: f32, width: f32, height: f32, angle: f32) -> PatternBar {
+    PatternBar {
+        center: Vec2::new(x, y),
+        size: Vec2::new(width, height),
+        angle,
+    }
+}
+
+/// The pattern of the blocks of a brick type, shaped after its letter.
+pub fn block_pattern(brick_type: BrickType) -> &'static [PatternBar] {
+    const W: f32 = PATTERN_WIDTH;
+    const O: [PatternBar; 4] = [
+        bar(0., 0.25, 0.64, W, 0.),
+        bar(0., -0.25, 0.64, W, 0.),
+        bar(-0.25, 0., W, 0.64, 0.),
+        bar(0.25, 0., W, 0.64, 0.),
+    ];
+    const I: [PatternBar; 1] = [bar(0., 0., W, 0.64, 0.)];
+    const J: [PatternBar; 2] = [bar(0.25, 0., W, 0.64, 0.), bar(-0.04, -0.25, 0.58, W, 0.)];
+    const L: [PatternBar; 2] = [bar(-0.25, 0., W, 0.64, 0.), bar(0.04, -0.25, 0.58, W, 0.)];
+    const S: [PatternBar; 1] = [bar(0., 0., W, 0.8, -FRAC_PI_4)];
+    const Z: [PatternBar; 1] = [bar(0., 0., W, 0.8, FRAC_PI_4)];
+    const T: [PatternBar; 2] = [bar(0., 0.25, 0.64, W, 0.), bar(0., -0.04, W, 0.56, 0.)];
+    const GARBAGE: [PatternBar; 2] = [
+        bar(0., 0., W, 0.7, FRAC_PI_4),
+        bar(0., 0., W, 0.7, -FRAC_PI_4),
+    ];
+    match brick_type {
+        BrickType::O => &O,
+        BrickType::I => &I,
+        BrickType::J => &J,
+        BrickType::L => &L,
+        BrickType::S => &S,
+        BrickType::Z => &Z,
+        BrickType::T => &T,
+        BrickType::Garbage => &GARBAGE,
+        BrickType::None => &[],
+    }
+}
+
+/// Black on the light blocks and white on the dark ones.
+pub fn pattern_color(block_color: Color) -> Color {
+    let [r, g, b, _] = block_color.as_rgba_f32();
+    if 0.299 * r + 0.587 * g + 0.114 * b > 0.5 {
+        Color::rgba(0., 0., 0., 0.6)
+    } else {
+        Color::rgba(1., 1., 1., 0.6)
+    }
+}
+
+/// The outline of the high contrast ghost.
+pub const HIGH_CONTRAST_GHOST: Color = Color::WHITE;
+
+/// The built-in themes, followed by the packs found in `THEME_DIR`.
+pub fn load_themes() -> Vec<Theme> {
+    #[allow(unused_mut)]
+    let mut themes = Theme::builtin();
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Ok(entries) = std::fs::read_dir(THEME_DIR) {
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+            .collect();
+        paths.sort();
+        for path in paths {
+            match Theme::load(&path) {
+                Ok(theme) => themes.push(theme),
+                Err(err) => eprintln!("failed to load theme {}: {}", path.display(), err),
+            }
+        }
+    }
+    themes
+}
+
+/// The themes to choose from and the one in use, with its loaded assets.
+#[derive(Resource)]
+pub struct ThemeRes {
+    pub themes: Vec<Theme>,
+    pub current: usize,
+    accessibility: Accessibility,
+    // the current theme with the palette of the accessibility options
+    applied: Theme,
+    font: Handle<Font>,
+    // the font of the language, used instead of the one of the theme
+    language_font: Option<Handle<Font>>,
+    block_textures: HashMap<BrickType, Handle<Image>>,
+}
+
+impl ThemeRes {
+    pub fn theme(&self) -> &Theme {
+        &self.applied
+    }
+
+    pub fn accessibility(&self) -> Accessibility {
+        self.accessibility
+    }
+
+    /// The pattern to draw on the blocks of a brick type, none while the patterns are off.
+    pub fn block_pattern(&self, brick_type: BrickType) -> &'static [PatternBar] {
+        if self.accessibility.block_patterns {
+            block_pattern(brick_type)
+        } else {
+            &[]
+        }
+    }
+
+    pub fn set_accessibility(&mut self, accessibility: Accessibility) {
+        self.accessibility = accessibility;
+        self.apply();
+    }
+
+    pub fn font(&self) -> Handle<Font> {
+        self.language_font
+            .clone()
+            .unwrap_or_else(|| self.font.clone())
+    }
+
+    pub fn set_language_font(&mut self, font: Option<&str>, asset_server: &AssetServer) {
+        self.language_font = font.map(|font| asset_server.load(font.to_string()));
+    }
+
+    pub fn block_texture(&self, brick_type: BrickType) -> Option<Handle<Image>> {
+        self.block_textures.get(&brick_type).cloned()
+    }
+
+    /// Uses the theme at `index`, loading its font and textures.
+    pub fn select(&mut self, index: usize, asset_server: &AssetServer) {
+        self.current = index % self.themes.len();
+        self.apply();
+        let theme = &self.themes[self.current];
+        self.font = asset_server.load(&theme.font);
+        self.block_textures = theme
+            .block_textures
+            .iter()
+            .map(|(brick_type, path)| (*brick_type, asset_server.load(path)))
+            .collect();
+    }
+
+    fn apply(&mut self) {
+        let palette = self.accessibility.color_vision.brick_colors();
+        self.applied = self.themes[self.current].clone().with_colors(palette);
+    }
+}
+
+impl FromWorld for ThemeRes {
+    fn from_world(world: &mut World) -> Self {
+        let mut theme_res = ThemeRes {
+            themes: load_themes(),
+            current: 0,
+            accessibility: Accessibility::default(),
+            applied: Theme::classic(),
+            font: Handle::default(),
+            language_font: None,
+            block_textures: HashMap::new(),
+        };
+        let settings = world
+            .get_resource::<SettingsRes>()
+            .cloned()
+            .unwrap_or_default();
+        let saved = theme_res
+            .themes
+            .iter()
+            .position(|theme| theme.name == settings.theme);
+        theme_res.accessibility = settings.accessibility;
+        let asset_server = world.resource::<AssetServer>();
+        theme_res.select(saved.unwrap_or(0), asset_server);
+        if let Some(locale) = world.get_resource::<LocaleRes>() {
+            theme_res.set_language_font(locale.installed_font(), asset_server);
+        }
+        theme_res
+    }
+}
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ThemeRes>().add_systems(
+            Update,
+            (apply_background_system, language_font_fallback_system),
+        );
+    }
+}
+
+fn apply_background_system(theme_res: Res<ThemeRes>, mut clear_color: ResMut<ClearColor>) {
+    if theme_res.is_changed() {
+        clear_color.0 = theme_res.theme().ui.background;
+    }
+}
+
+// the web build cannot check the font beforehand, a language whose font fails to load is dropped
+fn language_font_fallback_system(
+    asset_server: Res<AssetServer>,
+    mut theme_res: ResMut<ThemeRes>,
+    mut locale: ResMut<LocaleRes>,
+) {
+    let Some(font) = &theme_res.language_font else {
+        return;
+    };
+    if asset_server.load_state(font.id()) != LoadState::Failed {
+        return;
+    }
+    warn!(
+        "the font of the {} texts failed to load, the language is not offered",
+        locale.code
+    );
+    let code = locale.code.clone();
+    locale.locales.retain(|locale| locale.code != code);
+    locale.select(0);
+    theme_res.language_font = None;
+}
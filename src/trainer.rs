@@ -0,0 +1,89 @@
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+
+use crate::{
+    events::PieceLocked,
+    finesse::placement_cells,
+    game::{spawn_cells, BoardLayoutRes, GameEngineRes, GameSystemSet},
+    position::Position,
+    stats::GameStatsRes,
+    utils::despawn_with_component,
+    GameState,
+};
+
+// the placement to reach, translucent over the board
+const TARGET_COLOR: Color = Color::rgba(0.2, 0.9, 0.3, 0.45);
+
+/// The target of the falling brick in the finesse trainer.
+#[derive(Resource, Default)]
+struct TrainerRes {
+    target: Option<[Position; 4]>,
+    // the brick the target was chosen for
+    target_piece: usize,
+}
+
+#[derive(Component)]
+struct TargetView;
+
+pub struct TrainerPlugin;
+
+impl Plugin for TrainerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TrainerRes>()
+            .add_systems(OnEnter(GameState::Game), reset_trainer)
+            .add_systems(
+                OnExit(GameState::Game),
+                despawn_with_component::<TargetView>,
+            )
+            .add_systems(
+                Update,
+                trainer_system
+                    .in_set(GameSystemSet::Events)
+                    .run_if(in_state(GameState::Game).and_then(is_finesse_trainer)),
+            );
+    }
+}
+
+fn is_finesse_trainer(engine: Res<GameEngineRes>) -> bool {
+    engine.0.is_finesse_trainer()
+}
+
+fn reset_trainer(mut trainer: ResMut<TrainerRes>) {
+    *trainer = TrainerRes::default();
+}
+
+fn trainer_system(
+    mut commands: Commands,
+    mut locked_events: EventReader<PieceLocked>,
+    engine: Res<GameEngineRes>,
+    layout: Res<BoardLayoutRes>,
+    mut trainer: ResMut<TrainerRes>,
+    mut game_stats: ResMut<GameStatsRes>,
+    query: Query<Entity, With<TargetView>>,
+) {
+    let (mut hit, mut placed) = game_stats.0.targets.unwrap_or_default();
+    for event in locked_events.read() {
+        placed += 1;
+        if trainer.target == Some(placement_cells(&event.0, event.1)) {
+            hit += 1;
+        }
+    }
+    game_stats.0.targets = Some((hit, placed));
+
+    let engine = &engine.0;
+    if engine.active.is_none() || trainer.target_piece == engine.pieces {
+        return;
+    }
+    // any placement the brick can reach by shifting and rotating
+    trainer.target_piece = engine.pieces;
+    trainer.target = engine
+        .finesse_paths()
+        .choose(&mut rand::thread_rng())
+        .map(|path| path.cells);
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+    if let Some(cells) = trainer.target {
+        spawn_cells(&mut commands, &cells, TARGET_COLOR, &layout, TargetView);
+    }
+}
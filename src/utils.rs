@@ -2,7 +2,13 @@
 
 use bevy::prelude::*;
 
-use crate::constants::{PRESSED_BUTTON, HOVERED_PRESSED_BUTTON, HOVERED_BUTTON, NORMAL_BUTTON};
+use crate::{
+    engine::{LineClear, TICKS_PER_SECOND},
+    theme::ThemeRes,
+};
+
+// 20G, the brick falls through the whole board in a single frame
+pub const MAX_GRAVITY: f32 = 20.;
 
 // Generic system that takes a component as a parameter, and will despawn all entities with that component
 pub fn despawn_with_component<T: Component>(
@@ -33,13 +39,15 @@ pub fn common_button_system(
         (&Interaction, &mut BackgroundColor, Option<&SelectedOption>),
         (Changed<Interaction>, With<Button>),
     >,
+    theme_res: Res<ThemeRes>,
 ) {
+    let ui = theme_res.theme().ui;
     for (interaction, mut color, selected) in &mut interaction_query {
         *color = match (*interaction, selected) {
-            (Interaction::Pressed, _) | (Interaction::None, Some(_)) => PRESSED_BUTTON.into(),
-            (Interaction::Hovered, Some(_)) => HOVERED_PRESSED_BUTTON.into(),
-            (Interaction::Hovered, None) => HOVERED_BUTTON.into(),
-            (Interaction::None, None) => NORMAL_BUTTON.into(),
+            (Interaction::Pressed, _) | (Interaction::None, Some(_)) => ui.button_pressed.into(),
+            (Interaction::Hovered, Some(_)) => ui.button_hovered_pressed.into(),
+            (Interaction::Hovered, None) => ui.button_hovered.into(),
+            (Interaction::None, None) => ui.button.into(),
         }
     }
 }
@@ -51,7 +59,62 @@ pub fn common_button_system(
 pub fn get_score(level: usize, erase_lines: usize) -> usize {
     assert!(0 < erase_lines);
     assert!(erase_lines <= 4);
-    vec![40, 100, 300, 1200][(erase_lines - 1) as usize] * level
+    vec![40, 100, 300, 1200][erase_lines - 1] * level
+}
+
+///attack, the garbage lines sent by a line clear  
+///use as the guideline attack table
+///https://tetris.wiki/Garbage
+pub fn get_attack(clear: &LineClear) -> usize {
+    AttackTable::default().attack(clear)
+}
+
+/// The garbage lines sent by every kind of line clear.
+#[derive(Clone, Debug)]
+pub struct AttackTable {
+    // indexed by the number of cleared lines - 1
+    pub lines: [usize; 4],
+    pub tspin_lines: [usize; 4],
+    pub back_to_back: usize,
+    // indexed by the combo, the last value is used for longer combos
+    pub combo: Vec<usize>,
+    pub perfect_clear: usize,
+}
+
+impl Default for AttackTable {
+    fn default() -> Self {
+        AttackTable {
+            lines: [0, 1, 2, 4],
+            tspin_lines: [2, 4, 6, 6],
+            back_to_back: 1,
+            combo: vec![0, 0, 1, 1, 1, 2, 2, 3, 3, 4, 4],
+            perfect_clear: 10,
+        }
+    }
+}
+
+impl AttackTable {
+    pub fn attack(&self, clear: &LineClear) -> usize {
+        if clear.count == 0 {
+            return 0;
+        }
+        let index = clear.count.min(4) - 1;
+        let mut attack = if clear.is_tspin() {
+            self.tspin_lines[index]
+        } else {
+            self.lines[index]
+        };
+        if clear.back_to_back {
+            attack += self.back_to_back;
+        }
+        if let Some(last) = self.combo.last() {
+            attack += self.combo.get(clear.combo).unwrap_or(last);
+        }
+        if clear.perfect_clear {
+            attack += self.perfect_clear;
+        }
+        attack
+    }
 }
 
 ///level  
@@ -61,11 +124,12 @@ pub fn get_level(total_lines: usize) -> usize {
     (total_lines / 10 + 1).min(99)
 }
 
-///tetris speeding  
-///delay = 725 * .85 ^ level + level (ms)
-///use formula from dwhacks, http://gist.github.com/dwhacks/8644250
+///tetris gravity, in G (rows per frame, one frame is one tick)  
+///seconds per row = (0.8 - (level - 1) * 0.007) ^ (level - 1)
+///use formula from the tetris guideline, https://tetris.wiki/Marathon
 #[inline]
-pub fn get_speed(level: usize, default_speed: f32) -> f32 {
-    default_speed * (0.85_f32).powi(level as i32) + level as f32 / 1000.0
-    // default_speed
+pub fn get_gravity(level: usize) -> f32 {
+    let level = level.max(1) as f32 - 1.;
+    let seconds_per_row = (0.8 - level * 0.007).powf(level);
+    (1. / (seconds_per_row * TICKS_PER_SECOND as f32)).min(MAX_GRAVITY)
 }
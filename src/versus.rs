@@ -0,0 +1,578 @@
+use bevy::{prelude::*, sprite::Anchor};
+
+use crate::{
+    ai::{AiController, EASY_CPU_ACTION_TICKS, HARD_CPU_ACTION_TICKS, NORMAL_CPU_ACTION_TICKS},
+    engine::{EngineEvent, GameEngine, InputFrame, TICK_SECONDS},
+    game::{
+        apply_level_settings, spawn_engine_view, BoardLayoutRes, EngineViewFilter, GameSystemSet,
+        MAX_TICKS_PER_FRAME,
+    },
+    garbage::CLEAN_HOLE_CHANGE_RATE,
+    input::{read_gamepad, KeyMap},
+    layout::ScreenLayoutRes,
+    locale::{Locale, LocaleRes},
+    menu::{GameLevelRes, GameSelectedLevel},
+    mode::GameMode,
+    theme::ThemeRes,
+    utils::{despawn_with_component, AttackTable},
+    GameState,
+};
+
+pub(crate) const PLAYER_COUNT: usize = 2;
+const VERSUS_MODE: GameMode = GameMode::Marathon;
+// the boards are smaller than in a single game to fit side by side
+const VERSUS_BOARD_VIEW_WIDTH: f32 = 280.;
+const VERSUS_BOARD_VIEW_HEIGHT: f32 = 600.;
+const PLAYER_ORIGIN_X: [f32; PLAYER_COUNT] = [-285., 235.];
+// in a portrait window, the room for the title and the controls of a board above the other
+const PORTRAIT_PLAYER_MARGIN: f32 = 120.;
+const PORTRAIT_PLAYER_ORIGIN_X: f32 = -100.;
+const KEY_MAPS: [KeyMap; PLAYER_COUNT] = [KeyMap::WASD, KeyMap::ARROWS];
+const CONTROLS_HINTS: [&str; PLAYER_COUNT] = ["versus-hints-wasd", "versus-hints-arrows"];
+// against the CPU, the player keeps the single player keys
+const CPU_PLAYER: usize = 1;
+const CPU_CONTROLS_HINTS: [&str; PLAYER_COUNT] = ["versus-hints-single", ""];
+const GARBAGE_METER_WIDTH: f32 = 8.;
+const GARBAGE_METER_COLOR: Color = Color::rgb(0.9, 0.2, 0.2);
+
+/// The rules of a versus match.
+#[derive(Resource, Clone)]
+pub struct VersusConfigRes {
+    pub attack_table: AttackTable,
+    // see `GarbageGenerator`
+    pub hole_change_rate: f64,
+    // the right board is played by the AI
+    pub cpu_opponent: bool,
+}
+
+impl Default for VersusConfigRes {
+    fn default() -> Self {
+        VersusConfigRes {
+            attack_table: AttackTable::default(),
+            hole_change_rate: CLEAN_HOLE_CHANGE_RATE,
+            cpu_opponent: false,
+        }
+    }
+}
+
+/// A board of a versus match, also used for the local board of online matches.
+pub(crate) struct VersusPlayer {
+    pub(crate) engine: GameEngine,
+    pub(crate) layout: BoardLayoutRes,
+    pub(crate) pending_input: InputFrame,
+    // plays instead of the keyboard
+    pub(crate) ai: Option<AiController>,
+    // the garbage lines received and not raised yet
+    pub(crate) garbage_queued: usize,
+    pub(crate) lines_sent: usize,
+}
+
+impl VersusPlayer {
+    pub(crate) fn new(
+        mut engine: GameEngine,
+        layout: BoardLayoutRes,
+        config: &VersusConfigRes,
+    ) -> Self {
+        engine.set_garbage_hole_change_rate(config.hole_change_rate);
+        VersusPlayer {
+            engine,
+            layout,
+            pending_input: InputFrame::default(),
+            ai: None,
+            garbage_queued: 0,
+            lines_sent: 0,
+        }
+    }
+
+    /// Plays one tick and returns the garbage lines sent to the opponent.
+    pub(crate) fn step(&mut self, attack_table: &AttackTable) -> usize {
+        let input = std::mem::take(&mut self.pending_input);
+        let input = match self.ai.as_mut() {
+            Some(ai) => ai.next_input(&self.engine),
+            None => input,
+        };
+        self.engine.step(input);
+
+        let mut attack = 0;
+        let mut locked = false;
+        let mut cleared = false;
+        for event in self.engine.drain_events() {
+            match event {
+                EngineEvent::PieceLocked(..) => locked = true,
+                EngineEvent::LinesCleared(clear) => {
+                    cleared |= clear.count > 0;
+                    attack += attack_table.attack(&clear);
+                }
+                _ => (),
+            }
+        }
+
+        // the attack cancels the queued garbage first
+        let cancelled = attack.min(self.garbage_queued);
+        self.garbage_queued -= cancelled;
+        attack -= cancelled;
+        // the queued garbage rises when a brick locks without clearing lines
+        if locked && !cleared {
+            let count = std::mem::take(&mut self.garbage_queued);
+            self.engine.receive_garbage(count);
+        }
+        self.lines_sent += attack;
+        attack
+    }
+}
+
+#[derive(Clone, Copy)]
+enum VersusResult {
+    Winner(usize),
+    Draw,
+}
+
+#[derive(Resource)]
+struct VersusRes {
+    players: Vec<VersusPlayer>,
+    // the orientation the boards are laid out for
+    screen: ScreenLayoutRes,
+    accumulator: f32,
+    result: Option<VersusResult>,
+}
+
+impl VersusRes {
+    fn new(level: GameSelectedLevel, config: &VersusConfigRes, screen: ScreenLayoutRes) -> Self {
+        // both players get the same bricks and garbage holes, each from its own randomizer
+        let seed: u64 = rand::random();
+        let players = (0..PLAYER_COUNT)
+            .map(|i| {
+                let engine = GameEngine::new(level, VERSUS_MODE, seed);
+                let mut player = VersusPlayer::new(engine, player_layout(i, screen), config);
+                if config.cpu_opponent && i == CPU_PLAYER {
+                    player.ai = Some(AiController::new(cpu_action_ticks(level)));
+                }
+                player
+            })
+            .collect();
+        VersusRes {
+            players,
+            screen,
+            accumulator: 0.,
+            result: None,
+        }
+    }
+
+    /// Plays one tick on both boards, then sends the attacks to the opponents.
+    fn step(&mut self, config: &VersusConfigRes) {
+        let attacks: Vec<usize> = self
+            .players
+            .iter_mut()
+            .map(|player| player.step(&config.attack_table))
+            .collect();
+        for (i, attack) in attacks.into_iter().enumerate() {
+            self.players[(i + 1) % PLAYER_COUNT].garbage_queued += attack;
+        }
+
+        let losers: Vec<usize> = (0..PLAYER_COUNT)
+            .filter(|i| self.players[*i].engine.is_game_over())
+            .collect();
+        self.result = match losers.as_slice() {
+            [] => None,
+            [loser] => Some(VersusResult::Winner((loser + 1) % PLAYER_COUNT)),
+            _ => Some(VersusResult::Draw),
+        };
+    }
+}
+
+// the CPU gets faster with the difficulty
+fn cpu_action_ticks(level: GameSelectedLevel) -> u32 {
+    match level {
+        GameSelectedLevel::Easy => EASY_CPU_ACTION_TICKS,
+        GameSelectedLevel::Normal => NORMAL_CPU_ACTION_TICKS,
+        GameSelectedLevel::Hard => HARD_CPU_ACTION_TICKS,
+    }
+}
+
+fn player_name(i: usize, config: &VersusConfigRes, locale: &Locale) -> String {
+    if config.cpu_opponent {
+        locale.text(["versus-you", "versus-cpu"][i]).to_string()
+    } else {
+        locale.format("versus-player", &[("number", &(i + 1))])
+    }
+}
+
+/// Where the board of the i-th player is drawn, side by side in a landscape window
+/// and one above the other in a portrait one.
+pub(crate) fn player_layout(i: usize, screen: ScreenLayoutRes) -> BoardLayoutRes {
+    let (columns, rows) = VERSUS_MODE.board_size();
+    match screen {
+        ScreenLayoutRes::Landscape => BoardLayoutRes::fit(
+            columns,
+            rows,
+            VERSUS_BOARD_VIEW_WIDTH,
+            VERSUS_BOARD_VIEW_HEIGHT,
+        )
+        .with_origin_x(PLAYER_ORIGIN_X[i]),
+        ScreenLayoutRes::Portrait => {
+            let half_height = screen.size().y / 2.;
+            let layout = BoardLayoutRes::fit(
+                columns,
+                rows,
+                VERSUS_BOARD_VIEW_WIDTH,
+                half_height - PORTRAIT_PLAYER_MARGIN,
+            );
+            BoardLayoutRes {
+                origin_y: [1., -1.][i] * half_height / 2.,
+                ..layout.with_origin_x(PORTRAIT_PLAYER_ORIGIN_X)
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+pub(crate) struct VersusView;
+
+/// The height of the bar next to the board of the i-th player shows its queued garbage.
+#[derive(Component)]
+pub(crate) struct GarbageMeter(pub(crate) usize);
+
+#[derive(Component)]
+pub(crate) struct SentText(pub(crate) usize);
+
+#[derive(Component)]
+pub(crate) struct ResultBanner;
+
+#[derive(Component)]
+pub(crate) struct ResultText;
+
+pub struct VersusPlugin;
+
+impl Plugin for VersusPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<VersusConfigRes>()
+            .add_systems(OnEnter(GameState::Versus), setup_versus)
+            .add_systems(
+                OnExit(GameState::Versus),
+                (despawn_with_component::<VersusView>, despawn_engine_views),
+            )
+            .add_systems(
+                Update,
+                relayout_versus_system
+                    .in_set(GameSystemSet::Layout)
+                    .run_if(in_state(GameState::Versus)),
+            )
+            .add_systems(
+                Update,
+                versus_tick_system
+                    .in_set(GameSystemSet::Tick)
+                    .run_if(in_state(GameState::Versus)),
+            )
+            .add_systems(
+                Update,
+                (versus_render_system, versus_info_system)
+                    .in_set(GameSystemSet::Render)
+                    .run_if(in_state(GameState::Versus)),
+            )
+            .add_systems(
+                Update,
+                versus_control_system.run_if(in_state(GameState::Versus)),
+            );
+    }
+}
+
+fn setup_versus(
+    mut commands: Commands,
+    theme_res: Res<ThemeRes>,
+    locale: Res<LocaleRes>,
+    screen: Res<ScreenLayoutRes>,
+    game_level: Res<GameLevelRes>,
+    config: Res<VersusConfigRes>,
+) {
+    apply_level_settings(game_level.0);
+    spawn_local_versus_view(&mut commands, &theme_res, &locale, *screen, &config);
+    commands.insert_resource(VersusRes::new(game_level.0, &config, *screen));
+}
+
+fn spawn_local_versus_view(
+    commands: &mut Commands,
+    theme_res: &ThemeRes,
+    locale: &Locale,
+    screen: ScreenLayoutRes,
+    config: &VersusConfigRes,
+) {
+    let hints = if config.cpu_opponent {
+        CPU_CONTROLS_HINTS
+    } else {
+        CONTROLS_HINTS
+    };
+    spawn_versus_view(
+        commands,
+        theme_res,
+        locale,
+        screen,
+        [
+            &player_name(0, config, locale),
+            &player_name(1, config, locale),
+        ],
+        hints.map(|hint| locale.text(hint)),
+    );
+}
+
+// the boards keep playing, only their views are drawn again
+fn relayout_versus_system(
+    mut commands: Commands,
+    screen: Res<ScreenLayoutRes>,
+    theme_res: Res<ThemeRes>,
+    locale: Res<LocaleRes>,
+    config: Res<VersusConfigRes>,
+    mut versus: ResMut<VersusRes>,
+    query: Query<Entity, Or<(With<VersusView>, EngineViewFilter)>>,
+) {
+    if versus.screen == *screen {
+        return;
+    }
+    versus.screen = *screen;
+    for (i, player) in versus.players.iter_mut().enumerate() {
+        player.layout = player_layout(i, *screen);
+        player.engine.mark_dirty();
+    }
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+    spawn_local_versus_view(&mut commands, &theme_res, &locale, *screen, &config);
+}
+
+/// Spawns the titles, the garbage meters and the result banner around the two boards.
+pub(crate) fn spawn_versus_view(
+    commands: &mut Commands,
+    theme_res: &ThemeRes,
+    locale: &Locale,
+    screen: ScreenLayoutRes,
+    titles: [&str; PLAYER_COUNT],
+    hints: [&str; PLAYER_COUNT],
+) {
+    let text_style = |font_size| TextStyle {
+        font: theme_res.font(),
+        font_size,
+        color: theme_res.theme().ui.game_text,
+    };
+    for i in 0..PLAYER_COUNT {
+        let layout = &player_layout(i, screen);
+        let board_top = layout.board_top();
+        let board_bottom = board_top - layout.board_height();
+        let next_x = layout.board_right() + 100.;
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(titles[i], text_style(36.)),
+                transform: Transform::from_xyz(layout.origin_x, board_top + 30., 0.),
+                ..default()
+            },
+            VersusView,
+        ));
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(hints[i], text_style(18.))
+                    .with_alignment(TextAlignment::Center),
+                transform: Transform::from_xyz(layout.origin_x, board_bottom - 12., 0.),
+                text_anchor: Anchor::TopCenter,
+                ..default()
+            },
+            VersusView,
+        ));
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(locale.text("game-next"), text_style(28.)),
+                transform: Transform::from_xyz(next_x, layout.origin_y + 120., 0.),
+                ..default()
+            },
+            VersusView,
+        ));
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section("", text_style(28.)),
+                transform: Transform::from_xyz(next_x, layout.origin_y - 120., 0.),
+                ..default()
+            },
+            SentText(i),
+            VersusView,
+        ));
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: GARBAGE_METER_COLOR,
+                    custom_size: Some(Vec2::new(GARBAGE_METER_WIDTH, 0.)),
+                    anchor: Anchor::BottomCenter,
+                    ..default()
+                },
+                transform: Transform::from_xyz(
+                    layout.origin_x - layout.board_width() / 2. - GARBAGE_METER_WIDTH,
+                    board_bottom,
+                    0.,
+                ),
+                ..default()
+            },
+            GarbageMeter(i),
+            VersusView,
+        ));
+    }
+
+    commands
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgba(0., 0., 0., 0.8),
+                    custom_size: Some(Vec2::new(520., 180.)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(0., 0., 2.),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            ResultBanner,
+            VersusView,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text2dBundle {
+                    text: Text::from_section("", text_style(42.))
+                        .with_alignment(TextAlignment::Center),
+                    transform: Transform::from_xyz(0., 0., 0.1),
+                    ..default()
+                },
+                ResultText,
+            ));
+        });
+}
+
+pub(crate) fn despawn_engine_views(mut commands: Commands, query: Query<Entity, EngineViewFilter>) {
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn versus_tick_system(
+    time: Res<Time>,
+    keyboard_input: Res<Input<KeyCode>>,
+    button_input: Res<Input<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+    config: Res<VersusConfigRes>,
+    mut versus: ResMut<VersusRes>,
+) {
+    if versus.result.is_some() {
+        return;
+    }
+    // the first gamepad plays on the left board, the second one on the right board
+    let gamepads: Vec<Gamepad> = gamepads.iter().collect();
+    for (i, player) in versus.players.iter_mut().enumerate() {
+        let key_map = if config.cpu_opponent {
+            KeyMap::SINGLE_PLAYER
+        } else {
+            KEY_MAPS[i]
+        };
+        // keep the keys pressed during frames without any tick for the next tick
+        player.pending_input.0 |= key_map.read(&keyboard_input).0;
+        if let Some(gamepad) = gamepads.get(i) {
+            player.pending_input.0 |= read_gamepad(*gamepad, &button_input).0;
+        }
+    }
+    versus.accumulator =
+        (versus.accumulator + time.delta_seconds()).min(MAX_TICKS_PER_FRAME * TICK_SECONDS);
+
+    while versus.accumulator >= TICK_SECONDS && versus.result.is_none() {
+        versus.accumulator -= TICK_SECONDS;
+        versus.step(&config);
+    }
+}
+
+fn versus_render_system(
+    mut commands: Commands,
+    mut versus: ResMut<VersusRes>,
+    theme_res: Res<ThemeRes>,
+    view_query: Query<Entity, EngineViewFilter>,
+    mut meter_query: Query<(&mut Sprite, &GarbageMeter)>,
+) {
+    let mut dirty = false;
+    for player in versus.players.iter_mut() {
+        dirty |= player.engine.take_dirty();
+    }
+    if dirty {
+        for entity in &view_query {
+            commands.entity(entity).despawn_recursive();
+        }
+        for player in &versus.players {
+            spawn_engine_view(
+                &mut commands,
+                &player.engine,
+                None,
+                &player.layout,
+                &theme_res,
+            );
+        }
+    }
+
+    for (mut sprite, meter) in &mut meter_query {
+        let player = &versus.players[meter.0];
+        set_garbage_meter(&mut sprite, player.garbage_queued, &player.layout);
+    }
+}
+
+pub(crate) fn set_garbage_meter(
+    sprite: &mut Sprite,
+    garbage_queued: usize,
+    layout: &BoardLayoutRes,
+) {
+    let rows = garbage_queued.min(layout.rows);
+    sprite.custom_size = Some(Vec2::new(
+        GARBAGE_METER_WIDTH,
+        rows as f32 * layout.block_width,
+    ));
+}
+
+fn versus_info_system(
+    versus: Res<VersusRes>,
+    config: Res<VersusConfigRes>,
+    locale: Res<LocaleRes>,
+    mut sent_query: Query<(&mut Text, &SentText), Without<ResultText>>,
+    mut banner_query: Query<&mut Visibility, With<ResultBanner>>,
+    mut result_query: Query<&mut Text, With<ResultText>>,
+) {
+    for (mut text, sent) in &mut sent_query {
+        let lines = versus.players[sent.0].lines_sent;
+        text.sections[0].value = locale.format("versus-sent", &[("lines", &lines)]);
+    }
+
+    let Ok(mut visibility) = banner_query.get_single_mut() else {
+        return;
+    };
+    let Some(result) = versus.result else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    *visibility = Visibility::Visible;
+    if let Ok(mut text) = result_query.get_single_mut() {
+        let title = match result {
+            VersusResult::Winner(winner) if config.cpu_opponent && winner != CPU_PLAYER => {
+                locale.text("versus-you-win").to_string()
+            }
+            VersusResult::Winner(winner) => {
+                let player = player_name(winner, &config, &locale);
+                locale.format("versus-wins", &[("player", &player)])
+            }
+            VersusResult::Draw => locale.text("versus-draw").to_string(),
+        };
+        text.sections[0].value = format!("{}\n{}", title, locale.text("versus-rematch"));
+    }
+}
+
+fn versus_control_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    game_level: Res<GameLevelRes>,
+    screen: Res<ScreenLayoutRes>,
+    config: Res<VersusConfigRes>,
+    mut versus: ResMut<VersusRes>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        game_state.set(GameState::Menu);
+        return;
+    }
+    if versus.result.is_some() && keyboard_input.just_pressed(KeyCode::Return) {
+        *versus = VersusRes::new(game_level.0, &config, *screen);
+    }
+}